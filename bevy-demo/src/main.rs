@@ -0,0 +1,427 @@
+//! Node graph embedded into a Bevy app with `bevy_egui`.
+//!
+//! Every node of the graph is an entity with [`Operation`] and [`Output`] components,
+//! and the graph itself is a [`Snarl<Entity>`] stored in the [`Graph`] resource.
+//!
+//! The main points are:
+//!
+//! - Viewer is a [`SystemParam`] that holds queries and commands it needs.
+//!   It is passed to the system next to `ResMut<Graph>`, so `Snarl::show` can borrow
+//!   the graph mutably while the viewer accesses components of node entities.
+//! - Viewer spawns an entity when it inserts a node and despawns it when it removes one.
+//!   Entities despawned elsewhere are removed from the graph using [`RemovedComponents`].
+//! - Node values are evaluated by a regular system that reads wires from the graph.
+//! - Entities are not stable across runs, so the graph is saved as [`Snarl<Operation>`]
+//!   and entities are spawned again when it is loaded.
+
+use std::collections::{HashMap, HashSet};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_egui::{
+    egui::{self, Pos2, Ui},
+    EguiContexts, EguiPlugin,
+};
+use egui_snarl::{
+    ui::{PinInfo, SnarlStyle, SnarlViewer},
+    InPin, InPinId, NodeId, OutPin, OutPinId, Snarl,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(EguiPlugin)
+        .init_resource::<Graph>()
+        .init_resource::<SavedGraph>()
+        .add_systems(Startup, spawn_initial_graph)
+        .add_systems(Update, (forget_despawned, evaluate, graph_ui).chain())
+        .run();
+}
+
+/// Operation performed by the node.
+#[derive(Component, Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+enum Operation {
+    Constant(f64),
+    Add,
+    Multiply,
+    Display,
+}
+
+impl Operation {
+    const ALL: [Operation; 4] = [
+        Operation::Constant(0.0),
+        Operation::Add,
+        Operation::Multiply,
+        Operation::Display,
+    ];
+
+    const fn name(&self) -> &'static str {
+        match self {
+            Operation::Constant(_) => "Constant",
+            Operation::Add => "Add",
+            Operation::Multiply => "Multiply",
+            Operation::Display => "Display",
+        }
+    }
+
+    const fn inputs(&self) -> usize {
+        match self {
+            Operation::Constant(_) => 0,
+            Operation::Add | Operation::Multiply => 2,
+            Operation::Display => 1,
+        }
+    }
+
+    const fn outputs(&self) -> usize {
+        match self {
+            Operation::Display => 0,
+            _ => 1,
+        }
+    }
+
+    fn apply(&self, args: &[f64]) -> f64 {
+        match self {
+            Operation::Constant(value) => *value,
+            Operation::Add => args.iter().sum(),
+            Operation::Multiply => args.iter().product(),
+            Operation::Display => args.first().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Value of the node computed in the last evaluation.
+#[derive(Component, Clone, Copy, Default)]
+struct Output(f64);
+
+/// Graph of node entities.
+#[derive(Resource, Default)]
+struct Graph {
+    snarl: Snarl<Entity>,
+    style: SnarlStyle,
+}
+
+/// Graph saved with "Save" button.
+#[derive(Resource, Default)]
+struct SavedGraph(Option<String>);
+
+/// Viewer with access to node entities.
+///
+/// Queries are captured when the system runs,
+/// so the viewer doesn't need `&mut World` while the graph is shown.
+#[derive(SystemParam)]
+struct GraphViewer<'w, 's> {
+    commands: Commands<'w, 's>,
+    nodes: Query<'w, 's, (&'static mut Operation, &'static Output)>,
+}
+
+impl GraphViewer<'_, '_> {
+    /// Spawns node entity and inserts it into the graph.
+    fn spawn_node(&mut self, pos: Pos2, op: Operation, snarl: &mut Snarl<Entity>) -> NodeId {
+        let entity = self.commands.spawn((op, Output::default())).id();
+        snarl.insert_node(pos, entity)
+    }
+
+    /// Returns operation of the node entity.
+    ///
+    /// Entities spawned in this frame have no components until commands are applied,
+    /// so `None` is returned for them.
+    fn operation(&self, entity: Entity) -> Option<Operation> {
+        self.nodes.get(entity).ok().map(|(op, _)| *op)
+    }
+
+    /// Returns value of the output connected to the input pin.
+    fn input_value(&self, pin: &InPin, snarl: &Snarl<Entity>) -> Option<f64> {
+        let remote = pin.remotes.first()?;
+        let (_, output) = self.nodes.get(snarl[remote.node]).ok()?;
+        Some(output.0)
+    }
+
+    /// Converts graph of entities into graph of operations that can be serialized.
+    fn save(&self, snarl: &Snarl<Entity>) -> Snarl<Operation> {
+        let mut saved = Snarl::new();
+        let mut ids = HashMap::new();
+
+        for (id, pos, &entity) in snarl.nodes_pos_ids() {
+            if let Some(op) = self.operation(entity) {
+                ids.insert(id, saved.insert_node(pos, op));
+            }
+        }
+
+        for (from, to) in snarl.wires() {
+            if let (Some(&from_node), Some(&to_node)) = (ids.get(&from.node), ids.get(&to.node)) {
+                saved.connect(
+                    OutPinId {
+                        node: from_node,
+                        output: from.output,
+                    },
+                    InPinId {
+                        node: to_node,
+                        input: to.input,
+                    },
+                );
+            }
+        }
+
+        saved
+    }
+
+    /// Despawns current node entities and spawns entities for the saved graph.
+    fn load(&mut self, saved: &Snarl<Operation>, snarl: &mut Snarl<Entity>) {
+        for (_, &entity) in snarl.node_ids() {
+            self.commands.entity(entity).despawn();
+        }
+        snarl.clear();
+
+        let mut ids = HashMap::new();
+        for (id, pos, &op) in saved.nodes_pos_ids() {
+            ids.insert(id, self.spawn_node(pos, op, snarl));
+        }
+
+        for (from, to) in saved.wires() {
+            snarl.connect(
+                OutPinId {
+                    node: ids[&from.node],
+                    output: from.output,
+                },
+                InPinId {
+                    node: ids[&to.node],
+                    input: to.input,
+                },
+            );
+        }
+    }
+}
+
+impl SnarlViewer<Entity> for GraphViewer<'_, '_> {
+    fn title(&mut self, node: &Entity) -> String {
+        self.operation(*node)
+            .map_or("Spawning...", |op| op.name())
+            .to_owned()
+    }
+
+    fn inputs(&mut self, node: &Entity) -> usize {
+        self.operation(*node).map_or(0, |op| op.inputs())
+    }
+
+    fn outputs(&mut self, node: &Entity) -> usize {
+        self.operation(*node).map_or(0, |op| op.outputs())
+    }
+
+    fn show_input(
+        &mut self,
+        pin: &InPin,
+        ui: &mut Ui,
+        _scale: f32,
+        snarl: &mut Snarl<Entity>,
+    ) -> PinInfo {
+        match self.input_value(pin, snarl) {
+            Some(value) => ui.label(format!("{value:.2}")),
+            None => ui.weak("none"),
+        };
+        PinInfo::circle().with_fill(egui::Color32::from_rgb(0xb0, 0x70, 0x30))
+    }
+
+    fn show_output(
+        &mut self,
+        pin: &OutPin,
+        ui: &mut Ui,
+        _scale: f32,
+        snarl: &mut Snarl<Entity>,
+    ) -> PinInfo {
+        if let Ok((mut op, output)) = self.nodes.get_mut(snarl[pin.id.node]) {
+            match &mut *op {
+                Operation::Constant(value) => {
+                    ui.add(egui::DragValue::new(value).speed(0.1));
+                }
+                _ => {
+                    ui.label(format!("{:.2}", output.0));
+                }
+            }
+        }
+        PinInfo::circle().with_fill(egui::Color32::from_rgb(0xb0, 0x70, 0x30))
+    }
+
+    fn connect(&mut self, from: &OutPin, to: &InPin, snarl: &mut Snarl<Entity>) {
+        // Each input takes a single value.
+        snarl.drop_inputs(to.id);
+        snarl.connect(from.id, to.id);
+    }
+
+    fn has_graph_menu(&mut self, _pos: Pos2, _snarl: &mut Snarl<Entity>) -> bool {
+        true
+    }
+
+    fn show_graph_menu(&mut self, pos: Pos2, ui: &mut Ui, _scale: f32, snarl: &mut Snarl<Entity>) {
+        ui.label("Add node");
+        for op in Operation::ALL {
+            if ui.button(op.name()).clicked() {
+                self.spawn_node(pos, op, snarl);
+                ui.close_menu();
+            }
+        }
+    }
+
+    fn has_node_menu(&mut self, _node: &Entity) -> bool {
+        true
+    }
+
+    fn show_node_menu(
+        &mut self,
+        node: NodeId,
+        _inputs: &[InPin],
+        _outputs: &[OutPin],
+        ui: &mut Ui,
+        _scale: f32,
+        snarl: &mut Snarl<Entity>,
+    ) {
+        if ui.button("Remove").clicked() {
+            // Node and its entity are removed together.
+            let entity = snarl.remove_node(node);
+            self.commands.entity(entity).despawn();
+            ui.close_menu();
+        }
+    }
+}
+
+fn spawn_initial_graph(mut graph: ResMut<Graph>, mut commands: Commands) {
+    let mut spawn = |pos: Pos2, op: Operation| {
+        let entity = commands.spawn((op, Output::default())).id();
+        graph.snarl.insert_node(pos, entity)
+    };
+
+    let a = spawn(egui::pos2(0.0, 0.0), Operation::Constant(2.0));
+    let b = spawn(egui::pos2(0.0, 100.0), Operation::Constant(3.0));
+    let sum = spawn(egui::pos2(200.0, 50.0), Operation::Add);
+    let display = spawn(egui::pos2(400.0, 50.0), Operation::Display);
+
+    for (from, to, input) in [(a, sum, 0), (b, sum, 1), (sum, display, 0)] {
+        graph.snarl.connect(
+            OutPinId {
+                node: from,
+                output: 0,
+            },
+            InPinId { node: to, input },
+        );
+    }
+}
+
+/// Removes nodes whose entities were despawned outside of the graph UI.
+fn forget_despawned(mut removed: RemovedComponents<Operation>, mut graph: ResMut<Graph>) {
+    let removed = removed.read().collect::<HashSet<_>>();
+    if removed.is_empty() {
+        return;
+    }
+
+    let stale = graph
+        .snarl
+        .node_ids()
+        .filter(|(_, entity)| removed.contains(entity))
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+
+    for id in stale {
+        graph.snarl.remove_node(id);
+    }
+}
+
+/// Computes values of all nodes following wires of the graph.
+fn evaluate(graph: Res<Graph>, mut nodes: Query<(&Operation, &mut Output)>) {
+    let snarl = &graph.snarl;
+
+    let ops = snarl
+        .node_ids()
+        .filter_map(|(id, &entity)| Some((id, *nodes.get(entity).ok()?.0)))
+        .collect::<HashMap<_, _>>();
+
+    let mut values = HashMap::new();
+    for &id in ops.keys() {
+        evaluate_node(id, snarl, &ops, &mut values, &mut HashSet::new());
+    }
+
+    for (id, value) in values {
+        if let Ok((_, mut output)) = nodes.get_mut(snarl[id]) {
+            output.0 = value;
+        }
+    }
+}
+
+fn evaluate_node(
+    id: NodeId,
+    snarl: &Snarl<Entity>,
+    ops: &HashMap<NodeId, Operation>,
+    values: &mut HashMap<NodeId, f64>,
+    visiting: &mut HashSet<NodeId>,
+) -> f64 {
+    if let Some(&value) = values.get(&id) {
+        return value;
+    }
+
+    // Nodes in a cycle read zero from each other.
+    let Some(op) = ops.get(&id) else {
+        return 0.0;
+    };
+    if !visiting.insert(id) {
+        return 0.0;
+    }
+
+    let mut args = vec![0.0; op.inputs()];
+    for (from, to) in snarl.incoming_wires(id) {
+        if let Some(arg) = args.get_mut(to.input) {
+            *arg = evaluate_node(from.node, snarl, ops, values, visiting);
+        }
+    }
+
+    visiting.remove(&id);
+    let value = op.apply(&args);
+    values.insert(id, value);
+    value
+}
+
+fn graph_ui(
+    mut contexts: EguiContexts,
+    mut graph: ResMut<Graph>,
+    mut saved: ResMut<SavedGraph>,
+    mut viewer: GraphViewer,
+) {
+    let ctx = contexts.ctx_mut();
+    let Graph { snarl, style } = &mut *graph;
+    let mut despawn_selected = false;
+
+    egui::TopBottomPanel::top("menu").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            if ui.button("Save").clicked() {
+                saved.0 = serde_json::to_string(&viewer.save(snarl)).ok();
+            }
+
+            let saved_graph = saved
+                .0
+                .as_deref()
+                .and_then(|json| serde_json::from_str::<Snarl<Operation>>(json).ok());
+
+            if ui
+                .add_enabled(saved_graph.is_some(), egui::Button::new("Load"))
+                .clicked()
+            {
+                if let Some(saved_graph) = saved_graph {
+                    viewer.load(&saved_graph, snarl);
+                }
+            }
+
+            despawn_selected = ui.button("Despawn selected").clicked();
+        });
+    });
+
+    egui::CentralPanel::default().show(ctx, |ui| {
+        if despawn_selected {
+            // Selection is stored under the id of the `Ui` the graph is shown in.
+            // Only entities are despawned here,
+            // nodes are removed from the graph by `forget_despawned` system.
+            for node in Snarl::<Entity>::get_selected_nodes("graph", ui) {
+                if let Some(&entity) = snarl.get_node(node) {
+                    viewer.commands.entity(entity).despawn();
+                }
+            }
+        }
+
+        snarl.show(&mut viewer, style, "graph", ui);
+    });
+}
@@ -5,7 +5,10 @@ use std::collections::HashMap;
 use eframe::{App, CreationContext};
 use egui::{Color32, Id, Ui};
 use egui_snarl::{
-    ui::{AnyPins, NodeLayout, PinInfo, PinPlacement, SnarlStyle, SnarlViewer, WireStyle},
+    ui::{
+        AnyPin, AnyPins, NodeActivity, NodeIcon, NodeLayout, PinInfo, PinPlacement, SnarlStyle,
+        SnarlViewer, WireInfo, WireStyle,
+    },
     InPin, InPinId, NodeId, OutPin, OutPinId, Snarl,
 };
 
@@ -14,6 +17,9 @@ const NUMBER_COLOR: Color32 = Color32::from_rgb(0xb0, 0x00, 0x00);
 const IMAGE_COLOR: Color32 = Color32::from_rgb(0xb0, 0x00, 0xb0);
 const UNTYPED_COLOR: Color32 = Color32::from_rgb(0xb0, 0xb0, 0xb0);
 
+/// Expression inputs are packed tightly, so their wires curve within a smaller frame.
+const EXPR_WIRE_FRAME_SIZE: f32 = 10.0;
+
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 enum DemoNode {
     /// Node with single input.
@@ -92,7 +98,17 @@ impl DemoNode {
     }
 }
 
-struct DemoViewer;
+#[derive(Default)]
+struct DemoViewer {
+    /// Output pins which values were changed this frame.
+    changed: Vec<OutPinId>,
+
+    /// Time of the frame, used to fake image loading progress.
+    time: f64,
+
+    /// Nodes with names not containing this text are ghosted.
+    filter: String,
+}
 
 impl SnarlViewer<DemoNode> for DemoViewer {
     #[inline]
@@ -137,8 +153,13 @@ impl SnarlViewer<DemoNode> for DemoViewer {
             }
         }
 
-        for &remote in &to.remotes {
-            snarl.disconnect(remote, to.id);
+        if snarl.connected(from.id, to.id) {
+            return;
+        }
+
+        // Inputs accept single wire.
+        if snarl.in_pin_degree(to.id) > 0 {
+            snarl.drop_inputs(to.id);
         }
 
         snarl.connect(from.id, to.id);
@@ -154,6 +175,22 @@ impl SnarlViewer<DemoNode> for DemoViewer {
         }
     }
 
+    fn node_opacity(&mut self, node: &DemoNode) -> f32 {
+        let filter = self.filter.trim().to_lowercase();
+        if filter.is_empty() || node.name().to_lowercase().contains(&filter) {
+            1.0
+        } else {
+            0.3
+        }
+    }
+
+    fn node_icon(&mut self, node: &DemoNode) -> Option<NodeIcon> {
+        match node {
+            DemoNode::ShowImage(_) => Some(NodeIcon::glyph("🖼", 14.0)),
+            _ => None,
+        }
+    }
+
     fn inputs(&mut self, node: &DemoNode) -> usize {
         match node {
             DemoNode::Sink | DemoNode::ShowImage(_) => 1,
@@ -301,7 +338,10 @@ impl SnarlViewer<DemoNode> for DemoViewer {
                 if changed {
                     let expr_node = snarl[pin.id.node].expr_node();
 
-                    if let Ok(expr) = syn::parse_str(&expr_node.text) {
+                    let parsed = syn::parse_str(&expr_node.text);
+                    expr_node.invalid = parsed.is_err();
+
+                    if let Ok(expr) = parsed {
                         expr_node.expr = expr;
 
                         let values = Iterator::zip(
@@ -323,36 +363,16 @@ impl SnarlViewer<DemoNode> for DemoViewer {
 
                         expr_node.values = new_values;
 
-                        let old_inputs = (0..old_bindings.len())
-                            .map(|idx| {
-                                snarl.in_pin(InPinId {
-                                    node: pin.id.node,
-                                    input: idx + 1,
-                                })
-                            })
-                            .collect::<Vec<_>>();
-
-                        for (idx, name) in old_bindings.iter().enumerate() {
+                        // Input 0 is the expression itself, bindings follow it.
+                        snarl.remap_in_pins(pin.id.node, |input| {
+                            let Some(idx) = input.checked_sub(1) else {
+                                return Some(0);
+                            };
+                            let name = old_bindings.get(idx)?;
                             let new_idx =
-                                new_bindings.iter().position(|new_name| *new_name == *name);
-
-                            match new_idx {
-                                None => {
-                                    snarl.drop_inputs(old_inputs[idx].id);
-                                }
-                                Some(new_idx) if new_idx != idx => {
-                                    let new_in_pin = InPinId {
-                                        node: pin.id.node,
-                                        input: new_idx,
-                                    };
-                                    for &remote in &old_inputs[idx].remotes {
-                                        snarl.disconnect(remote, old_inputs[idx].id);
-                                        snarl.connect(remote, new_in_pin);
-                                    }
-                                }
-                                _ => {}
-                            }
-                        }
+                                new_bindings.iter().position(|new_name| new_name == name)?;
+                            Some(new_idx + 1)
+                        });
                     }
                 }
                 PinInfo::circle()
@@ -368,7 +388,9 @@ impl SnarlViewer<DemoNode> for DemoViewer {
                             let node = &mut snarl[pin.id.node];
                             ui.label(node.label_in(pin.id.input));
                             ui.add(egui::DragValue::new(node.number_in(pin.id.input)));
-                            PinInfo::circle().with_fill(NUMBER_COLOR)
+                            PinInfo::circle()
+                                .with_fill(NUMBER_COLOR)
+                                .with_wire_frame_size(EXPR_WIRE_FRAME_SIZE)
                         }
                         [remote] => {
                             let new_value = snarl[remote.node].number_out();
@@ -376,7 +398,9 @@ impl SnarlViewer<DemoNode> for DemoViewer {
                             ui.label(node.label_in(pin.id.input));
                             ui.label(format_float(new_value));
                             *node.number_in(pin.id.input) = new_value;
-                            PinInfo::circle().with_fill(NUMBER_COLOR)
+                            PinInfo::circle()
+                                .with_fill(NUMBER_COLOR)
+                                .with_wire_frame_size(EXPR_WIRE_FRAME_SIZE)
                         }
                         _ => unreachable!("Expr pins has only one wire"),
                     }
@@ -401,7 +425,9 @@ impl SnarlViewer<DemoNode> for DemoViewer {
             }
             DemoNode::Number(ref mut value) => {
                 assert_eq!(pin.id.output, 0, "Number node has only one output");
-                ui.add(egui::DragValue::new(value));
+                if ui.add(egui::DragValue::new(value)).changed() {
+                    self.changed.push(pin.id);
+                }
                 PinInfo::circle().with_fill(NUMBER_COLOR)
             }
             DemoNode::String(ref mut value) => {
@@ -430,6 +456,41 @@ impl SnarlViewer<DemoNode> for DemoViewer {
         }
     }
 
+    fn node_progress(&mut self, node: &DemoNode) -> Option<NodeActivity> {
+        match node {
+            // Waiting for URL.
+            DemoNode::ShowImage(uri) if uri.is_empty() => Some(NodeActivity::Spinner),
+            // Fake loading progress that restarts every 3 seconds.
+            #[allow(clippy::cast_possible_truncation)]
+            DemoNode::ShowImage(_) => {
+                Some(NodeActivity::Progress((self.time / 3.0).fract() as f32))
+            }
+            _ => None,
+        }
+    }
+
+    fn wire_pulse(&mut self, from: &OutPinId, _to: &InPinId, _snarl: &Snarl<DemoNode>) -> bool {
+        self.changed.contains(from)
+    }
+
+    fn wire_style(
+        &mut self,
+        _from: &OutPinId,
+        to: &InPinId,
+        default: WireInfo,
+        snarl: &Snarl<DemoNode>,
+    ) -> WireInfo {
+        // Highlight wires into expressions that fail to parse.
+        match snarl.get_node(to.node) {
+            Some(DemoNode::ExprNode(expr_node)) if expr_node.invalid => WireInfo {
+                color: Color32::RED,
+                end_color: None,
+                ..default
+            },
+            _ => default,
+        }
+    }
+
     fn has_graph_menu(&mut self, _pos: egui::Pos2, _snarl: &mut Snarl<DemoNode>) -> bool {
         true
     }
@@ -515,12 +576,28 @@ impl SnarlViewer<DemoNode> for DemoViewer {
         ui.label("Add node");
 
         match src_pins {
+            AnyPins::Out(src_pins) if src_pins.len() > 1 => {
+                // Several wires are dropped at once.
+                // Sum them up with a new expression node that has an input for each wire.
+                let all_num = src_pins
+                    .iter()
+                    .all(|pin| pin_out_compat(snarl.get_node(pin.node).unwrap()) == PIN_NUM);
+
+                if all_num && ui.button("Sum").clicked() {
+                    let new_node =
+                        snarl.insert_node(pos, DemoNode::ExprNode(ExprNode::sum(src_pins.len())));
+
+                    for (idx, src_pin) in src_pins.iter().enumerate() {
+                        let dst_pin = InPinId {
+                            node: new_node,
+                            input: idx + 1,
+                        };
+                        snarl.connect(*src_pin, dst_pin);
+                    }
+                    ui.close_menu();
+                }
+            }
             AnyPins::Out(src_pins) => {
-                assert!(
-                    src_pins.len() == 1,
-                    "There's no concept of multi-input nodes in this demo"
-                );
-
                 let src_pin = src_pins[0];
                 let src_out_ty = pin_out_compat(snarl.get_node(src_pin.node).unwrap());
                 let dst_in_candidates = [
@@ -594,15 +671,36 @@ impl SnarlViewer<DemoNode> for DemoViewer {
         true
     }
 
-    fn show_node_menu(
+    fn show_node_menu_for_pin(
         &mut self,
         node: NodeId,
+        clicked_pin: Option<AnyPin>,
         _inputs: &[InPin],
         _outputs: &[OutPin],
         ui: &mut Ui,
         _scale: f32,
         snarl: &mut Snarl<DemoNode>,
     ) {
+        match clicked_pin {
+            Some(AnyPin::In(pin)) => {
+                ui.label(format!("Input {}", pin.input));
+                if ui.button("Disconnect").clicked() {
+                    snarl.drop_inputs(pin);
+                    ui.close_menu();
+                }
+                ui.separator();
+            }
+            Some(AnyPin::Out(pin)) => {
+                ui.label(format!("Output {}", pin.output));
+                if ui.button("Disconnect").clicked() {
+                    snarl.drop_outputs(pin);
+                    ui.close_menu();
+                }
+                ui.separator();
+            }
+            None => {}
+        }
+
         ui.label("Node menu");
         if ui.button("Remove").clicked() {
             snarl.remove_node(node);
@@ -666,6 +764,10 @@ struct ExprNode {
     bindings: Vec<String>,
     values: Vec<f64>,
     expr: Expr,
+
+    /// Text failed to parse last time it was changed.
+    #[serde(default)]
+    invalid: bool,
 }
 
 impl ExprNode {
@@ -675,6 +777,30 @@ impl ExprNode {
             bindings: Vec::new(),
             values: Vec::new(),
             expr: Expr::Val(0.0),
+            invalid: false,
+        }
+    }
+
+    /// Expression that sums `count` variables.
+    fn sum(count: usize) -> Self {
+        let bindings = (0..count).map(|idx| format!("x{idx}")).collect::<Vec<_>>();
+
+        let expr = bindings
+            .iter()
+            .map(|name| Expr::Var(name.clone()))
+            .reduce(|lhs, rhs| Expr::BinOp {
+                lhs: Box::new(lhs),
+                op: BinOp::Add,
+                rhs: Box::new(rhs),
+            })
+            .unwrap_or(Expr::Val(0.0));
+
+        ExprNode {
+            text: bindings.join(" + "),
+            values: vec![0.0; count],
+            bindings,
+            expr,
+            invalid: false,
         }
     }
 
@@ -952,13 +1078,23 @@ pub struct DemoApp {
     snarl: Snarl<DemoNode>,
     style: SnarlStyle,
     snarl_ui_id: Option<Id>,
+    filter: String,
+    /// Wire style replaced by "Sagging wires" toggle, restored when it is unchecked.
+    unsagged_wire_style: Option<WireStyle>,
 }
 
-const fn default_style() -> SnarlStyle {
+fn default_style() -> SnarlStyle {
     SnarlStyle {
         node_layout: Some(NodeLayout::FlippedSandwich),
         pin_placement: Some(PinPlacement::Edge),
         pin_size: Some(7.0),
+        collapsed_pin_stubs: Some(true),
+        min_interactive_opacity: Some(0.5),
+        delete_selected_shortcut: Some(egui::KeyboardShortcut::new(
+            egui::Modifiers::NONE,
+            egui::Key::Delete,
+        )),
+        detach_wire_modifiers: Some(egui::Modifiers::ALT),
         node_frame: Some(egui::Frame {
             inner_margin: egui::Margin::same(8.0),
             outer_margin: egui::Margin {
@@ -984,17 +1120,56 @@ const fn default_style() -> SnarlStyle {
     }
 }
 
+/// Initial graph with collapsed nodes connected with each other.
+fn default_snarl() -> Snarl<DemoNode> {
+    let mut snarl = Snarl::new();
+
+    let number = snarl.insert_node_collapsed(egui::pos2(0.0, 0.0), DemoNode::Number(42.0));
+    let sink = snarl.insert_node(egui::pos2(200.0, 0.0), DemoNode::Sink);
+
+    snarl.connect(
+        OutPinId {
+            node: number,
+            output: 0,
+        },
+        InPinId {
+            node: sink,
+            input: 0,
+        },
+    );
+
+    let uri = "https://www.rust-lang.org/logos/rust-logo-128x128.png";
+    let string = snarl.insert_node(egui::pos2(0.0, 150.0), DemoNode::String(uri.to_owned()));
+    let image = snarl.insert_node_collapsed(
+        egui::pos2(200.0, 150.0),
+        DemoNode::ShowImage(uri.to_owned()),
+    );
+
+    snarl.connect(
+        OutPinId {
+            node: string,
+            output: 0,
+        },
+        InPinId {
+            node: image,
+            input: 0,
+        },
+    );
+
+    snarl
+}
+
 impl DemoApp {
     pub fn new(cx: &CreationContext) -> Self {
         egui_extras::install_image_loaders(&cx.egui_ctx);
 
         cx.egui_ctx.style_mut(|style| style.animation_time *= 10.0);
 
-        let snarl = cx.storage.map_or_else(Snarl::new, |storage| {
+        let snarl = cx.storage.map_or_else(default_snarl, |storage| {
             storage
                 .get_string("snarl")
                 .and_then(|snarl| serde_json::from_str(&snarl).ok())
-                .unwrap_or_default()
+                .unwrap_or_else(default_snarl)
         });
         // let snarl = Snarl::new();
 
@@ -1010,6 +1185,8 @@ impl DemoApp {
             snarl,
             style,
             snarl_ui_id: None,
+            filter: String::new(),
+            unsagged_wire_style: None,
         }
     }
 }
@@ -1035,6 +1212,22 @@ impl App for DemoApp {
                 if ui.button("Clear All").clicked() {
                     self.snarl = Snarl::default();
                 }
+
+                let mut sagging = matches!(self.style.wire_style, Some(WireStyle::Sagging { .. }));
+                if ui.checkbox(&mut sagging, "Sagging wires").changed() {
+                    if sagging {
+                        self.unsagged_wire_style = self.style.wire_style;
+                        self.style.wire_style = Some(WireStyle::Sagging { sag: 0.2 });
+                    } else {
+                        self.style.wire_style = self.unsagged_wire_style.take();
+                    }
+                }
+
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.filter)
+                        .hint_text("Filter nodes")
+                        .desired_width(120.0),
+                );
             });
         });
 
@@ -1051,9 +1244,10 @@ impl App for DemoApp {
 
                     let selected =
                         Snarl::<DemoNode>::get_selected_nodes_at("snarl", snarl_ui_id, ui.ctx());
-                    let mut selected = selected
-                        .into_iter()
-                        .map(|id| (id, &self.snarl[id]))
+                    let mut selected = self
+                        .snarl
+                        .node_infos(selected)
+                        .filter_map(|(id, info)| Some((id, &info?.value)))
                         .collect::<Vec<_>>();
 
                     selected.sort_by_key(|(id, _)| *id);
@@ -1081,7 +1275,25 @@ impl App for DemoApp {
         egui::CentralPanel::default().show(ctx, |ui| {
             self.snarl_ui_id = Some(ui.id());
 
-            self.snarl.show(&mut DemoViewer, &self.style, "snarl", ui);
+            // Keep fake image loading progress moving.
+            if self
+                .snarl
+                .nodes()
+                .any(|node| matches!(node, DemoNode::ShowImage(_)))
+            {
+                ui.ctx().request_repaint();
+            }
+
+            self.snarl.show(
+                &mut DemoViewer {
+                    time: ui.input(|i| i.time),
+                    filter: self.filter.clone(),
+                    ..DemoViewer::default()
+                },
+                &self.style,
+                "snarl",
+                ui,
+            );
         });
     }
 
@@ -0,0 +1,143 @@
+//! Comparing two graphs.
+
+use emath::Vec2;
+
+use crate::{InPinId, NodeId, OutPinId, Snarl};
+
+/// Differences between two graphs returned by [`Snarl::diff`].
+///
+/// All lists are sorted by node ids and pin indices.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnarlDiff {
+    /// Nodes present only in the new graph.
+    pub added_nodes: Vec<NodeId>,
+
+    /// Nodes present only in the old graph.
+    pub removed_nodes: Vec<NodeId>,
+
+    /// Nodes present in both graphs with different positions,
+    /// along with position delta from old to new graph.
+    pub moved_nodes: Vec<(NodeId, Vec2)>,
+
+    /// Nodes present in both graphs with different values.
+    pub changed_nodes: Vec<NodeId>,
+
+    /// Wires present only in the new graph.
+    pub added_wires: Vec<(OutPinId, InPinId)>,
+
+    /// Wires present only in the old graph.
+    pub removed_wires: Vec<(OutPinId, InPinId)>,
+}
+
+impl SnarlDiff {
+    /// Nodes that moved by this distance or less are not reported as moved.
+    pub const MOVE_EPSILON: f32 = 0.001;
+
+    /// Returns true if graphs are equal.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns total number of differences.
+    /// A node that both moved and changed is counted twice.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.node_changes() + self.wire_changes()
+    }
+
+    /// Returns number of added, removed, moved and changed nodes.
+    #[must_use]
+    pub fn node_changes(&self) -> usize {
+        self.added_nodes.len()
+            + self.removed_nodes.len()
+            + self.moved_nodes.len()
+            + self.changed_nodes.len()
+    }
+
+    /// Returns number of added and removed wires.
+    #[must_use]
+    pub fn wire_changes(&self) -> usize {
+        self.added_wires.len() + self.removed_wires.len()
+    }
+}
+
+impl<T> Snarl<T> {
+    /// Compares this graph with `other` one.
+    ///
+    /// This graph is treated as the old one and `other` as the new one.
+    /// Node values are compared with `value_eq`, so `T` doesn't need to implement [`PartialEq`].
+    /// Changes of node's open state are not reported.
+    ///
+    /// Nodes are matched by [`NodeId`].
+    /// Identifiers of removed nodes are reused by nodes inserted later,
+    /// so a node removed and another one inserted between the two graphs
+    /// may be reported as moved and changed node instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut old = Snarl::new();
+    /// let a = old.insert_node(pos2(0.0, 0.0), 1);
+    /// let b = old.insert_node(pos2(100.0, 0.0), 2);
+    ///
+    /// let mut new = old.clone();
+    /// new.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    /// *new.get_node_mut(b).unwrap() = 3;
+    ///
+    /// let diff = old.diff(&new, |x, y| x == y);
+    /// assert_eq!(diff.changed_nodes, [b]);
+    /// assert_eq!(diff.wire_changes(), 1);
+    /// assert!(old.diff(&old, |x, y| x == y).is_empty());
+    /// ```
+    #[must_use]
+    pub fn diff<F>(&self, other: &Snarl<T>, mut value_eq: F) -> SnarlDiff
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        let mut diff = SnarlDiff::default();
+
+        for (idx, node) in &self.nodes {
+            let id = NodeId(idx);
+            match other.nodes.get(idx) {
+                None => diff.removed_nodes.push(id),
+                Some(other_node) => {
+                    let delta = other_node.pos - node.pos;
+                    if delta.length() > SnarlDiff::MOVE_EPSILON {
+                        diff.moved_nodes.push((id, delta));
+                    }
+                    if !value_eq(&node.value, &other_node.value) {
+                        diff.changed_nodes.push(id);
+                    }
+                }
+            }
+        }
+
+        for (idx, _) in &other.nodes {
+            if !self.nodes.contains(idx) {
+                diff.added_nodes.push(NodeId(idx));
+            }
+        }
+
+        diff.added_wires = other
+            .wires
+            .iter()
+            .filter(|wire| !self.wires.wires.contains(wire))
+            .map(|wire| (wire.out_pin, wire.in_pin))
+            .collect();
+        diff.added_wires.sort_unstable();
+
+        diff.removed_wires = self
+            .wires
+            .iter()
+            .filter(|wire| !other.wires.wires.contains(wire))
+            .map(|wire| (wire.out_pin, wire.in_pin))
+            .collect();
+        diff.removed_wires.sort_unstable();
+
+        diff
+    }
+}
@@ -3,6 +3,13 @@
 //!
 //! Provides a node-graph container for egui.
 //!
+//! # Features
+//!
+//! - `ui` (default) - [`ui`] module to show graphs with egui.
+//!   Without it the crate doesn't depend on egui and can be used to store,
+//!   transform and evaluate graphs headless.
+//! - `serde` - serialization of graphs.
+//!   Serialized graphs are the same with and without `ui` feature.
 //!
 
 #![deny(missing_docs)]
@@ -10,11 +17,32 @@
 // #![warn(clippy::pedantic)]
 #![allow(clippy::inline_always, clippy::use_self)]
 
+#[cfg(feature = "ui")]
 pub mod ui;
 
-use std::ops::{Index, IndexMut};
-
-use egui::{ahash::HashSet, Pos2};
+mod diff;
+mod dot;
+mod limits;
+mod subgraph;
+mod traverse;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::{
+    diff::SnarlDiff,
+    limits::{DeserializeError, LimitExceeded, SnarlCounts, SnarlLimits},
+    traverse::Cycle,
+};
+
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+use ahash::HashSet;
+use emath::Pos2;
 use slab::Slab;
 
 impl<T> Default for Snarl<T> {
@@ -46,7 +74,7 @@ pub struct Node<T> {
 
     /// Position of the top-left corner of the node.
     /// This does not include frame margin.
-    pub pos: egui::Pos2,
+    pub pos: emath::Pos2,
 
     /// Flag indicating that the node is open - not collapsed.
     pub open: bool,
@@ -75,12 +103,44 @@ pub struct InPinId {
     pub input: usize,
 }
 
+/// Error returned by fallible methods that connect or disconnect pins,
+/// e.g. [`Snarl::try_connect`], when node of either pin does not exist.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConnectError {
+    /// Node of the output pin does not exist.
+    MissingSource {
+        /// Output pin of the missing node.
+        pin: OutPinId,
+    },
+
+    /// Node of the input pin does not exist.
+    MissingTarget {
+        /// Input pin of the missing node.
+        pin: InPinId,
+    },
+}
+
+impl fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ConnectError::MissingSource { pin } => {
+                write!(f, "source node {} does not exist", pin.node.0)
+            }
+            ConnectError::MissingTarget { pin } => {
+                write!(f, "target node {} does not exist", pin.node.0)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConnectError {}
+
 /// Connection between two nodes.
 ///
 /// Nodes may support multiple connections to the same input or output.
 /// But duplicate connections between same input and the same output are not allowed.
 /// Attempt to insert existing connection will be ignored.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Wire {
     out_pin: OutPinId,
@@ -100,9 +160,13 @@ impl serde::Serialize for Wires {
     {
         use serde::ser::SerializeSeq;
 
-        let mut seq = serializer.serialize_seq(Some(self.wires.len()))?;
-        for wire in &self.wires {
-            seq.serialize_element(&wire)?;
+        // Wires are sorted, so that same graph is always serialized the same way.
+        let mut wires = self.wires.iter().collect::<Vec<_>>();
+        wires.sort_unstable();
+
+        let mut seq = serializer.serialize_seq(Some(wires.len()))?;
+        for wire in wires {
+            seq.serialize_element(wire)?;
         }
         seq.end()
     }
@@ -127,7 +191,7 @@ impl<'de> serde::Deserialize<'de> for Wires {
             where
                 A: serde::de::SeqAccess<'de>,
             {
-                let mut wires = HashSet::with_hasher(egui::ahash::RandomState::new());
+                let mut wires = HashSet::with_hasher(ahash::RandomState::new());
                 while let Some(wire) = seq.next_element()? {
                     wires.insert(wire);
                 }
@@ -143,7 +207,7 @@ impl<'de> serde::Deserialize<'de> for Wires {
 impl Wires {
     fn new() -> Self {
         Wires {
-            wires: HashSet::with_hasher(egui::ahash::RandomState::new()),
+            wires: HashSet::with_hasher(ahash::RandomState::new()),
         }
     }
 
@@ -155,6 +219,10 @@ impl Wires {
         self.wires.remove(wire)
     }
 
+    fn contains(&self, wire: &Wire) -> bool {
+        self.wires.contains(wire)
+    }
+
     fn drop_node(&mut self, node: NodeId) -> usize {
         let count = self.wires.len();
         self.wires
@@ -162,6 +230,12 @@ impl Wires {
         count - self.wires.len()
     }
 
+    fn retain(&mut self, mut f: impl FnMut(OutPinId, InPinId) -> bool) -> usize {
+        let count = self.wires.len();
+        self.wires.retain(|wire| f(wire.out_pin, wire.in_pin));
+        count - self.wires.len()
+    }
+
     fn drop_inputs(&mut self, pin: InPinId) -> usize {
         let count = self.wires.len();
         self.wires.retain(|wire| wire.in_pin != pin);
@@ -174,29 +248,123 @@ impl Wires {
         count - self.wires.len()
     }
 
+    /// Removes wires for which `f` returns true and returns them.
+    fn take(&mut self, mut f: impl FnMut(&Wire) -> bool) -> Vec<Wire> {
+        let mut taken = Vec::new();
+        self.wires.retain(|wire| {
+            if f(wire) {
+                taken.push(*wire);
+                false
+            } else {
+                true
+            }
+        });
+        taken
+    }
+
+    /// Moves wires connected to inputs of the node to other inputs
+    /// or removes them if `f` returns `None`.
+    /// Wires that become equal are merged.
+    fn remap_inputs(&mut self, node: NodeId, mut f: impl FnMut(usize) -> Option<usize>) {
+        for mut wire in self.take(|wire| wire.in_pin.node == node) {
+            if let Some(input) = f(wire.in_pin.input) {
+                wire.in_pin.input = input;
+                self.wires.insert(wire);
+            }
+        }
+    }
+
+    /// Moves wires connected to outputs of the node to other outputs
+    /// or removes them if `f` returns `None`.
+    /// Wires that become equal are merged.
+    fn remap_outputs(&mut self, node: NodeId, mut f: impl FnMut(usize) -> Option<usize>) {
+        for mut wire in self.take(|wire| wire.out_pin.node == node) {
+            if let Some(output) = f(wire.out_pin.output) {
+                wire.out_pin.output = output;
+                self.wires.insert(wire);
+            }
+        }
+    }
+
     fn wired_inputs(&self, out_pin: OutPinId) -> impl Iterator<Item = InPinId> + '_ {
         self.wires
             .iter()
             .filter(move |wire| wire.out_pin == out_pin)
-            .map(|wire| (wire.in_pin))
+            .map(|wire| wire.in_pin)
     }
 
     fn wired_outputs(&self, in_pin: InPinId) -> impl Iterator<Item = OutPinId> + '_ {
         self.wires
             .iter()
             .filter(move |wire| wire.in_pin == in_pin)
-            .map(|wire| (wire.out_pin))
+            .map(|wire| wire.out_pin)
     }
 
     fn iter(&self) -> impl Iterator<Item = Wire> + '_ {
         self.wires.iter().copied()
     }
+
+    /// Collects remotes of first `inputs` inputs and `outputs` outputs of the node
+    /// in a single pass over wires.
+    #[cfg(feature = "ui")]
+    fn node_remotes(
+        &self,
+        node: NodeId,
+        inputs: usize,
+        outputs: usize,
+    ) -> (Vec<Vec<OutPinId>>, Vec<Vec<InPinId>>) {
+        let mut in_remotes = vec![Vec::new(); inputs];
+        let mut out_remotes = vec![Vec::new(); outputs];
+
+        for wire in &self.wires {
+            if wire.in_pin.node == node {
+                if let Some(remotes) = in_remotes.get_mut(wire.in_pin.input) {
+                    remotes.push(wire.out_pin);
+                }
+            }
+            if wire.out_pin.node == node {
+                if let Some(remotes) = out_remotes.get_mut(wire.out_pin.output) {
+                    remotes.push(wire.in_pin);
+                }
+            }
+        }
+
+        (in_remotes, out_remotes)
+    }
 }
 
+/// How far past the number of nodes an explicitly chosen node id may be.
+/// Storage for nodes grows with the largest id, so huge ids are rejected.
+const MAX_NODE_ID_GAP: usize = 65_536;
+
 /// Snarl is generic node-graph container.
 ///
 /// It holds graph state - positioned nodes and wires between their pins.
 /// It can be rendered using [`Snarl::show`].
+///
+/// Iterating over `&Snarl<T>` or `&mut Snarl<T>` is the canonical way to visit all nodes.
+/// It yields node identifiers with references to [`Node`]s.
+///
+/// # Examples
+///
+/// ```
+/// # use egui_snarl::Snarl;
+/// # use emath::pos2;
+/// let mut snarl = Snarl::<i32>::new();
+/// snarl.insert_node(pos2(0.0, 0.0), 1);
+/// snarl.insert_node(pos2(100.0, 0.0), 2);
+///
+/// for (_, node) in &mut snarl {
+///     node.value *= 10;
+/// }
+///
+/// let mut sum = 0;
+/// for (id, node) in &snarl {
+///     assert_eq!(snarl[id], node.value);
+///     sum += node.value;
+/// }
+/// assert_eq!(sum, 30);
+/// ```
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Snarl<T> {
@@ -222,6 +390,197 @@ impl<T> Snarl<T> {
         }
     }
 
+    /// Removes all nodes and wires, keeping allocated memory.
+    ///
+    /// Node ids start from zero again, so new nodes may pick up UI state of removed nodes
+    /// with the same ids, like draw order or selection.
+    /// Use [`Snarl::forget_ui_state`] to reset UI state as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::<()>::new();
+    /// snarl.insert_node(emath::pos2(0.0, 0.0), ());
+    /// snarl.clear();
+    /// assert!(snarl.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.wires.wires.clear();
+    }
+
+    /// Returns true if the Snarl has no nodes.
+    ///
+    /// Graph without nodes has no wires either.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns number of nodes in the Snarl.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), ());
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    ///
+    /// assert_eq!(snarl.node_count(), 2);
+    /// assert_eq!(snarl.wire_count(), 1);
+    /// ```
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns number of wires in the Snarl.
+    #[must_use]
+    pub fn wire_count(&self) -> usize {
+        self.wires.wires.len()
+    }
+
+    /// Reserves capacity for at least `additional` more nodes.
+    ///
+    /// Use it before inserting many nodes, e.g. when importing a graph,
+    /// to avoid repeated reallocations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::<()>::new();
+    /// snarl.reserve_nodes(100);
+    /// assert!(snarl.capacity() >= 100);
+    ///
+    /// let capacity = snarl.capacity();
+    /// for i in 0..100 {
+    ///     snarl.insert_node(emath::pos2(i as f32, 0.0), ());
+    /// }
+    /// assert_eq!(snarl.capacity(), capacity);
+    /// ```
+    pub fn reserve_nodes(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+    }
+
+    /// Returns number of nodes the Snarl can hold without reallocating.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    /// Converts node values with `f`, keeping node ids, positions, open flags and wires.
+    ///
+    /// Useful to migrate graphs between versions of node type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), "one");
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), "three");
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    ///
+    /// let lengths = snarl.map(|_, value| value.len());
+    /// assert_eq!(lengths[b], 5);
+    /// assert_eq!(lengths.wire_count(), 1);
+    /// ```
+    #[must_use]
+    pub fn map<U>(self, mut f: impl FnMut(NodeId, T) -> U) -> Snarl<U> {
+        let nodes = self
+            .nodes
+            .into_iter()
+            .map(|(idx, node)| {
+                let node = Node {
+                    value: f(NodeId(idx), node.value),
+                    pos: node.pos,
+                    open: node.open,
+                };
+                (idx, node)
+            })
+            .collect();
+
+        Snarl {
+            nodes,
+            wires: self.wires,
+        }
+    }
+
+    /// Builds a new graph with node values produced by `f` from values of this graph.
+    /// Node ids, positions, open flags and wires are the same in both graphs.
+    ///
+    /// Unlike [`Snarl::map`], this graph is kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), 1);
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), 2);
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    ///
+    /// let labels = snarl.map_ref(|_, value| value.to_string());
+    /// assert_eq!(labels[b], "2");
+    /// assert_eq!(snarl[b], 2);
+    /// ```
+    #[must_use]
+    pub fn map_ref<U>(&self, mut f: impl FnMut(NodeId, &T) -> U) -> Snarl<U> {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|(idx, node)| {
+                let node = Node {
+                    value: f(NodeId(idx), &node.value),
+                    pos: node.pos,
+                    open: node.open,
+                };
+                (idx, node)
+            })
+            .collect();
+
+        Snarl {
+            nodes,
+            wires: self.wires.clone(),
+        }
+    }
+
+    /// Converts node values with `f`, keeping node ids, positions, open flags and wires.
+    ///
+    /// # Errors
+    ///
+    /// Returns first error returned by `f`.
+    /// The whole conversion is aborted then.
+    pub fn try_map<U, E>(
+        self,
+        mut f: impl FnMut(NodeId, T) -> Result<U, E>,
+    ) -> Result<Snarl<U>, E> {
+        let nodes = self
+            .nodes
+            .into_iter()
+            .map(|(idx, node)| {
+                let node = Node {
+                    value: f(NodeId(idx), node.value)?,
+                    pos: node.pos,
+                    open: node.open,
+                };
+                Ok((idx, node))
+            })
+            .collect::<Result<_, E>>()?;
+
+        Ok(Snarl {
+            nodes,
+            wires: self.wires,
+        })
+    }
+
     /// Adds a node to the Snarl.
     /// Returns the index of the node.
     ///
@@ -230,9 +589,9 @@ impl<T> Snarl<T> {
     /// ```
     /// # use egui_snarl::Snarl;
     /// let mut snarl = Snarl::<()>::new();
-    /// snarl.insert_node(egui::pos2(0.0, 0.0), ());
+    /// snarl.insert_node(emath::pos2(0.0, 0.0), ());
     /// ```
-    pub fn insert_node(&mut self, pos: egui::Pos2, node: T) -> NodeId {
+    pub fn insert_node(&mut self, pos: emath::Pos2, node: T) -> NodeId {
         let idx = self.nodes.insert(Node {
             value: node,
             pos,
@@ -250,9 +609,9 @@ impl<T> Snarl<T> {
     /// ```
     /// # use egui_snarl::Snarl;
     /// let mut snarl = Snarl::<()>::new();
-    /// snarl.insert_node_collapsed(egui::pos2(0.0, 0.0), ());
+    /// snarl.insert_node_collapsed(emath::pos2(0.0, 0.0), ());
     /// ```
-    pub fn insert_node_collapsed(&mut self, pos: egui::Pos2, node: T) -> NodeId {
+    pub fn insert_node_collapsed(&mut self, pos: emath::Pos2, node: T) -> NodeId {
         let idx = self.nodes.insert(Node {
             value: node,
             pos,
@@ -262,6 +621,177 @@ impl<T> Snarl<T> {
         NodeId(idx)
     }
 
+    /// Adds a node to the Snarl with specified id.
+    /// Returns false and drops the node if the id is already taken
+    /// or is more than 65 536 past the number of nodes,
+    /// since storage for nodes grows with the largest id.
+    ///
+    /// Useful to keep ids from another format when importing graphs.
+    /// Ids in between are left vacant and will be used by [`Snarl::insert_node`].
+    /// Mixing the two may therefore make ids collide,
+    /// so insert all nodes with known ids before adding new nodes.
+    ///
+    /// Unless `id` is the one [`Snarl::insert_node`] would pick next,
+    /// node storage is rebuilt, which takes time proportional to the number of nodes.
+    /// Use [`Snarl::from_nodes_with_ids`] to import many nodes at once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{NodeId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// assert!(snarl.insert_node_with_id(NodeId(5), emath::pos2(0.0, 0.0), ()));
+    /// assert!(!snarl.insert_node_with_id(NodeId(5), emath::pos2(0.0, 0.0), ()));
+    /// assert!(snarl.get_node(NodeId(5)).is_some());
+    ///
+    /// // Ids are not hashes.
+    /// assert!(!snarl.insert_node_with_id(NodeId(usize::MAX), emath::pos2(0.0, 0.0), ()));
+    /// ```
+    pub fn insert_node_with_id(&mut self, id: NodeId, pos: emath::Pos2, node: T) -> bool {
+        if self.nodes.contains(id.0) || id.0.saturating_sub(self.nodes.len()) > MAX_NODE_ID_GAP {
+            return false;
+        }
+
+        let node = Node {
+            value: node,
+            pos,
+            open: true,
+        };
+
+        if self.nodes.vacant_key() == id.0 {
+            self.nodes.insert(node);
+        } else {
+            let nodes = std::mem::take(&mut self.nodes);
+            self.nodes = nodes
+                .into_iter()
+                .chain(std::iter::once((id.0, node)))
+                .collect();
+        }
+        true
+    }
+
+    /// Creates a Snarl with nodes at specified ids and no wires.
+    ///
+    /// Like inserting each node with [`Snarl::insert_node_with_id`] into an empty Snarl,
+    /// but node storage is built once, so it takes time proportional to the number of nodes
+    /// no matter in which order ids come.
+    ///
+    /// # Errors
+    ///
+    /// Returns id that is listed twice or is more than 65 536 past the number of nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{NodeId, Snarl};
+    /// # use emath::pos2;
+    /// let snarl = Snarl::from_nodes_with_ids([
+    ///     (NodeId(7), pos2(0.0, 0.0), "a"),
+    ///     (NodeId(2), pos2(100.0, 0.0), "b"),
+    /// ])
+    /// .unwrap();
+    /// assert_eq!(snarl[NodeId(7)], "a");
+    /// assert_eq!(snarl[NodeId(2)], "b");
+    ///
+    /// let duplicate = [(NodeId(1), pos2(0.0, 0.0), "a"), (NodeId(1), pos2(0.0, 0.0), "b")];
+    /// assert_eq!(Snarl::from_nodes_with_ids(duplicate).unwrap_err(), NodeId(1));
+    /// ```
+    pub fn from_nodes_with_ids(
+        nodes: impl IntoIterator<Item = (NodeId, emath::Pos2, T)>,
+    ) -> Result<Self, NodeId> {
+        let mut nodes = nodes
+            .into_iter()
+            .map(|(id, pos, value)| {
+                (
+                    id.0,
+                    Node {
+                        value,
+                        pos,
+                        open: true,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        nodes.sort_by_key(|(idx, _)| *idx);
+
+        if let Some(pair) = nodes.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(NodeId(pair[0].0));
+        }
+        if let Some(&(idx, _)) = nodes.last() {
+            if idx.saturating_sub(nodes.len()) > MAX_NODE_ID_GAP {
+                return Err(NodeId(idx));
+            }
+        }
+
+        Ok(Snarl {
+            nodes: nodes.into_iter().collect(),
+            wires: Wires::new(),
+        })
+    }
+
+    /// Moves nodes to contiguous ids starting from `NodeId(0)`,
+    /// filling gaps left by removed nodes.
+    /// Relative order of ids is kept and wires are rewritten to the new ids.
+    ///
+    /// Returns mapping from old node ids to new ones for every node,
+    /// so that external references to nodes can be updated.
+    /// Node selection and draw order kept by the UI are not remapped.
+    ///
+    /// Wires with missing source or target node are dropped,
+    /// as with [`Snarl::prune_invalid_wires`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, NodeId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), "a");
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), "b");
+    /// let c = snarl.insert_node(pos2(200.0, 0.0), "c");
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: c, input: 0 });
+    /// snarl.remove_node(b);
+    ///
+    /// let ids = snarl.compact();
+    /// assert_eq!(ids[&c], NodeId(1));
+    /// assert_eq!(snarl[NodeId(1)], "c");
+    /// assert_eq!(
+    ///     snarl.wires().collect::<Vec<_>>(),
+    ///     [(OutPinId { node: NodeId(0), output: 0 }, InPinId { node: NodeId(1), input: 0 })],
+    /// );
+    /// ```
+    pub fn compact(&mut self) -> HashMap<NodeId, NodeId> {
+        let capacity = self.nodes.len();
+        let nodes = std::mem::replace(&mut self.nodes, Slab::with_capacity(capacity));
+
+        let mut ids = HashMap::with_capacity(nodes.len());
+        for (idx, node) in nodes {
+            ids.insert(NodeId(idx), NodeId(self.nodes.insert(node)));
+        }
+
+        let wires = std::mem::replace(&mut self.wires, Wires::new());
+        for wire in wires.wires {
+            let (Some(&out_node), Some(&in_node)) =
+                (ids.get(&wire.out_pin.node), ids.get(&wire.in_pin.node))
+            else {
+                continue;
+            };
+
+            self.wires.insert(Wire {
+                out_pin: OutPinId {
+                    node: out_node,
+                    output: wire.out_pin.output,
+                },
+                in_pin: InPinId {
+                    node: in_node,
+                    input: wire.in_pin.input,
+                },
+            });
+        }
+
+        ids
+    }
+
     /// Opens or collapses a node.
     ///
     /// # Panics
@@ -272,6 +802,56 @@ impl<T> Snarl<T> {
         self.nodes[node.0].open = open;
     }
 
+    /// Opens or collapses all nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::<()>::new();
+    /// snarl.insert_node(emath::pos2(0.0, 0.0), ());
+    /// snarl.insert_node_collapsed(emath::pos2(100.0, 0.0), ());
+    ///
+    /// snarl.open_all(false);
+    /// assert!(snarl.nodes_info().all(|node| !node.open));
+    ///
+    /// snarl.open_all(true);
+    /// assert!(snarl.nodes_info().all(|node| node.open));
+    /// ```
+    pub fn open_all(&mut self, open: bool) {
+        for (_, node) in &mut self.nodes {
+            node.open = open;
+        }
+    }
+
+    /// Opens or collapses specified nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the nodes does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.insert_node(emath::pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(emath::pos2(100.0, 0.0), ());
+    /// let c = snarl.insert_node(emath::pos2(200.0, 0.0), ());
+    ///
+    /// snarl.open_nodes(&[a, c], false);
+    ///
+    /// assert!(!snarl.get_node_info(a).unwrap().open);
+    /// assert!(snarl.get_node_info(b).unwrap().open);
+    /// assert!(!snarl.get_node_info(c).unwrap().open);
+    /// ```
+    #[track_caller]
+    pub fn open_nodes(&mut self, nodes: &[NodeId], open: bool) {
+        for node in nodes {
+            self.nodes[node.0].open = open;
+        }
+    }
+
     /// Removes a node from the Snarl.
     /// Returns the node if it was removed.
     ///
@@ -284,14 +864,119 @@ impl<T> Snarl<T> {
     /// ```
     /// # use egui_snarl::Snarl;
     /// let mut snarl = Snarl::<()>::new();
-    /// let node = snarl.insert_node(egui::pos2(0.0, 0.0), ());
+    /// let node = snarl.insert_node(emath::pos2(0.0, 0.0), ());
     /// snarl.remove_node(node);
     /// ```
     #[track_caller]
     pub fn remove_node(&mut self, idx: NodeId) -> T {
-        let value = self.nodes.remove(idx.0).value;
+        match self.try_remove_node(idx) {
+            Some(value) => value,
+            None => panic!("node {} does not exist", idx.0),
+        }
+    }
+
+    /// Removes a node from the Snarl.
+    /// Returns the node if it was removed or `None` if the node does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::new();
+    /// let node = snarl.insert_node(emath::pos2(0.0, 0.0), 42);
+    /// assert_eq!(snarl.try_remove_node(node), Some(42));
+    /// assert_eq!(snarl.try_remove_node(node), None);
+    /// ```
+    pub fn try_remove_node(&mut self, idx: NodeId) -> Option<T> {
+        let value = self.nodes.try_remove(idx.0)?.value;
         self.wires.drop_node(idx);
-        value
+        Some(value)
+    }
+
+    /// Removes all nodes for which `f` returns `false` along with their wires.
+    /// Returns number of removed nodes.
+    ///
+    /// Wires are pruned once after all nodes are visited,
+    /// which is cheaper than removing nodes one by one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), 1);
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), 2);
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    ///
+    /// assert_eq!(snarl.retain_nodes(|_, node| node.value % 2 == 0), 1);
+    /// assert_eq!(snarl.node_count(), 1);
+    /// assert_eq!(snarl.wire_count(), 0);
+    /// ```
+    pub fn retain_nodes(&mut self, mut f: impl FnMut(NodeId, &mut Node<T>) -> bool) -> usize {
+        let count = self.nodes.len();
+        self.nodes.retain(|idx, node| f(NodeId(idx), node));
+
+        let removed = count - self.nodes.len();
+        if removed > 0 {
+            self.prune_invalid_wires();
+        }
+        removed
+    }
+
+    /// Checks that all wires connect existing nodes.
+    ///
+    /// Graph built with [`Snarl`] methods is always valid,
+    /// but wires of a deserialized graph may refer to missing nodes,
+    /// e.g. after saved document was edited by hand.
+    ///
+    /// # Errors
+    ///
+    /// Returns all wires with missing source or target node, sorted.
+    /// Use [`Snarl::prune_invalid_wires`] to remove them.
+    pub fn validate(&self) -> Result<(), Vec<(OutPinId, InPinId)>> {
+        let mut invalid = self
+            .wires()
+            .filter(|&(from, to)| self.check_wire(from, to).is_err())
+            .collect::<Vec<_>>();
+
+        if invalid.is_empty() {
+            return Ok(());
+        }
+
+        invalid.sort_unstable();
+        Err(invalid)
+    }
+
+    /// Removes all wires with missing source or target node.
+    /// Returns number of removed wires.
+    ///
+    /// See [`Snarl::validate`].
+    pub fn prune_invalid_wires(&mut self) -> usize {
+        let nodes = &self.nodes;
+        self.wires
+            .retain(|from, to| nodes.contains(from.node.0) && nodes.contains(to.node.0))
+    }
+
+    /// Removes all wires for which `f` returns `false`.
+    /// Returns number of removed wires.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), 1);
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), 2);
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    /// snarl.connect(OutPinId { node: a, output: 1 }, InPinId { node: b, input: 1 });
+    ///
+    /// assert_eq!(snarl.retain_wires(|out_pin, _| out_pin.output == 0), 1);
+    /// assert_eq!(snarl.wire_count(), 1);
+    /// ```
+    pub fn retain_wires(&mut self, f: impl FnMut(OutPinId, InPinId) -> bool) -> usize {
+        self.wires.retain(f)
     }
 
     /// Connects two nodes.
@@ -301,16 +986,48 @@ impl<T> Snarl<T> {
     /// # Panics
     ///
     /// Panics if either node does not exist.
+    /// Use [`Snarl::try_connect`] to get an error instead.
     #[track_caller]
     pub fn connect(&mut self, from: OutPinId, to: InPinId) -> bool {
-        assert!(self.nodes.contains(from.node.0));
-        assert!(self.nodes.contains(to.node.0));
+        match self.try_connect(from, to) {
+            Ok(connected) => connected,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Connects two nodes.
+    /// Returns `Ok(true)` if the connection was successful.
+    /// Returns `Ok(false)` if the connection already exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConnectError::MissingSource`] if node of `from` pin does not exist
+    /// and [`ConnectError::MissingTarget`] if node of `to` pin does not exist.
+    /// Source node is checked first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{ConnectError, InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.insert_node(emath::pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(emath::pos2(100.0, 0.0), ());
+    ///
+    /// let from = OutPinId { node: a, output: 0 };
+    /// let to = InPinId { node: b, input: 0 };
+    /// assert_eq!(snarl.try_connect(from, to), Ok(true));
+    ///
+    /// snarl.remove_node(b);
+    /// assert_eq!(snarl.try_connect(from, to), Err(ConnectError::MissingTarget { pin: to }));
+    /// ```
+    pub fn try_connect(&mut self, from: OutPinId, to: InPinId) -> Result<bool, ConnectError> {
+        self.check_wire(from, to)?;
 
         let wire = Wire {
             out_pin: from,
             in_pin: to,
         };
-        self.wires.insert(wire)
+        Ok(self.wires.insert(wire))
     }
 
     /// Disconnects two nodes.
@@ -319,17 +1036,91 @@ impl<T> Snarl<T> {
     /// # Panics
     ///
     /// Panics if either node does not exist.
+    /// Use [`Snarl::try_disconnect`] to get an error instead.
     #[track_caller]
     pub fn disconnect(&mut self, from: OutPinId, to: InPinId) -> bool {
-        assert!(self.nodes.contains(from.node.0));
-        assert!(self.nodes.contains(to.node.0));
+        match self.try_disconnect(from, to) {
+            Ok(disconnected) => disconnected,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Disconnects two nodes.
+    /// Returns `Ok(true)` if the connection was removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConnectError::MissingSource`] if node of `from` pin does not exist
+    /// and [`ConnectError::MissingTarget`] if node of `to` pin does not exist.
+    /// Source node is checked first.
+    pub fn try_disconnect(&mut self, from: OutPinId, to: InPinId) -> Result<bool, ConnectError> {
+        self.check_wire(from, to)?;
 
         let wire = Wire {
             out_pin: from,
             in_pin: to,
         };
+        Ok(self.wires.remove(&wire))
+    }
+
+    /// Connects all given pairs of pins.
+    /// Returns number of wires that were created.
+    ///
+    /// Wires that already exist and wires with missing nodes on either end are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, NodeId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.insert_node(emath::pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(emath::pos2(100.0, 0.0), ());
+    ///
+    /// let from = OutPinId { node: a, output: 0 };
+    /// let missing = InPinId { node: NodeId(7), input: 0 };
+    /// let created = snarl.connect_many([
+    ///     (from, InPinId { node: b, input: 0 }),
+    ///     (from, InPinId { node: b, input: 0 }),
+    ///     (from, InPinId { node: b, input: 1 }),
+    ///     (from, missing),
+    /// ]);
+    /// assert_eq!(created, 2);
+    /// ```
+    pub fn connect_many(&mut self, wires: impl IntoIterator<Item = (OutPinId, InPinId)>) -> usize {
+        let mut count = 0;
+        for (from, to) in wires {
+            if let Ok(true) = self.try_connect(from, to) {
+                count += 1;
+            }
+        }
+        count
+    }
 
-        self.wires.remove(&wire)
+    /// Disconnects all given pairs of pins.
+    /// Returns number of wires that were removed.
+    ///
+    /// Pairs that are not connected and ones with missing nodes are skipped.
+    pub fn disconnect_many(
+        &mut self,
+        wires: impl IntoIterator<Item = (OutPinId, InPinId)>,
+    ) -> usize {
+        let mut count = 0;
+        for (from, to) in wires {
+            if let Ok(true) = self.try_disconnect(from, to) {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    fn check_wire(&self, from: OutPinId, to: InPinId) -> Result<(), ConnectError> {
+        if !self.nodes.contains(from.node.0) {
+            return Err(ConnectError::MissingSource { pin: from });
+        }
+        if !self.nodes.contains(to.node.0) {
+            return Err(ConnectError::MissingTarget { pin: to });
+        }
+        Ok(())
     }
 
     /// Removes all connections to the node's pin.
@@ -339,10 +1130,23 @@ impl<T> Snarl<T> {
     /// # Panics
     ///
     /// Panics if the node does not exist.
+    /// Use [`Snarl::try_drop_inputs`] to get `None` instead.
     #[track_caller]
     pub fn drop_inputs(&mut self, pin: InPinId) -> usize {
-        assert!(self.nodes.contains(pin.node.0));
-        self.wires.drop_inputs(pin)
+        match self.try_drop_inputs(pin) {
+            Some(count) => count,
+            None => panic!("node {} does not exist", pin.node.0),
+        }
+    }
+
+    /// Removes all connections to the node's pin.
+    ///
+    /// Returns number of removed connections or `None` if the node does not exist.
+    pub fn try_drop_inputs(&mut self, pin: InPinId) -> Option<usize> {
+        if !self.nodes.contains(pin.node.0) {
+            return None;
+        }
+        Some(self.wires.drop_inputs(pin))
     }
 
     /// Removes all connections from the node's pin.
@@ -351,10 +1155,110 @@ impl<T> Snarl<T> {
     /// # Panics
     ///
     /// Panics if the node does not exist.
+    /// Use [`Snarl::try_drop_outputs`] to get `None` instead.
     #[track_caller]
     pub fn drop_outputs(&mut self, pin: OutPinId) -> usize {
-        assert!(self.nodes.contains(pin.node.0));
-        self.wires.drop_outputs(pin)
+        match self.try_drop_outputs(pin) {
+            Some(count) => count,
+            None => panic!("node {} does not exist", pin.node.0),
+        }
+    }
+
+    /// Removes all connections from the node's pin.
+    ///
+    /// Returns number of removed connections or `None` if the node does not exist.
+    pub fn try_drop_outputs(&mut self, pin: OutPinId) -> Option<usize> {
+        if !self.nodes.contains(pin.node.0) {
+            return None;
+        }
+        Some(self.wires.drop_outputs(pin))
+    }
+
+    /// Remaps wires connected to the node's inputs after inputs were reordered.
+    ///
+    /// Input at index `i` is identified by key `old_keys[i]` before reordering
+    /// and by key `new_keys[i]` after.
+    /// Wires connected to an input are moved to the input with the same key.
+    /// Wires connected to inputs whose keys are missing in `new_keys` are removed.
+    /// Wires connected to inputs beyond `old_keys` are left untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.insert_node(emath::pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(emath::pos2(100.0, 0.0), ());
+    ///
+    /// let out_pin = OutPinId { node: a, output: 0 };
+    /// snarl.connect(out_pin, InPinId { node: b, input: 0 });
+    ///
+    /// // Inputs of `b` are swapped.
+    /// snarl.remap_inputs_by_key(b, &["x", "y"], &["y", "x"]);
+    ///
+    /// assert!(snarl.wires().eq([(out_pin, InPinId { node: b, input: 1 })]));
+    /// ```
+    #[track_caller]
+    pub fn remap_inputs_by_key<K>(&mut self, node: NodeId, old_keys: &[K], new_keys: &[K])
+    where
+        K: PartialEq,
+    {
+        self.remap_in_pins(node, |input| match old_keys.get(input) {
+            None => Some(input),
+            Some(key) => new_keys.iter().position(|k| k == key),
+        });
+    }
+
+    /// Moves wires connected to input `i` of the node to input `map(i)`,
+    /// or removes them if `map(i)` returns `None`.
+    ///
+    /// Use it when inputs of the node are added, removed or reordered.
+    /// Wires that end up connecting the same pins are merged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.insert_node(emath::pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(emath::pos2(100.0, 0.0), ());
+    ///
+    /// let out_pin = OutPinId { node: a, output: 0 };
+    /// snarl.connect(out_pin, InPinId { node: b, input: 0 });
+    /// snarl.connect(out_pin, InPinId { node: b, input: 1 });
+    ///
+    /// // First input of `b` is removed.
+    /// snarl.remap_in_pins(b, |input| input.checked_sub(1));
+    ///
+    /// assert!(snarl.wires().eq([(out_pin, InPinId { node: b, input: 0 })]));
+    /// ```
+    #[track_caller]
+    pub fn remap_in_pins(&mut self, node: NodeId, map: impl FnMut(usize) -> Option<usize>) {
+        assert!(self.nodes.contains(node.0));
+        self.wires.remap_inputs(node, map);
+    }
+
+    /// Moves wires connected to output `i` of the node to output `map(i)`,
+    /// or removes them if `map(i)` returns `None`.
+    ///
+    /// Use it when outputs of the node are added, removed or reordered.
+    /// Wires that end up connecting the same pins are merged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not exist.
+    #[track_caller]
+    pub fn remap_out_pins(&mut self, node: NodeId, map: impl FnMut(usize) -> Option<usize>) {
+        assert!(self.nodes.contains(node.0));
+        self.wires.remap_outputs(node, map);
     }
 
     /// Returns reference to the node.
@@ -382,6 +1286,32 @@ impl<T> Snarl<T> {
         self.nodes.get_mut(idx.0)
     }
 
+    /// Iterates over data of nodes with given ids.
+    /// Yields `None` for ids of nodes that don't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{NodeId, Snarl};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), "a");
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), "b");
+    /// snarl.remove_node(a);
+    ///
+    /// let values = snarl
+    ///     .node_infos([a, b])
+    ///     .map(|(id, info)| (id, info.map(|info| info.value)))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(values, [(a, None), (b, Some("b"))]);
+    /// ```
+    pub fn node_infos<'a>(
+        &'a self,
+        ids: impl IntoIterator<Item = NodeId> + 'a,
+    ) -> impl Iterator<Item = (NodeId, Option<&'a Node<T>>)> + 'a {
+        ids.into_iter().map(|id| (id, self.nodes.get(id.0)))
+    }
+
     /// Iterates over shared references to each node.
     pub fn nodes(&self) -> NodesIter<'_, T> {
         NodesIter {
@@ -471,17 +1401,314 @@ impl<T> Snarl<T> {
         self.wires.iter().map(|wire| (wire.out_pin, wire.in_pin))
     }
 
+    /// Iterates over wires connected to any pin of the node.
+    ///
+    /// Wire connecting node to itself is returned once.
+    /// Returns nothing if the node does not exist.
+    pub fn wires_of(&self, node: NodeId) -> impl Iterator<Item = (OutPinId, InPinId)> + '_ {
+        self.wires()
+            .filter(move |(out_pin, in_pin)| out_pin.node == node || in_pin.node == node)
+    }
+
+    /// Iterates over wires connected to inputs of the node.
+    ///
+    /// Returns nothing if the node does not exist.
+    pub fn incoming_wires(&self, node: NodeId) -> impl Iterator<Item = (OutPinId, InPinId)> + '_ {
+        self.wires().filter(move |(_, in_pin)| in_pin.node == node)
+    }
+
+    /// Iterates over wires connected to outputs of the node.
+    ///
+    /// Returns nothing if the node does not exist.
+    pub fn outgoing_wires(&self, node: NodeId) -> impl Iterator<Item = (OutPinId, InPinId)> + '_ {
+        self.wires()
+            .filter(move |(out_pin, _)| out_pin.node == node)
+    }
+
+    /// Returns true if the output pin is wired to the input pin.
+    ///
+    /// Unlike collecting remotes of [`Snarl::in_pin`] this does not allocate
+    /// and takes constant time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), ());
+    /// let from = OutPinId { node: a, output: 0 };
+    /// let to = InPinId { node: b, input: 0 };
+    ///
+    /// assert!(!snarl.connected(from, to));
+    /// snarl.connect(from, to);
+    /// assert!(snarl.connected(from, to));
+    /// ```
+    #[must_use]
+    pub fn connected(&self, from: OutPinId, to: InPinId) -> bool {
+        self.wires.contains(&Wire {
+            out_pin: from,
+            in_pin: to,
+        })
+    }
+
+    /// Returns true if any pin of one node is wired to any pin of the other node,
+    /// in either direction.
+    ///
+    /// Use [`Snarl::connected`] to check specific pins.
+    /// Does not allocate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), ());
+    /// let c = snarl.insert_node(pos2(200.0, 0.0), ());
+    /// snarl.connect(OutPinId { node: b, output: 1 }, InPinId { node: a, input: 2 });
+    ///
+    /// assert!(snarl.are_nodes_connected(a, b));
+    /// assert!(snarl.are_nodes_connected(b, a));
+    /// assert!(!snarl.are_nodes_connected(a, c));
+    /// ```
+    #[must_use]
+    pub fn are_nodes_connected(&self, a: NodeId, b: NodeId) -> bool {
+        self.wires.iter().any(|wire| {
+            (wire.out_pin.node == a && wire.in_pin.node == b)
+                || (wire.out_pin.node == b && wire.in_pin.node == a)
+        })
+    }
+
+    /// Iterates over wires connected to the input pin.
+    ///
+    /// Number of returned wires is the pin's fan-in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), ());
+    /// let input = InPinId { node: b, input: 0 };
+    /// snarl.connect(OutPinId { node: a, output: 0 }, input);
+    /// snarl.connect(OutPinId { node: a, output: 1 }, input);
+    ///
+    /// assert_eq!(snarl.in_wires(input).count(), 2);
+    /// ```
+    pub fn in_wires(&self, pin: InPinId) -> impl Iterator<Item = (OutPinId, InPinId)> + '_ {
+        self.wires
+            .wired_outputs(pin)
+            .map(move |out_pin| (out_pin, pin))
+    }
+
+    /// Iterates over wires connected to the output pin.
+    ///
+    /// Number of returned wires is the pin's fan-out.
+    pub fn out_wires(&self, pin: OutPinId) -> impl Iterator<Item = (OutPinId, InPinId)> + '_ {
+        self.wires
+            .wired_inputs(pin)
+            .map(move |in_pin| (pin, in_pin))
+    }
+
+    /// Returns number of wires connected to the input pin.
+    #[must_use]
+    pub fn in_pin_degree(&self, pin: InPinId) -> usize {
+        self.in_wires(pin).count()
+    }
+
+    /// Returns number of wires connected to the output pin.
+    #[must_use]
+    pub fn out_pin_degree(&self, pin: OutPinId) -> usize {
+        self.out_wires(pin).count()
+    }
+
+    /// Returns number of wires connected to inputs of the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), ());
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 1 });
+    ///
+    /// assert_eq!(snarl.node_in_degree(b), 2);
+    /// assert_eq!(snarl.node_out_degree(a), 2);
+    /// assert_eq!(snarl.out_pin_degree(OutPinId { node: a, output: 0 }), 2);
+    /// ```
+    #[must_use]
+    pub fn node_in_degree(&self, node: NodeId) -> usize {
+        self.incoming_wires(node).count()
+    }
+
+    /// Returns number of wires connected to outputs of the node.
+    #[must_use]
+    pub fn node_out_degree(&self, node: NodeId) -> usize {
+        self.outgoing_wires(node).count()
+    }
+
+    /// Exports wires with nodes identified by keys instead of [`NodeId`]s.
+    ///
+    /// Each wire is returned as `(from_key, output, to_key, input)`.
+    /// Wires are sorted by node ids and pin indices, so that result is stable
+    /// and can be stored and compared with previous exports.
+    ///
+    /// Use [`Snarl::import_wires_by_key`] to restore the wires.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{InPinId, OutPinId, Snarl};
+    /// let mut snarl = Snarl::<&str>::new();
+    /// let a = snarl.insert_node(emath::pos2(0.0, 0.0), "a");
+    /// let b = snarl.insert_node(emath::pos2(100.0, 0.0), "b");
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 1 });
+    ///
+    /// let wires = snarl.export_wires_by_key(|_, name| name.to_string());
+    /// assert_eq!(wires, [("a".to_string(), 0, "b".to_string(), 1)]);
+    /// ```
+    pub fn export_wires_by_key<K>(
+        &self,
+        key: impl Fn(NodeId, &T) -> K,
+    ) -> Vec<(K, usize, K, usize)> {
+        let mut wires = self.wires().collect::<Vec<_>>();
+        wires.sort_unstable();
+
+        wires
+            .into_iter()
+            .map(|(out_pin, in_pin)| {
+                (
+                    key(out_pin.node, &self.nodes[out_pin.node.0].value),
+                    out_pin.output,
+                    key(in_pin.node, &self.nodes[in_pin.node.0].value),
+                    in_pin.input,
+                )
+            })
+            .collect()
+    }
+
+    /// Imports wires with nodes identified by keys,
+    /// e.g. exported with [`Snarl::export_wires_by_key`].
+    ///
+    /// `lookup` maps key to the [`NodeId`] of the node.
+    /// Wires that refer to unknown keys or to nodes that do not exist are skipped
+    /// and reported in returned [`ImportReport`].
+    ///
+    /// Note that snarl does not know how many pins nodes have,
+    /// so pin indices are not validated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// let mut snarl = Snarl::<&str>::new();
+    /// let a = snarl.insert_node(emath::pos2(0.0, 0.0), "a");
+    /// let b = snarl.insert_node(emath::pos2(100.0, 0.0), "b");
+    ///
+    /// let wires = [("a", 0, "b", 1), ("a", 0, "c", 0), ("b", 2, "a", 0)];
+    ///
+    /// let report = snarl.import_wires_by_key(&wires, |key| match *key {
+    ///     "a" => Some(a),
+    ///     "b" => Some(b),
+    ///     _ => None,
+    /// });
+    ///
+    /// assert_eq!(report.connected, 2);
+    /// assert_eq!(report.unknown_keys, [1]);
+    ///
+    /// // Export after import reproduces the same wires.
+    /// let mut exported = snarl.export_wires_by_key(|_, name| *name);
+    /// let mut expected = vec![("a", 0, "b", 1), ("b", 2, "a", 0)];
+    /// exported.sort();
+    /// expected.sort();
+    /// assert_eq!(exported, expected);
+    /// ```
+    pub fn import_wires_by_key<K>(
+        &mut self,
+        wires: &[(K, usize, K, usize)],
+        lookup: impl Fn(&K) -> Option<NodeId>,
+    ) -> ImportReport {
+        let mut report = ImportReport::default();
+
+        for (idx, (from, output, to, input)) in wires.iter().enumerate() {
+            let (Some(from), Some(to)) = (lookup(from), lookup(to)) else {
+                report.unknown_keys.push(idx);
+                continue;
+            };
+
+            if !self.nodes.contains(from.0) || !self.nodes.contains(to.0) {
+                report.missing_nodes.push(idx);
+                continue;
+            }
+
+            let out_pin = OutPinId {
+                node: from,
+                output: *output,
+            };
+            let in_pin = InPinId {
+                node: to,
+                input: *input,
+            };
+
+            if self.connect(out_pin, in_pin) {
+                report.connected += 1;
+            } else {
+                report.existing += 1;
+            }
+        }
+
+        report
+    }
+
     /// Returns input pin of the node.
+    ///
+    /// This is the way to get [`InPin`] with its remotes outside of [`SnarlViewer`](crate::ui::SnarlViewer) callbacks,
+    /// e.g. to call helper functions shared with them.
+    /// Use [`InPin::from_parts`] if remotes are already known.
     #[must_use]
     pub fn in_pin(&self, pin: InPinId) -> InPin {
         InPin::new(self, pin)
     }
 
     /// Returns output pin of the node.
+    ///
+    /// This is the way to get [`OutPin`] with its remotes outside of [`SnarlViewer`](crate::ui::SnarlViewer) callbacks,
+    /// e.g. to call helper functions shared with them.
+    /// Use [`OutPin::from_parts`] if remotes are already known.
     #[must_use]
     pub fn out_pin(&self, pin: OutPinId) -> OutPin {
         OutPin::new(self, pin)
     }
+
+    /// Returns first `inputs` input pins and first `outputs` output pins of the node.
+    ///
+    /// Wires are scanned once for all pins instead of once per pin.
+    #[cfg(feature = "ui")]
+    fn node_pins(&self, node: NodeId, inputs: usize, outputs: usize) -> (Vec<InPin>, Vec<OutPin>) {
+        let (in_remotes, out_remotes) = self.wires.node_remotes(node, inputs, outputs);
+
+        let inputs = in_remotes
+            .into_iter()
+            .enumerate()
+            .map(|(input, remotes)| InPin::from_parts(InPinId { node, input }, remotes))
+            .collect();
+
+        let outputs = out_remotes
+            .into_iter()
+            .enumerate()
+            .map(|(output, remotes)| OutPin::from_parts(OutPinId { node, output }, remotes))
+            .collect();
+
+        (inputs, outputs)
+    }
 }
 
 impl<T> Index<NodeId> for Snarl<T> {
@@ -502,6 +1729,26 @@ impl<T> IndexMut<NodeId> for Snarl<T> {
     }
 }
 
+impl<'a, T> IntoIterator for &'a Snarl<T> {
+    type Item = (NodeId, &'a Node<T>);
+    type IntoIter = NodeIdsDataIter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> NodeIdsDataIter<'a, T> {
+        self.nodes_ids_data()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Snarl<T> {
+    type Item = (NodeId, &'a mut Node<T>);
+    type IntoIter = NodeIdsDataIterMut<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> NodeIdsDataIterMut<'a, T> {
+        self.nodes_ids_data_mut()
+    }
+}
+
 /// Iterator over shared references to nodes.
 #[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
 pub struct NodesIter<'a, T> {
@@ -797,7 +2044,19 @@ pub struct OutPin {
     pub id: OutPinId,
 
     /// List of input pins connected to this output pin.
+    /// Empty for pins without wires, which doesn't allocate.
     pub remotes: Vec<InPinId>,
+
+    /// Whether pointer was over the pin or its content in the previous frame.
+    ///
+    /// This is always `false` outside of [`Snarl::show`].
+    pub hovered: bool,
+
+    /// Whether new wire is being dragged over the pin
+    /// and will be connected to it when released.
+    ///
+    /// This is always `false` outside of [`Snarl::show`].
+    pub wire_target: bool,
 }
 
 /// Node and its output pin.
@@ -807,23 +2066,73 @@ pub struct InPin {
     pub id: InPinId,
 
     /// List of output pins connected to this input pin.
+    /// Empty for pins without wires, which doesn't allocate.
     pub remotes: Vec<OutPinId>,
+
+    /// Whether pointer was over the pin or its content in the previous frame.
+    ///
+    /// This is always `false` outside of [`Snarl::show`].
+    pub hovered: bool,
+
+    /// Whether new wire is being dragged over the pin
+    /// and will be connected to it when released.
+    ///
+    /// This is always `false` outside of [`Snarl::show`].
+    pub wire_target: bool,
 }
 
 impl OutPin {
     fn new<T>(snarl: &Snarl<T>, pin: OutPinId) -> Self {
+        OutPin::from_parts(pin, snarl.wires.wired_inputs(pin).collect())
+    }
+
+    /// Creates output pin from its identifier and connected input pins.
+    ///
+    /// Remotes are not checked against any graph.
+    /// Use [`Snarl::out_pin`] to get pin with remotes taken from the graph.
+    #[must_use]
+    pub const fn from_parts(id: OutPinId, remotes: Vec<InPinId>) -> Self {
         OutPin {
-            id: pin,
-            remotes: snarl.wires.wired_inputs(pin).collect(),
+            id,
+            remotes,
+            hovered: false,
+            wire_target: false,
         }
     }
 }
 
 impl InPin {
     fn new<T>(snarl: &Snarl<T>, pin: InPinId) -> Self {
+        InPin::from_parts(pin, snarl.wires.wired_outputs(pin).collect())
+    }
+
+    /// Creates input pin from its identifier and connected output pins.
+    ///
+    /// Remotes are not checked against any graph.
+    /// Use [`Snarl::in_pin`] to get pin with remotes taken from the graph.
+    #[must_use]
+    pub const fn from_parts(id: InPinId, remotes: Vec<OutPinId>) -> Self {
         InPin {
-            id: pin,
-            remotes: snarl.wires.wired_outputs(pin).collect(),
+            id,
+            remotes,
+            hovered: false,
+            wire_target: false,
         }
     }
 }
+
+/// Report of [`Snarl::import_wires_by_key`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Number of wires that were connected.
+    pub connected: usize,
+
+    /// Number of wires that already existed.
+    pub existing: usize,
+
+    /// Indices of skipped wires with keys not known to the lookup function.
+    pub unknown_keys: Vec<usize>,
+
+    /// Indices of skipped wires with keys resolved to nodes that do not exist in the snarl.
+    pub missing_nodes: Vec<usize>,
+}
@@ -0,0 +1,493 @@
+//! Limits for loading graphs from untrusted sources.
+
+use std::fmt;
+
+use emath::Pos2;
+
+use crate::{NodeId, Snarl};
+
+/// Limits checked by [`Snarl::deserialize_with_limits`].
+///
+/// Recommended limits depend on the application,
+/// but graphs edited by hand rarely have more than a few thousand nodes.
+/// [`SnarlLimits::new`] returns limits that are generous for such graphs
+/// and still keep loading and the first frame fast.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SnarlLimits {
+    /// Maximum number of nodes.
+    /// Node identifiers must be less than this value as well,
+    /// since storage for nodes grows with the largest identifier.
+    ///
+    /// Defaults to 10 000.
+    pub max_nodes: usize,
+
+    /// Maximum number of wires.
+    ///
+    /// Defaults to 100 000.
+    pub max_wires: usize,
+
+    /// Maximum absolute value of node position coordinates.
+    /// Non-finite coordinates are always rejected.
+    ///
+    /// Defaults to 1 000 000.
+    pub max_coordinate: f32,
+}
+
+impl SnarlLimits {
+    /// Creates new [`SnarlLimits`] with recommended values.
+    #[must_use]
+    pub const fn new() -> Self {
+        SnarlLimits {
+            max_nodes: 10_000,
+            max_wires: 100_000,
+            max_coordinate: 1_000_000.0,
+        }
+    }
+}
+
+impl Default for SnarlLimits {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Limit from [`SnarlLimits`] that was exceeded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LimitExceeded {
+    /// Graph has more nodes than allowed.
+    Nodes {
+        /// Maximum number of nodes.
+        limit: usize,
+    },
+
+    /// Node identifier is too large.
+    NodeId {
+        /// Identifier of the node.
+        node: NodeId,
+
+        /// Maximum number of nodes.
+        limit: usize,
+    },
+
+    /// Graph has more wires than allowed.
+    Wires {
+        /// Maximum number of wires.
+        limit: usize,
+    },
+
+    /// Node position is too far from origin or not finite.
+    Coordinate {
+        /// Identifier of the node.
+        node: NodeId,
+
+        /// Position of the node.
+        pos: Pos2,
+
+        /// Maximum absolute value of coordinates.
+        limit: f32,
+    },
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            LimitExceeded::Nodes { limit } => write!(f, "graph has more than {limit} nodes"),
+            LimitExceeded::NodeId { node, limit } => {
+                write!(f, "node id {} is not less than {limit}", node.0)
+            }
+            LimitExceeded::Wires { limit } => write!(f, "graph has more than {limit} wires"),
+            LimitExceeded::Coordinate { node, pos, limit } => write!(
+                f,
+                "node {} position {pos:?} is outside of [-{limit}, {limit}]",
+                node.0
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+/// Error returned by [`Snarl::deserialize_with_limits`].
+#[derive(Debug)]
+pub enum DeserializeError<E> {
+    /// Graph exceeds limits.
+    /// Deserialization stopped as soon as the limit was exceeded.
+    Limit(LimitExceeded),
+
+    /// Deserializer failed.
+    Deserialize(E),
+}
+
+impl<E> fmt::Display for DeserializeError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeserializeError::Limit(limit) => fmt::Display::fmt(limit, f),
+            DeserializeError::Deserialize(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl<E> std::error::Error for DeserializeError<E>
+where
+    E: std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DeserializeError::Limit(limit) => Some(limit),
+            DeserializeError::Deserialize(err) => Some(err),
+        }
+    }
+}
+
+/// Number of nodes and wires in the graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SnarlCounts {
+    /// Number of nodes.
+    pub nodes: usize,
+
+    /// Number of wires.
+    pub wires: usize,
+}
+
+impl SnarlCounts {
+    /// Checks counts against the limits.
+    ///
+    /// # Errors
+    ///
+    /// Returns first exceeded limit.
+    pub const fn check(&self, limits: &SnarlLimits) -> Result<(), LimitExceeded> {
+        if self.nodes > limits.max_nodes {
+            return Err(LimitExceeded::Nodes {
+                limit: limits.max_nodes,
+            });
+        }
+        if self.wires > limits.max_wires {
+            return Err(LimitExceeded::Wires {
+                limit: limits.max_wires,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<T> Snarl<T> {
+    /// Returns number of nodes and wires in the graph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, SnarlCounts, InPinId, OutPinId, NodeId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::<()>::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), ());
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    ///
+    /// assert_eq!(snarl.counts(), SnarlCounts { nodes: 2, wires: 1 });
+    /// ```
+    #[must_use]
+    pub fn counts(&self) -> SnarlCounts {
+        SnarlCounts {
+            nodes: self.node_count(),
+            wires: self.wire_count(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod de {
+    use std::{cell::Cell, fmt, marker::PhantomData};
+
+    use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+    use slab::Slab;
+
+    use crate::{Node, NodeId, Snarl, Wire, Wires};
+
+    use super::{DeserializeError, LimitExceeded, SnarlLimits};
+
+    impl<T> Snarl<T> {
+        /// Deserializes graph from untrusted source, failing as soon as any limit is exceeded.
+        ///
+        /// Accepts the same format as `Deserialize` implementation of [`Snarl`].
+        /// Nodes and wires are checked while they are read,
+        /// so oversized input is rejected before it is fully loaded into memory.
+        ///
+        /// # Errors
+        ///
+        /// Returns [`DeserializeError::Limit`] if graph exceeds `limits`
+        /// and [`DeserializeError::Deserialize`] if deserializer fails otherwise.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use egui_snarl::{DeserializeError, LimitExceeded, Snarl, SnarlLimits};
+        /// let json = r#"{"nodes":{"0":{"value":1,"pos":{"x":0.0,"y":0.0},"open":true}},"wires":[]}"#;
+        ///
+        /// let limits = SnarlLimits::new();
+        /// let mut de = serde_json::Deserializer::from_str(json);
+        /// let snarl = Snarl::<i32>::deserialize_with_limits(&mut de, limits).unwrap();
+        /// assert_eq!(snarl.counts().nodes, 1);
+        ///
+        /// let limits = SnarlLimits { max_nodes: 0, ..SnarlLimits::new() };
+        /// let mut de = serde_json::Deserializer::from_str(json);
+        /// let err = Snarl::<i32>::deserialize_with_limits(&mut de, limits).unwrap_err();
+        /// assert!(matches!(err, DeserializeError::Limit(LimitExceeded::Nodes { limit: 0 })));
+        /// ```
+        pub fn deserialize_with_limits<'de, D>(
+            deserializer: D,
+            limits: SnarlLimits,
+        ) -> Result<Self, DeserializeError<D::Error>>
+        where
+            D: Deserializer<'de>,
+            T: serde::Deserialize<'de>,
+        {
+            let exceeded = Cell::new(None);
+
+            let result = deserializer.deserialize_struct(
+                "Snarl",
+                &["nodes", "wires"],
+                SnarlVisitor {
+                    limits: &limits,
+                    exceeded: &exceeded,
+                    marker: PhantomData,
+                },
+            );
+
+            match (result, exceeded.get()) {
+                (_, Some(limit)) => Err(DeserializeError::Limit(limit)),
+                (Ok(snarl), None) => Ok(snarl),
+                (Err(err), None) => Err(DeserializeError::Deserialize(err)),
+            }
+        }
+    }
+
+    /// Records exceeded limit and returns error that stops deserialization.
+    fn exceed<E: de::Error>(exceeded: &Cell<Option<LimitExceeded>>, limit: LimitExceeded) -> E {
+        exceeded.set(Some(limit));
+        E::custom(limit)
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(field_identifier, rename_all = "lowercase")]
+    enum Field {
+        Nodes,
+        Wires,
+        #[serde(other)]
+        Ignore,
+    }
+
+    struct SnarlVisitor<'a, T> {
+        limits: &'a SnarlLimits,
+        exceeded: &'a Cell<Option<LimitExceeded>>,
+        marker: PhantomData<fn() -> T>,
+    }
+
+    impl<'a, T> SnarlVisitor<'a, T> {
+        const fn nodes(&self) -> NodesSeed<'a, T> {
+            NodesSeed {
+                limits: self.limits,
+                exceeded: self.exceeded,
+                marker: PhantomData,
+            }
+        }
+
+        const fn wires(&self) -> WiresSeed<'a> {
+            WiresSeed {
+                limits: self.limits,
+                exceeded: self.exceeded,
+            }
+        }
+    }
+
+    impl<'de, T> Visitor<'de> for SnarlVisitor<'_, T>
+    where
+        T: serde::Deserialize<'de>,
+    {
+        type Value = Snarl<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("struct Snarl")
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Snarl<T>, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let nodes = seq
+                .next_element_seed(self.nodes())?
+                .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+            let wires = seq
+                .next_element_seed(self.wires())?
+                .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+            Ok(Snarl { nodes, wires })
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Snarl<T>, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut nodes = None;
+            let mut wires = None;
+
+            while let Some(field) = map.next_key()? {
+                match field {
+                    Field::Nodes => {
+                        if nodes.is_some() {
+                            return Err(de::Error::duplicate_field("nodes"));
+                        }
+                        nodes = Some(map.next_value_seed(self.nodes())?);
+                    }
+                    Field::Wires => {
+                        if wires.is_some() {
+                            return Err(de::Error::duplicate_field("wires"));
+                        }
+                        wires = Some(map.next_value_seed(self.wires())?);
+                    }
+                    Field::Ignore => {
+                        map.next_value::<de::IgnoredAny>()?;
+                    }
+                }
+            }
+
+            let nodes = nodes.ok_or_else(|| de::Error::missing_field("nodes"))?;
+            let wires = wires.ok_or_else(|| de::Error::missing_field("wires"))?;
+
+            Ok(Snarl { nodes, wires })
+        }
+    }
+
+    struct NodesSeed<'a, T> {
+        limits: &'a SnarlLimits,
+        exceeded: &'a Cell<Option<LimitExceeded>>,
+        marker: PhantomData<fn() -> T>,
+    }
+
+    impl<'de, T> DeserializeSeed<'de> for NodesSeed<'_, T>
+    where
+        T: serde::Deserialize<'de>,
+    {
+        type Value = Slab<Node<T>>;
+
+        fn deserialize<D>(self, deserializer: D) -> Result<Slab<Node<T>>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_map(self)
+        }
+    }
+
+    impl<'de, T> Visitor<'de> for NodesSeed<'_, T>
+    where
+        T: serde::Deserialize<'de>,
+    {
+        type Value = Slab<Node<T>>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a map of nodes")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Slab<Node<T>>, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let limits = self.limits;
+
+            // Size hint comes from the input and can't be trusted.
+            let capacity = map.size_hint().unwrap_or(0).min(limits.max_nodes);
+            let mut nodes = Vec::with_capacity(capacity);
+
+            while let Some(idx) = map.next_key::<usize>()? {
+                if nodes.len() >= limits.max_nodes {
+                    return Err(exceed(
+                        self.exceeded,
+                        LimitExceeded::Nodes {
+                            limit: limits.max_nodes,
+                        },
+                    ));
+                }
+
+                if idx >= limits.max_nodes {
+                    return Err(exceed(
+                        self.exceeded,
+                        LimitExceeded::NodeId {
+                            node: NodeId(idx),
+                            limit: limits.max_nodes,
+                        },
+                    ));
+                }
+
+                let node: Node<T> = map.next_value()?;
+
+                let max = limits.max_coordinate;
+                if !(node.pos.x.abs() <= max && node.pos.y.abs() <= max) {
+                    return Err(exceed(
+                        self.exceeded,
+                        LimitExceeded::Coordinate {
+                            node: NodeId(idx),
+                            pos: node.pos,
+                            limit: max,
+                        },
+                    ));
+                }
+
+                nodes.push((idx, node));
+            }
+
+            Ok(nodes.into_iter().collect())
+        }
+    }
+
+    struct WiresSeed<'a> {
+        limits: &'a SnarlLimits,
+        exceeded: &'a Cell<Option<LimitExceeded>>,
+    }
+
+    impl<'de> DeserializeSeed<'de> for WiresSeed<'_> {
+        type Value = Wires;
+
+        fn deserialize<D>(self, deserializer: D) -> Result<Wires, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_seq(self)
+        }
+    }
+
+    impl<'de> Visitor<'de> for WiresSeed<'_> {
+        type Value = Wires;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a sequence of wires")
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Wires, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let limits = self.limits;
+
+            let mut wires = Wires::new();
+            let mut count = 0;
+
+            while let Some(wire) = seq.next_element::<Wire>()? {
+                count += 1;
+                if count > limits.max_wires {
+                    return Err(exceed(
+                        self.exceeded,
+                        LimitExceeded::Wires {
+                            limit: limits.max_wires,
+                        },
+                    ));
+                }
+                wires.insert(wire);
+            }
+
+            Ok(wires)
+        }
+    }
+}
@@ -0,0 +1,386 @@
+//! Moving and copying nodes within and between graphs.
+
+use std::collections::HashMap;
+
+use emath::Vec2;
+
+use crate::{InPinId, NodeId, OutPinId, Snarl, Wire};
+
+impl<T> Snarl<T> {
+    /// Moves given nodes into a new graph along with wires between them.
+    ///
+    /// Nodes keep their positions and open state.
+    /// Extracted nodes get new ids in the order they are listed,
+    /// i.e. first extracted node is `NodeId(0)` in the new graph, second is `NodeId(1)` and so on.
+    /// Ids of nodes that don't exist and repeated ids are skipped.
+    ///
+    /// Wires between extracted and remaining nodes are removed and returned
+    /// sorted, with original node ids.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, NodeId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), "a");
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), "b");
+    /// let c = snarl.insert_node(pos2(200.0, 0.0), "c");
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    /// snarl.connect(OutPinId { node: b, output: 0 }, InPinId { node: c, input: 0 });
+    ///
+    /// let (group, severed) = snarl.extract_nodes(&[b, c]);
+    /// assert_eq!(group[NodeId(0)], "b");
+    /// assert_eq!(group.wire_count(), 1);
+    /// assert_eq!(severed, [(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 })]);
+    /// assert_eq!(snarl.node_count(), 1);
+    /// ```
+    pub fn extract_nodes(&mut self, nodes: &[NodeId]) -> (Snarl<T>, Vec<(OutPinId, InPinId)>) {
+        let mut extracted = Snarl::new();
+        let mut ids = ahash::HashMap::default();
+
+        for &node in nodes {
+            if let Some(value) = self.nodes.try_remove(node.0) {
+                ids.insert(node, NodeId(extracted.nodes.insert(value)));
+            }
+        }
+
+        let mut severed = Vec::new();
+        self.wires.wires.retain(|wire| {
+            match (ids.get(&wire.out_pin.node), ids.get(&wire.in_pin.node)) {
+                (None, None) => return true,
+                (Some(&from), Some(&to)) => {
+                    extracted.wires.insert(Wire {
+                        out_pin: OutPinId {
+                            node: from,
+                            output: wire.out_pin.output,
+                        },
+                        in_pin: InPinId {
+                            node: to,
+                            input: wire.in_pin.input,
+                        },
+                    });
+                }
+                _ => severed.push((wire.out_pin, wire.in_pin)),
+            }
+            false
+        });
+        severed.sort_unstable();
+
+        (extracted, severed)
+    }
+
+    /// Inserts all nodes and wires of `other` graph into this one.
+    ///
+    /// Node positions are translated by `offset`, open state is kept.
+    /// Nodes get new ids in this graph and wires are remapped to them.
+    /// Returns mapping from node ids in `other` graph to new ids in this one,
+    /// e.g. to reconnect wires severed by [`Snarl::extract_nodes`].
+    /// Wires of `other` with missing source or target node are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::{pos2, vec2};
+    /// let mut snarl = Snarl::new();
+    /// snarl.insert_node(pos2(0.0, 0.0), "x");
+    ///
+    /// let mut other = Snarl::new();
+    /// let a = other.insert_node(pos2(0.0, 0.0), "a");
+    /// let b = other.insert_node(pos2(100.0, 0.0), "b");
+    /// other.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    ///
+    /// let ids = snarl.insert_snarl(other, vec2(0.0, 100.0));
+    /// assert_eq!(snarl[ids[&a]], "a");
+    /// assert_eq!(snarl.get_node_info(ids[&b]).unwrap().pos, pos2(100.0, 100.0));
+    /// assert_eq!(
+    ///     snarl.wires().collect::<Vec<_>>(),
+    ///     [(OutPinId { node: ids[&a], output: 0 }, InPinId { node: ids[&b], input: 0 })],
+    /// );
+    /// ```
+    pub fn insert_snarl(&mut self, other: Snarl<T>, offset: Vec2) -> HashMap<NodeId, NodeId> {
+        self.nodes.reserve(other.nodes.len());
+
+        let mut ids = HashMap::with_capacity(other.nodes.len());
+        for (idx, mut node) in other.nodes {
+            node.pos += offset;
+            ids.insert(NodeId(idx), NodeId(self.nodes.insert(node)));
+        }
+
+        let wires = other.wires.wires.into_iter().filter_map(|wire| {
+            Some(Wire {
+                out_pin: OutPinId {
+                    node: *ids.get(&wire.out_pin.node)?,
+                    output: wire.out_pin.output,
+                },
+                in_pin: InPinId {
+                    node: *ids.get(&wire.in_pin.node)?,
+                    input: wire.in_pin.input,
+                },
+            })
+        });
+
+        for wire in wires {
+            self.wires.insert(wire);
+        }
+
+        ids
+    }
+
+    /// Appends all nodes and wires of `other` graph to this one.
+    ///
+    /// Same as [`Snarl::insert_snarl`], but returns new ids of `other`'s nodes
+    /// in order of their ids in `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::{pos2, vec2};
+    /// let mut snarl = Snarl::new();
+    /// snarl.insert_node(pos2(0.0, 0.0), "x");
+    ///
+    /// let mut template = Snarl::new();
+    /// let a = template.insert_node(pos2(0.0, 0.0), "a");
+    /// let b = template.insert_node(pos2(100.0, 0.0), "b");
+    /// template.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    ///
+    /// let ids = snarl.merge(template, vec2(0.0, 100.0));
+    /// assert_eq!(snarl[ids[0]], "a");
+    /// assert_eq!(snarl[ids[1]], "b");
+    /// ```
+    pub fn merge(&mut self, other: Snarl<T>, offset: Vec2) -> Vec<NodeId> {
+        let mut ids = self
+            .insert_snarl(other, offset)
+            .into_iter()
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.into_iter().map(|(_, id)| id).collect()
+    }
+}
+
+impl<T: Clone> Snarl<T> {
+    /// Inserts a copy of the node with position translated by `offset`.
+    /// Returns id of the copy.
+    ///
+    /// Value and open state are cloned, wires are not.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not exist.
+    /// Use [`Snarl::try_duplicate_node`] to get `None` instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// # use emath::{pos2, vec2};
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), "a");
+    ///
+    /// let copy = snarl.duplicate_node(a, vec2(20.0, 20.0));
+    /// assert_eq!(snarl[copy], "a");
+    /// assert_eq!(snarl.get_node_info(copy).unwrap().pos, pos2(20.0, 20.0));
+    /// ```
+    #[track_caller]
+    pub fn duplicate_node(&mut self, node: NodeId, offset: Vec2) -> NodeId {
+        match self.try_duplicate_node(node, offset) {
+            Some(copy) => copy,
+            None => panic!("node {} does not exist", node.0),
+        }
+    }
+
+    /// Inserts a copy of the node with position translated by `offset`.
+    /// Returns id of the copy or `None` if the node does not exist.
+    ///
+    /// Value and open state are cloned, wires are not.
+    pub fn try_duplicate_node(&mut self, node: NodeId, offset: Vec2) -> Option<NodeId> {
+        let mut copy = self.nodes.get(node.0)?.clone();
+        copy.pos += offset;
+        Some(NodeId(self.nodes.insert(copy)))
+    }
+
+    /// Inserts a copy of the node with position translated by `offset`
+    /// and connects its inputs to the same outputs as inputs of the original node.
+    /// Returns id of the copy.
+    ///
+    /// This is what editors usually do to duplicate a node.
+    /// Wires from outputs of the node are not copied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not exist.
+    /// Use [`Snarl::try_duplicate_node_with_inputs`] to get `None` instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::{pos2, vec2};
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), "a");
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), "b");
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    ///
+    /// let copy = snarl.duplicate_node_with_inputs(b, vec2(0.0, 50.0));
+    /// assert_eq!(snarl.in_pin_degree(InPinId { node: copy, input: 0 }), 1);
+    /// assert_eq!(snarl.out_pin_degree(OutPinId { node: a, output: 0 }), 2);
+    /// ```
+    #[track_caller]
+    pub fn duplicate_node_with_inputs(&mut self, node: NodeId, offset: Vec2) -> NodeId {
+        match self.try_duplicate_node_with_inputs(node, offset) {
+            Some(copy) => copy,
+            None => panic!("node {} does not exist", node.0),
+        }
+    }
+
+    /// Inserts a copy of the node with position translated by `offset`
+    /// and connects its inputs to the same outputs as inputs of the original node.
+    /// Returns id of the copy or `None` if the node does not exist.
+    ///
+    /// Wires from outputs of the node are not copied.
+    pub fn try_duplicate_node_with_inputs(&mut self, node: NodeId, offset: Vec2) -> Option<NodeId> {
+        let copy = self.try_duplicate_node(node, offset)?;
+
+        let wires = self
+            .incoming_wires(node)
+            .map(|(out_pin, in_pin)| Wire {
+                out_pin,
+                in_pin: InPinId {
+                    node: copy,
+                    input: in_pin.input,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        for wire in wires {
+            self.wires.insert(wire);
+        }
+
+        Some(copy)
+    }
+
+    /// Copies given nodes into a new graph along with wires between them.
+    ///
+    /// Nodes keep their positions and open state.
+    /// Copied nodes get new ids in the order they are listed, as with [`Snarl::extract_nodes`].
+    /// Ids of nodes that don't exist and repeated ids are skipped.
+    /// Wires between copied and other nodes are not copied.
+    ///
+    /// Returns new graph and mapping from ids of copied nodes to their ids in the new graph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), "a");
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), "b");
+    /// let c = snarl.insert_node(pos2(200.0, 0.0), "c");
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    /// snarl.connect(OutPinId { node: b, output: 0 }, InPinId { node: c, input: 0 });
+    ///
+    /// let (group, ids) = snarl.subgraph(&[b, c]);
+    /// assert_eq!(group[ids[&b]], "b");
+    /// assert_eq!(group.wire_count(), 1);
+    /// assert_eq!(snarl.node_count(), 3);
+    /// ```
+    pub fn subgraph(&self, nodes: &[NodeId]) -> (Snarl<T>, HashMap<NodeId, NodeId>) {
+        let mut subgraph = Snarl::new();
+        let mut ids = HashMap::with_capacity(nodes.len());
+
+        for &node in nodes {
+            if ids.contains_key(&node) {
+                continue;
+            }
+            if let Some(info) = self.nodes.get(node.0) {
+                ids.insert(node, NodeId(subgraph.nodes.insert(info.clone())));
+            }
+        }
+
+        for wire in self.wires.iter() {
+            if let (Some(&from), Some(&to)) =
+                (ids.get(&wire.out_pin.node), ids.get(&wire.in_pin.node))
+            {
+                subgraph.wires.insert(Wire {
+                    out_pin: OutPinId {
+                        node: from,
+                        output: wire.out_pin.output,
+                    },
+                    in_pin: InPinId {
+                        node: to,
+                        input: wire.in_pin.input,
+                    },
+                });
+            }
+        }
+
+        (subgraph, ids)
+    }
+
+    /// Inserts copies of given nodes with positions translated by `offset`
+    /// and copies wires between them.
+    ///
+    /// Value and open state are cloned.
+    /// Wires between copied and other nodes are not copied.
+    /// Ids of nodes that don't exist and repeated ids are skipped.
+    ///
+    /// Returns mapping from ids of copied nodes to ids of their copies.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::{pos2, vec2};
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), "a");
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), "b");
+    /// let c = snarl.insert_node(pos2(200.0, 0.0), "c");
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    /// snarl.connect(OutPinId { node: b, output: 0 }, InPinId { node: c, input: 0 });
+    ///
+    /// let ids = snarl.duplicate_nodes(&[b, c], vec2(0.0, 100.0));
+    /// assert_eq!(snarl[ids[&b]], "b");
+    /// assert_eq!(snarl.wire_count(), 3);
+    /// assert!(snarl.connected(
+    ///     OutPinId { node: ids[&b], output: 0 },
+    ///     InPinId { node: ids[&c], input: 0 },
+    /// ));
+    /// ```
+    pub fn duplicate_nodes(&mut self, nodes: &[NodeId], offset: Vec2) -> HashMap<NodeId, NodeId> {
+        let mut ids = HashMap::with_capacity(nodes.len());
+        for &node in nodes {
+            if ids.contains_key(&node) {
+                continue;
+            }
+            if let Some(copy) = self.try_duplicate_node(node, offset) {
+                ids.insert(node, copy);
+            }
+        }
+
+        let wires = self
+            .wires
+            .iter()
+            .filter_map(|wire| {
+                Some(Wire {
+                    out_pin: OutPinId {
+                        node: *ids.get(&wire.out_pin.node)?,
+                        output: wire.out_pin.output,
+                    },
+                    in_pin: InPinId {
+                        node: *ids.get(&wire.in_pin.node)?,
+                        input: wire.in_pin.input,
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for wire in wires {
+            self.wires.insert(wire);
+        }
+
+        ids
+    }
+}
@@ -0,0 +1,1737 @@
+use std::collections::HashMap;
+
+use emath::{pos2, vec2, Pos2};
+
+use crate::{ConnectError, InPinId, NodeId, OutPinId, Snarl};
+
+/// Simple deterministic pseudo-random generator.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+
+        #[allow(clippy::cast_possible_truncation)]
+        let value = (self.0 >> 33) as usize;
+        value % bound
+    }
+}
+
+/// Generates snarl with nodes keyed by `u64` and random wires.
+fn random_snarl(rng: &mut Lcg) -> Snarl<u64> {
+    let mut snarl = Snarl::new();
+
+    let nodes = (0..1 + rng.next(10))
+        .map(|i| snarl.insert_node(pos2(0.0, 0.0), 1000 + i as u64))
+        .collect::<Vec<_>>();
+
+    for _ in 0..rng.next(30) {
+        let from = OutPinId {
+            node: nodes[rng.next(nodes.len())],
+            output: rng.next(3),
+        };
+        let to = InPinId {
+            node: nodes[rng.next(nodes.len())],
+            input: rng.next(3),
+        };
+        snarl.connect(from, to);
+    }
+
+    snarl
+}
+
+#[test]
+fn export_import_wires_by_key_roundtrip() {
+    let mut rng = Lcg(42);
+
+    for _ in 0..100 {
+        let snarl = random_snarl(&mut rng);
+        let wires = snarl.export_wires_by_key(|_, key| *key);
+
+        // Same nodes inserted in reverse order get different ids.
+        let mut keys = snarl.nodes().copied().collect::<Vec<_>>();
+        keys.reverse();
+
+        let mut imported = Snarl::new();
+        let ids = keys
+            .iter()
+            .map(|key| (*key, imported.insert_node(pos2(0.0, 0.0), *key)))
+            .collect::<HashMap<u64, NodeId>>();
+
+        let report = imported.import_wires_by_key(&wires, |key| ids.get(key).copied());
+
+        assert_eq!(report.connected, wires.len());
+        assert_eq!(report.existing, 0);
+        assert!(report.unknown_keys.is_empty());
+        assert!(report.missing_nodes.is_empty());
+
+        let mut reexported = imported.export_wires_by_key(|_, key| *key);
+        let mut wires = wires;
+        reexported.sort_unstable();
+        wires.sort_unstable();
+        assert_eq!(reexported, wires);
+    }
+}
+
+#[test]
+#[cfg(feature = "ui")]
+fn node_pins_match_single_pin_queries() {
+    let mut rng = Lcg(7);
+
+    for _ in 0..100 {
+        let snarl = random_snarl(&mut rng);
+
+        for (node, _) in &snarl {
+            // Wires to pins past the counts are ignored.
+            let (inputs, outputs) = snarl.node_pins(node, 2, 3);
+            assert_eq!(inputs.len(), 2);
+            assert_eq!(outputs.len(), 3);
+
+            for pin in inputs {
+                let mut expected = snarl.in_pin(pin.id).remotes;
+                let mut remotes = pin.remotes;
+                expected.sort_unstable();
+                remotes.sort_unstable();
+                assert_eq!(remotes, expected);
+            }
+
+            for pin in outputs {
+                let mut expected = snarl.out_pin(pin.id).remotes;
+                let mut remotes = pin.remotes;
+                expected.sort_unstable();
+                remotes.sort_unstable();
+                assert_eq!(remotes, expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn try_connect_reports_missing_end() {
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), 0);
+    let b = snarl.insert_node(pos2(100.0, 0.0), 1);
+
+    let from = OutPinId { node: a, output: 0 };
+    let to = InPinId { node: b, input: 0 };
+    assert_eq!(snarl.try_connect(from, to), Ok(true));
+    assert_eq!(snarl.try_connect(from, to), Ok(false));
+
+    snarl.remove_node(b);
+    assert_eq!(
+        snarl.try_connect(from, to),
+        Err(ConnectError::MissingTarget { pin: to })
+    );
+    assert_eq!(
+        snarl.try_disconnect(from, to),
+        Err(ConnectError::MissingTarget { pin: to })
+    );
+
+    // Source is checked first.
+    snarl.remove_node(a);
+    assert_eq!(
+        snarl.try_connect(from, to),
+        Err(ConnectError::MissingSource { pin: from })
+    );
+    assert_eq!(snarl.counts().wires, 0);
+}
+
+#[test]
+fn fallible_methods_reject_removed_node() {
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), 0);
+    let b = snarl.insert_node(pos2(100.0, 0.0), 1);
+
+    let from = OutPinId { node: a, output: 0 };
+    let to = InPinId { node: b, input: 0 };
+    snarl.connect(from, to);
+
+    assert_eq!(snarl.try_remove_node(a), Some(0));
+    assert_eq!(snarl.try_remove_node(a), None);
+
+    // Old id of the removed node is rejected, other node is intact.
+    assert_eq!(
+        snarl.try_connect(from, to),
+        Err(ConnectError::MissingSource { pin: from })
+    );
+    assert_eq!(snarl.try_drop_outputs(from), None);
+    assert_eq!(snarl.try_drop_inputs(to), Some(0));
+    assert_eq!(snarl.get_node(b), Some(&1));
+}
+
+#[test]
+fn clear_removes_everything_and_resets_ids() {
+    let mut rng = Lcg(3);
+    let mut snarl = random_snarl(&mut rng);
+    snarl.connect(
+        OutPinId {
+            node: NodeId(0),
+            output: 0,
+        },
+        InPinId {
+            node: NodeId(0),
+            input: 0,
+        },
+    );
+    assert!(!snarl.is_empty());
+
+    snarl.clear();
+    assert!(snarl.is_empty());
+    assert_eq!(snarl.counts().nodes, 0);
+    assert_eq!(snarl.wires().count(), 0);
+
+    assert_eq!(snarl.insert_node(pos2(0.0, 0.0), 0), NodeId(0));
+}
+
+#[test]
+fn counts_match_iterators() {
+    let mut rng = Lcg(5);
+    let mut snarl = random_snarl(&mut rng);
+    snarl.remove_node(NodeId(0));
+
+    assert_eq!(snarl.node_count(), snarl.nodes().count());
+    assert_eq!(snarl.wire_count(), snarl.wires().count());
+}
+
+#[test]
+fn retain_nodes_prunes_wires_of_removed_nodes() {
+    let mut snarl = Snarl::new();
+    let [a, b, c] = [0, 1, 2].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+
+    let a_to_b = (
+        OutPinId { node: a, output: 0 },
+        InPinId { node: b, input: 0 },
+    );
+    let b_to_c = (
+        OutPinId { node: b, output: 0 },
+        InPinId { node: c, input: 0 },
+    );
+    let a_to_c = (
+        OutPinId { node: a, output: 1 },
+        InPinId { node: c, input: 1 },
+    );
+    for (from, to) in [a_to_b, b_to_c, a_to_c] {
+        snarl.connect(from, to);
+    }
+
+    assert_eq!(snarl.retain_nodes(|id, _| id != b), 1);
+    assert!(snarl.get_node(b).is_none());
+    assert_eq!(snarl.wires().collect::<Vec<_>>(), [a_to_c]);
+
+    assert_eq!(snarl.retain_nodes(|_, _| true), 0);
+    assert_eq!(snarl.node_count(), 2);
+}
+
+#[test]
+fn retain_wires_keeps_nodes() {
+    let mut snarl = Snarl::new();
+    let [a, b, c] = [0, 1, 2].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    snarl.connect(out(a, 0), inp(b, 0));
+    snarl.connect(out(b, 0), inp(c, 0));
+    snarl.connect(out(a, 1), inp(c, 1));
+
+    // Drop all wires going into `c`.
+    assert_eq!(snarl.retain_wires(|_, in_pin| in_pin.node != c), 2);
+    assert_eq!(snarl.wires().collect::<Vec<_>>(), [(out(a, 0), inp(b, 0))]);
+    assert_eq!(snarl.node_count(), 3);
+
+    assert_eq!(snarl.retain_wires(|_, _| true), 0);
+    assert_eq!(snarl.retain_wires(|_, _| false), 1);
+    assert_eq!(snarl.wire_count(), 0);
+}
+
+#[test]
+fn map_keeps_ids_positions_and_wires() {
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), "a");
+    let gap = snarl.insert_node(pos2(0.0, 0.0), "gap");
+    let b = snarl.insert_node_collapsed(pos2(100.0, 50.0), "bb");
+    let c = snarl.insert_node(pos2(200.0, 0.0), "ccc");
+    snarl.remove_node(gap);
+
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+    snarl.connect(out(a, 0), inp(b, 0));
+    snarl.connect(out(b, 1), inp(c, 2));
+
+    let mut wires = snarl.wires().collect::<Vec<_>>();
+    wires.sort();
+    fn layout<T>(snarl: &Snarl<T>) -> Vec<(NodeId, Pos2, bool)> {
+        snarl
+            .node_ids()
+            .map(|(id, _)| {
+                let node = snarl.get_node_info(id).unwrap();
+                (id, node.pos, node.open)
+            })
+            .collect()
+    }
+    let nodes = layout(&snarl);
+
+    let lengths = snarl.clone().map(|_, value| value.len());
+
+    assert_eq!(layout(&lengths), nodes);
+    assert_eq!((lengths[a], lengths[b], lengths[c]), (1, 2, 3));
+    let mut mapped_wires = lengths.wires().collect::<Vec<_>>();
+    mapped_wires.sort();
+    assert_eq!(mapped_wires, wires);
+
+    // Error aborts the conversion.
+    let result = snarl.try_map(|id, value| if id == c { Err(id) } else { Ok(value.len()) });
+    assert_eq!(result.err(), Some(c));
+}
+
+#[test]
+fn map_ref_round_trips_ids_and_wires() {
+    let mut snarl = Snarl::new();
+    let [a, b, c, d] = [1, 2, 3, 4].map(|i| snarl.insert_node(pos2(i as f32, 0.0), i));
+    snarl.remove_node(b);
+
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+    snarl.connect(out(a, 0), inp(c, 0));
+    snarl.connect(out(c, 0), inp(d, 1));
+
+    let strings = snarl.map_ref(|_, value| value.to_string());
+
+    let ids = |snarl: &Snarl<_>| snarl.node_ids().map(|(id, _)| id).collect::<Vec<_>>();
+    assert_eq!(ids(&snarl), [a, c, d]);
+    assert_eq!(
+        strings.node_ids().map(|(id, _)| id).collect::<Vec<_>>(),
+        [a, c, d]
+    );
+    assert_eq!((&*strings[a], &*strings[c], &*strings[d]), ("1", "3", "4"));
+    assert_eq!(strings.get_node_info(d).unwrap().pos, pos2(4.0, 0.0));
+
+    let mut wires = snarl.wires().collect::<Vec<_>>();
+    let mut mapped_wires = strings.wires().collect::<Vec<_>>();
+    wires.sort();
+    mapped_wires.sort();
+    assert_eq!(mapped_wires, wires);
+
+    // Mapping back restores the original graph.
+    let numbers = strings.map(|_, value| value.parse::<i32>().unwrap());
+    assert_eq!(ids(&numbers), [a, c, d]);
+    assert_eq!((numbers[a], numbers[c], numbers[d]), (1, 3, 4));
+    assert_eq!(numbers.wire_count(), 2);
+}
+
+#[test]
+fn pin_wires_count_fan_in_and_fan_out() {
+    let mut snarl = Snarl::new();
+    let [a, b, c] = [0, 1, 2].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    snarl.connect(out(a, 0), inp(b, 0));
+    snarl.connect(out(a, 0), inp(c, 0));
+    snarl.connect(out(b, 0), inp(c, 0));
+    snarl.connect(out(b, 1), inp(c, 1));
+
+    let mut fan_in = snarl.in_wires(inp(c, 0)).collect::<Vec<_>>();
+    fan_in.sort();
+    assert_eq!(fan_in, [(out(a, 0), inp(c, 0)), (out(b, 0), inp(c, 0))]);
+
+    let mut fan_out = snarl.out_wires(out(a, 0)).collect::<Vec<_>>();
+    fan_out.sort();
+    assert_eq!(fan_out, [(out(a, 0), inp(b, 0)), (out(a, 0), inp(c, 0))]);
+
+    assert_eq!(snarl.in_wires(inp(a, 0)).count(), 0);
+    assert_eq!(snarl.out_wires(out(c, 0)).count(), 0);
+    assert_eq!(snarl.wires_of(b).count(), 3);
+}
+
+#[test]
+fn connected_checks_exact_wire() {
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(100.0, 0.0), ());
+    snarl.connect(out(a, 0), inp(b, 1));
+
+    assert!(snarl.connected(out(a, 0), inp(b, 1)));
+    assert!(!snarl.connected(out(a, 1), inp(b, 1)));
+    assert!(!snarl.connected(out(a, 0), inp(b, 0)));
+    assert!(!snarl.connected(out(b, 0), inp(a, 1)));
+    assert!(!snarl.connected(out(NodeId(7), 0), inp(b, 1)));
+
+    snarl.disconnect(out(a, 0), inp(b, 1));
+    assert!(!snarl.connected(out(a, 0), inp(b, 1)));
+}
+
+#[test]
+fn nodes_are_connected_by_any_wire() {
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(100.0, 0.0), ());
+    let c = snarl.insert_node(pos2(200.0, 0.0), ());
+    snarl.connect(out(a, 2), inp(b, 1));
+    snarl.connect(out(c, 0), inp(c, 0));
+
+    assert!(snarl.are_nodes_connected(a, b));
+    assert!(snarl.are_nodes_connected(b, a));
+    assert!(!snarl.are_nodes_connected(a, c));
+    assert!(!snarl.are_nodes_connected(a, a));
+    assert!(snarl.are_nodes_connected(c, c));
+
+    snarl.disconnect(out(a, 2), inp(b, 1));
+    assert!(!snarl.are_nodes_connected(a, b));
+}
+
+#[test]
+fn dot_lists_all_nodes_and_wires() {
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), "a");
+    let b = snarl.insert_node(pos2(100.0, 0.0), "{b|c}");
+    let c = snarl.insert_node(pos2(200.0, 0.0), "\"c\"");
+    let lonely = snarl.insert_node(pos2(300.0, 0.0), "lonely");
+    snarl.connect(out(a, 0), inp(b, 2));
+    snarl.connect(out(a, 0), inp(c, 0));
+    snarl.connect(out(b, 1), inp(c, 1));
+    snarl.connect(out(c, 0), inp(a, 0));
+
+    let dot = snarl.to_dot(|_, name| (*name).to_owned());
+
+    let body = dot
+        .strip_prefix("digraph {\n")
+        .and_then(|body| body.strip_suffix("}\n"))
+        .expect("digraph");
+
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    for line in body.lines() {
+        let statement = line.trim().strip_suffix(';').expect("statement");
+        if let Some((from, to)) = statement.split_once(" -> ") {
+            edges.push((from.to_owned(), to.to_owned()));
+        } else if let Some((name, attrs)) = statement.split_once(" [label=\"") {
+            let label = attrs.strip_suffix("\"]").expect("label");
+            nodes.push((name.to_owned(), label.to_owned()));
+        }
+    }
+
+    assert_eq!(
+        nodes,
+        [
+            ("node0".to_owned(), "{{<in0> 0}|a|{<out0> 0}}".to_owned()),
+            (
+                "node1".to_owned(),
+                "{{<in2> 2}|\\{b\\|c\\}|{<out1> 1}}".to_owned()
+            ),
+            (
+                "node2".to_owned(),
+                "{{<in0> 0|<in1> 1}|\\\"c\\\"|{<out0> 0}}".to_owned()
+            ),
+            (format!("node{}", lonely.0), "{lonely}".to_owned()),
+        ]
+    );
+
+    let mut expected = snarl
+        .wires()
+        .map(|(out_pin, in_pin)| {
+            (
+                format!("node{}:out{}", out_pin.node.0, out_pin.output),
+                format!("node{}:in{}", in_pin.node.0, in_pin.input),
+            )
+        })
+        .collect::<Vec<_>>();
+    expected.sort();
+    edges.sort();
+    assert_eq!(edges, expected);
+}
+
+#[test]
+fn connect_many_counts_only_new_wires() {
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(100.0, 0.0), ());
+    let c = snarl.insert_node(pos2(200.0, 0.0), ());
+    snarl.connect(out(a, 0), inp(b, 0));
+    snarl.remove_node(c);
+
+    let created = snarl.connect_many([
+        (out(a, 0), inp(b, 0)),
+        (out(a, 0), inp(b, 1)),
+        (out(a, 0), inp(b, 1)),
+        (out(a, 1), inp(c, 0)),
+        (out(c, 0), inp(b, 2)),
+        (out(b, 0), inp(a, 0)),
+    ]);
+    assert_eq!(created, 2);
+    assert_eq!(snarl.wire_count(), 3);
+
+    let removed = snarl.disconnect_many([
+        (out(a, 0), inp(b, 0)),
+        (out(a, 0), inp(b, 0)),
+        (out(a, 0), inp(b, 2)),
+        (out(c, 0), inp(b, 1)),
+        (out(b, 0), inp(a, 0)),
+    ]);
+    assert_eq!(removed, 2);
+    assert_eq!(snarl.wires().collect::<Vec<_>>(), [(out(a, 0), inp(b, 1))]);
+}
+
+#[test]
+fn degrees_count_wires() {
+    let mut snarl = Snarl::new();
+    let [a, b, c] = [0, 1, 2].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    // Two outputs connected to one input.
+    snarl.connect(out(a, 0), inp(c, 0));
+    snarl.connect(out(b, 0), inp(c, 0));
+    snarl.connect(out(b, 1), inp(c, 1));
+
+    assert_eq!(snarl.in_pin_degree(inp(c, 0)), 2);
+    assert_eq!(snarl.in_pin_degree(inp(c, 1)), 1);
+    assert_eq!(snarl.in_pin_degree(inp(a, 0)), 0);
+    assert_eq!(snarl.out_pin_degree(out(b, 0)), 1);
+    assert_eq!(snarl.out_pin_degree(out(c, 0)), 0);
+
+    assert_eq!(snarl.node_in_degree(c), 3);
+    assert_eq!(snarl.node_out_degree(b), 2);
+    assert_eq!(snarl.node_in_degree(a), 0);
+    assert_eq!(snarl.node_out_degree(c), 0);
+}
+
+#[test]
+fn remap_pins_moves_drops_and_merges_wires() {
+    let mut snarl = Snarl::new();
+    let [a, b, c] = [0, 1, 2].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    snarl.connect(out(a, 0), inp(b, 0));
+    snarl.connect(out(a, 0), inp(b, 1));
+    snarl.connect(out(a, 1), inp(b, 2));
+    snarl.connect(out(b, 0), inp(c, 0));
+    snarl.connect(out(a, 0), inp(c, 1));
+
+    // Inputs 0 and 1 of `b` are merged, input 2 is removed.
+    snarl.remap_in_pins(b, |input| (input < 2).then_some(0));
+
+    let mut wires = snarl.wires().collect::<Vec<_>>();
+    wires.sort();
+    assert_eq!(
+        wires,
+        [
+            (out(a, 0), inp(b, 0)),
+            (out(a, 0), inp(c, 1)),
+            (out(b, 0), inp(c, 0)),
+        ]
+    );
+
+    // Outputs of `a` are swapped, wires of other nodes are untouched.
+    snarl.remap_out_pins(a, |output| Some(1 - output));
+
+    let mut wires = snarl.wires().collect::<Vec<_>>();
+    wires.sort();
+    assert_eq!(
+        wires,
+        [
+            (out(a, 1), inp(b, 0)),
+            (out(a, 1), inp(c, 1)),
+            (out(b, 0), inp(c, 0)),
+        ]
+    );
+}
+
+#[test]
+fn remap_inputs_by_key_drops_and_reorders_wires() {
+    let mut snarl = Snarl::new();
+    let [a, b, c] = [0, 1, 2].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    snarl.connect(out(a, 0), inp(b, 0));
+    snarl.connect(out(a, 1), inp(b, 1));
+    snarl.connect(out(a, 2), inp(b, 2));
+    snarl.connect(out(a, 3), inp(b, 3));
+    snarl.connect(out(a, 0), inp(c, 0));
+
+    // Key "y" is dropped, "x" and "z" are swapped, input 3 has no key.
+    snarl.remap_inputs_by_key(b, &["x", "y", "z"], &["z", "w", "x"]);
+
+    let mut wires = snarl.wires().collect::<Vec<_>>();
+    wires.sort();
+    assert_eq!(
+        wires,
+        [
+            (out(a, 0), inp(b, 2)),
+            (out(a, 0), inp(c, 0)),
+            (out(a, 2), inp(b, 0)),
+            (out(a, 3), inp(b, 3)),
+        ]
+    );
+}
+
+#[test]
+fn insert_node_with_id_keeps_sparse_ids() {
+    let ids = [7, 2, 12, 3].map(NodeId);
+
+    let mut snarl = Snarl::new();
+    for (value, &id) in ids.iter().enumerate() {
+        assert!(snarl.insert_node_with_id(id, pos2(value as f32, 0.0), value));
+    }
+    assert!(!snarl.insert_node_with_id(ids[0], pos2(0.0, 0.0), 100));
+    assert_eq!(snarl[ids[0]], 0);
+
+    // Storage grows with the largest id, so ids far past the number of nodes are rejected.
+    assert!(!snarl.insert_node_with_id(NodeId(usize::MAX), pos2(0.0, 0.0), 100));
+    assert!(!snarl.insert_node_with_id(NodeId(1 << 20), pos2(0.0, 0.0), 100));
+    assert_eq!(snarl.node_count(), 4);
+
+    snarl.connect(
+        OutPinId {
+            node: ids[0],
+            output: 0,
+        },
+        InPinId {
+            node: ids[2],
+            input: 1,
+        },
+    );
+
+    let mut stored = snarl
+        .node_ids()
+        .map(|(id, &value)| (id, value))
+        .collect::<Vec<_>>();
+    stored.sort_unstable();
+    assert_eq!(stored, [(ids[1], 1), (ids[3], 3), (ids[0], 0), (ids[2], 2)]);
+    assert_eq!(snarl.get_node_info(ids[2]).unwrap().pos, pos2(2.0, 0.0));
+
+    // Round-trip through another graph keeps the ids.
+    let mut copy = Snarl::new();
+    for (id, &value) in snarl.node_ids() {
+        let pos = snarl.get_node_info(id).unwrap().pos;
+        assert!(copy.insert_node_with_id(id, pos, value));
+    }
+    for (out_pin, in_pin) in snarl.wires() {
+        copy.connect(out_pin, in_pin);
+    }
+    assert_eq!(
+        copy.node_ids().map(|(id, _)| id).collect::<Vec<_>>(),
+        snarl.node_ids().map(|(id, _)| id).collect::<Vec<_>>()
+    );
+    assert!(copy.wires().eq(snarl.wires()));
+
+    // Vacant ids are used by regular insertion.
+    let id = snarl.insert_node(pos2(0.0, 0.0), 4);
+    assert!(!ids.contains(&id));
+    assert_eq!(snarl.node_count(), 5);
+}
+
+#[test]
+fn would_create_cycle_in_chain() {
+    // a -> b -> c
+    let mut snarl = Snarl::new();
+    let [a, b, c] = [0, 1, 2].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    snarl.connect(out(a, 0), inp(b, 0));
+    // Second wire between the same pair of nodes.
+    snarl.connect(out(a, 1), inp(b, 1));
+    snarl.connect(out(b, 0), inp(c, 0));
+
+    assert!(snarl.would_create_cycle(out(c, 0), inp(a, 0)));
+    assert!(snarl.would_create_cycle(out(b, 0), inp(a, 0)));
+    assert!(snarl.would_create_cycle(out(b, 1), inp(b, 2)));
+
+    assert!(!snarl.would_create_cycle(out(a, 2), inp(c, 1)));
+    assert!(!snarl.would_create_cycle(out(a, 0), inp(b, 0)));
+}
+
+#[test]
+fn topological_order_sorts_dependencies() {
+    let out = |node: NodeId| OutPinId { node, output: 0 };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    // Chain c -> b -> a.
+    let mut snarl = Snarl::new();
+    let [a, b, c] = [0, 1, 2].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+    snarl.connect(out(c), inp(b, 0));
+    snarl.connect(out(b), inp(a, 0));
+    assert_eq!(snarl.topological_order(), Ok(vec![c, b, a]));
+
+    // Diamond d -> (e, f) -> g with unconnected h.
+    let mut snarl = Snarl::new();
+    let [g, f, e, d, h] = [0, 1, 2, 3, 4].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+    snarl.connect(out(d), inp(e, 0));
+    snarl.connect(out(d), inp(f, 0));
+    snarl.connect(out(e), inp(g, 0));
+    snarl.connect(out(f), inp(g, 1));
+    // Second wire between the same nodes.
+    snarl.connect(OutPinId { node: d, output: 1 }, inp(e, 1));
+
+    let order = snarl.topological_order().unwrap();
+    assert_eq!(order.len(), 5);
+    assert!(order.contains(&h));
+    let position = |node| order.iter().position(|&n| n == node).unwrap();
+    assert!(position(d) < position(e) && position(d) < position(f));
+    assert!(position(e) < position(g) && position(f) < position(g));
+
+    // Cycle i -> j -> k -> j, node i is not part of the cycle.
+    let mut snarl = Snarl::new();
+    let [i, j, k] = [0, 1, 2].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+    snarl.connect(out(i), inp(j, 0));
+    snarl.connect(out(j), inp(k, 0));
+    snarl.connect(out(k), inp(j, 1));
+
+    assert_eq!(snarl.topological_order().unwrap_err().nodes, [j, k]);
+}
+
+#[test]
+fn topological_order_reports_cycle_nodes() {
+    let out = |node: NodeId| OutPinId { node, output: 0 };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    // DAG a -> b -> d, a -> c -> d in one component and e -> f in another, with unconnected g.
+    let mut snarl = Snarl::new();
+    let [a, b, c, d, e, f, g] = [0, 1, 2, 3, 4, 5, 6].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+    snarl.connect(out(a), inp(b, 0));
+    snarl.connect(out(a), inp(c, 0));
+    snarl.connect(out(b), inp(d, 0));
+    snarl.connect(out(c), inp(d, 1));
+    snarl.connect(out(e), inp(f, 0));
+
+    let order = snarl.topological_order().unwrap();
+    assert_eq!(order.len(), 7);
+    assert!(order.contains(&g));
+    let position = |node| order.iter().position(|&n| n == node).unwrap();
+    assert!(position(a) < position(b) && position(a) < position(c));
+    assert!(position(b) < position(d) && position(c) < position(d));
+    assert!(position(e) < position(f));
+
+    // Self-loop.
+    let mut snarl = Snarl::new();
+    let [a, b] = [0, 1].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+    snarl.connect(out(a), inp(b, 0));
+    snarl.connect(out(b), inp(b, 1));
+    assert_eq!(snarl.topological_order().unwrap_err().nodes, [b]);
+
+    // Two-node cycle b <-> c fed by a and feeding d.
+    let mut snarl = Snarl::new();
+    let [a, b, c, d] = [0, 1, 2, 3].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+    snarl.connect(out(a), inp(b, 0));
+    snarl.connect(out(b), inp(c, 0));
+    snarl.connect(out(c), inp(b, 1));
+    snarl.connect(out(c), inp(d, 0));
+    assert_eq!(snarl.topological_order().unwrap_err().nodes, [b, c]);
+}
+
+#[test]
+fn neighbors_are_deduplicated() {
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+    let sorted = |nodes: &mut dyn Iterator<Item = NodeId>| {
+        let mut nodes = nodes.collect::<Vec<_>>();
+        nodes.sort_unstable();
+        nodes
+    };
+
+    // a feeds b twice, b feeds a back, b feeds c and itself, d is unconnected.
+    let mut snarl = Snarl::new();
+    let [a, b, c, d] = [0, 1, 2, 3].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+    snarl.connect(out(a, 0), inp(b, 0));
+    snarl.connect(out(a, 1), inp(b, 1));
+    snarl.connect(out(b, 0), inp(a, 0));
+    snarl.connect(out(b, 1), inp(c, 0));
+    snarl.connect(out(b, 2), inp(b, 2));
+
+    assert_eq!(sorted(&mut snarl.neighbors(a)), [b]);
+    assert_eq!(sorted(&mut snarl.neighbors(b)), [a, b, c]);
+    assert_eq!(sorted(&mut snarl.successors(b)), [a, b, c]);
+    assert_eq!(sorted(&mut snarl.predecessors(b)), [a, b]);
+    assert_eq!(sorted(&mut snarl.predecessors(c)), [b]);
+    assert_eq!(sorted(&mut snarl.successors(c)), []);
+    assert_eq!(sorted(&mut snarl.neighbors(d)), []);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn neighbors_ignore_dangling_wires() {
+    let json = concat!(
+        r#"{"nodes":{"#,
+        r#""0":{"value":10,"pos":{"x":0.0,"y":0.0},"open":true},"#,
+        r#""2":{"value":20,"pos":{"x":100.0,"y":0.0},"open":true}},"#,
+        r#""wires":["#,
+        r#"{"out_pin":{"node":0,"output":0},"in_pin":{"node":2,"input":0}},"#,
+        r#"{"out_pin":{"node":0,"output":1},"in_pin":{"node":7,"input":0}},"#,
+        r#"{"out_pin":{"node":7,"output":0},"in_pin":{"node":2,"input":1}}"#,
+        r#"]}"#,
+    );
+    let snarl = serde_json::from_str::<Snarl<i32>>(json).unwrap();
+
+    assert!(snarl.neighbors(NodeId(0)).eq([NodeId(2)]));
+    assert!(snarl.neighbors(NodeId(2)).eq([NodeId(0)]));
+    assert!(snarl.successors(NodeId(0)).eq([NodeId(2)]));
+    assert!(snarl.predecessors(NodeId(2)).eq([NodeId(0)]));
+    assert_eq!(snarl.neighbors(NodeId(7)).count(), 0);
+}
+
+#[test]
+fn ancestors_and_descendants_visit_each_node_once() {
+    let out = |node: NodeId| OutPinId { node, output: 0 };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+    let sorted = |nodes: &mut dyn Iterator<Item = NodeId>| {
+        let mut nodes = nodes.collect::<Vec<_>>();
+        nodes.sort_unstable();
+        nodes
+    };
+
+    // Diamond a -> (b, c) -> d with unconnected e.
+    let mut snarl = Snarl::new();
+    let [a, b, c, d, e] = [0, 1, 2, 3, 4].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+    snarl.connect(out(a), inp(b, 0));
+    snarl.connect(out(a), inp(c, 0));
+    snarl.connect(out(b), inp(d, 0));
+    snarl.connect(out(c), inp(d, 1));
+
+    assert_eq!(sorted(&mut snarl.descendants(a)), [b, c, d]);
+    assert_eq!(sorted(&mut snarl.ancestors(d)), [a, b, c]);
+    assert_eq!(sorted(&mut snarl.ancestors(b)), [a]);
+    assert_eq!(sorted(&mut snarl.descendants(d)), []);
+    assert_eq!(sorted(&mut snarl.descendants(e)), []);
+
+    // Breadth-first order.
+    let order = snarl.descendants(a).collect::<Vec<_>>();
+    assert_eq!(order[2], d);
+
+    // Cycle a -> b -> c -> a with d fed by c.
+    let mut snarl = Snarl::new();
+    let [a, b, c, d] = [0, 1, 2, 3].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+    snarl.connect(out(a), inp(b, 0));
+    snarl.connect(out(b), inp(c, 0));
+    snarl.connect(out(c), inp(a, 0));
+    snarl.connect(out(c), inp(d, 0));
+
+    assert_eq!(sorted(&mut snarl.descendants(a)), [b, c, d]);
+    assert_eq!(sorted(&mut snarl.ancestors(a)), [b, c]);
+    assert_eq!(sorted(&mut snarl.ancestors(d)), [a, b, c]);
+}
+
+#[test]
+fn compact_makes_ids_contiguous() {
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    let mut snarl = Snarl::new();
+    let ids = (0..6)
+        .map(|i| snarl.insert_node(pos2(i as f32 * 100.0, 0.0), i))
+        .collect::<Vec<_>>();
+    snarl.connect(out(ids[0], 0), inp(ids[3], 1));
+    snarl.connect(out(ids[3], 0), inp(ids[5], 0));
+    snarl.connect(out(ids[5], 2), inp(ids[0], 0));
+    snarl.connect(out(ids[1], 0), inp(ids[2], 0));
+    snarl.remove_node(ids[1]);
+    snarl.remove_node(ids[2]);
+    snarl.remove_node(ids[4]);
+
+    let map = snarl.compact();
+    assert_eq!(map.len(), 3);
+    assert_eq!(map[&ids[0]], NodeId(0));
+    assert_eq!(map[&ids[3]], NodeId(1));
+    assert_eq!(map[&ids[5]], NodeId(2));
+
+    assert_eq!(
+        snarl.node_ids().map(|(id, _)| id).collect::<Vec<_>>(),
+        (0..snarl.node_count()).map(NodeId).collect::<Vec<_>>()
+    );
+    assert_eq!(snarl[NodeId(1)], 3);
+    assert_eq!(
+        snarl.get_node_info(NodeId(2)).unwrap().pos,
+        pos2(500.0, 0.0)
+    );
+
+    let mut wires = snarl.wires().collect::<Vec<_>>();
+    wires.sort();
+    assert_eq!(
+        wires,
+        [
+            (out(NodeId(0), 0), inp(NodeId(1), 1)),
+            (out(NodeId(1), 0), inp(NodeId(2), 0)),
+            (out(NodeId(2), 2), inp(NodeId(0), 0)),
+        ]
+    );
+
+    // New nodes are appended after compacted ones.
+    assert_eq!(snarl.insert_node(pos2(0.0, 0.0), 6), NodeId(3));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn connected_components_ignore_dangling_wires() {
+    let json = concat!(
+        r#"{"nodes":{"#,
+        r#""0":{"value":10,"pos":{"x":0.0,"y":0.0},"open":true},"#,
+        r#""2":{"value":20,"pos":{"x":100.0,"y":0.0},"open":true}},"#,
+        r#""wires":["#,
+        r#"{"out_pin":{"node":0,"output":0},"in_pin":{"node":7,"input":0}},"#,
+        r#"{"out_pin":{"node":7,"output":0},"in_pin":{"node":2,"input":0}}"#,
+        r#"]}"#,
+    );
+    let snarl = serde_json::from_str::<Snarl<i32>>(json).unwrap();
+
+    // Nodes are not connected through the missing node.
+    assert_eq!(
+        snarl.connected_components(),
+        [vec![NodeId(0)], vec![NodeId(2)]]
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn compact_drops_dangling_wires() {
+    let json = concat!(
+        r#"{"nodes":{"#,
+        r#""0":{"value":10,"pos":{"x":0.0,"y":0.0},"open":true},"#,
+        r#""2":{"value":20,"pos":{"x":100.0,"y":0.0},"open":true}},"#,
+        r#""wires":["#,
+        r#"{"out_pin":{"node":0,"output":0},"in_pin":{"node":2,"input":0}},"#,
+        r#"{"out_pin":{"node":2,"output":1},"in_pin":{"node":5,"input":0}},"#,
+        r#"{"out_pin":{"node":1,"output":0},"in_pin":{"node":0,"input":3}}"#,
+        r#"]}"#,
+    );
+    let mut snarl = serde_json::from_str::<Snarl<i32>>(json).unwrap();
+
+    let map = snarl.compact();
+    assert_eq!(map.len(), 2);
+    assert_eq!(map[&NodeId(2)], NodeId(1));
+    assert_eq!(
+        snarl.wires().collect::<Vec<_>>(),
+        [(
+            OutPinId {
+                node: NodeId(0),
+                output: 0
+            },
+            InPinId {
+                node: NodeId(1),
+                input: 0
+            }
+        )]
+    );
+    assert_eq!(snarl.validate(), Ok(()));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn inserted_snarl_drops_dangling_wires() {
+    let json = concat!(
+        r#"{"nodes":{"#,
+        r#""0":{"value":10,"pos":{"x":0.0,"y":0.0},"open":true},"#,
+        r#""2":{"value":20,"pos":{"x":100.0,"y":0.0},"open":true}},"#,
+        r#""wires":["#,
+        r#"{"out_pin":{"node":0,"output":0},"in_pin":{"node":2,"input":0}},"#,
+        r#"{"out_pin":{"node":2,"output":1},"in_pin":{"node":5,"input":0}},"#,
+        r#"{"out_pin":{"node":1,"output":0},"in_pin":{"node":0,"input":3}}"#,
+        r#"]}"#,
+    );
+    let other = serde_json::from_str::<Snarl<i32>>(json).unwrap();
+
+    let mut snarl = Snarl::new();
+    let x = snarl.insert_node(pos2(0.0, 0.0), 0);
+    let ids = snarl.insert_snarl(other.clone(), vec2(0.0, 0.0));
+    assert_eq!(ids.len(), 2);
+    assert_eq!(
+        snarl.wires().collect::<Vec<_>>(),
+        [(
+            OutPinId {
+                node: ids[&NodeId(0)],
+                output: 0
+            },
+            InPinId {
+                node: ids[&NodeId(2)],
+                input: 0
+            }
+        )]
+    );
+
+    snarl.remove_node(x);
+    let ids = snarl.merge(other, vec2(0.0, 0.0));
+    assert_eq!(ids.len(), 2);
+    assert_eq!(snarl.wire_count(), 2);
+    assert_eq!(snarl.validate(), Ok(()));
+}
+
+#[test]
+fn subgraph_copies_only_wires_inside_the_set() {
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), 'a');
+    let b = snarl.insert_node(pos2(100.0, 0.0), 'b');
+    let c = snarl.insert_node_collapsed(pos2(200.0, 0.0), 'c');
+    let d = snarl.insert_node(pos2(300.0, 0.0), 'd');
+    snarl.connect(out(a, 0), inp(b, 0));
+    snarl.connect(out(b, 0), inp(c, 0));
+    snarl.connect(out(c, 0), inp(d, 0));
+    snarl.remove_node(a);
+
+    let (group, ids) = snarl.subgraph(&[c, b, c, NodeId(42)]);
+    assert_eq!(ids.len(), 2);
+    assert_eq!(ids[&c], NodeId(0));
+    assert_eq!(ids[&b], NodeId(1));
+    assert_eq!(group[NodeId(0)], 'c');
+    assert!(!group.get_node_info(NodeId(0)).unwrap().open);
+    assert_eq!(
+        group.get_node_info(NodeId(1)).unwrap().pos,
+        pos2(100.0, 0.0)
+    );
+    assert_eq!(
+        group.wires().collect::<Vec<_>>(),
+        [(out(NodeId(1), 0), inp(NodeId(0), 0))]
+    );
+
+    // Source graph is untouched.
+    assert_eq!(snarl.node_count(), 3);
+    assert_eq!(snarl.wire_count(), 2);
+}
+
+#[test]
+fn duplicated_group_keeps_only_internal_wires() {
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), 'a');
+    let b = snarl.insert_node(pos2(100.0, 0.0), 'b');
+    let c = snarl.insert_node_collapsed(pos2(200.0, 0.0), 'c');
+    let d = snarl.insert_node(pos2(300.0, 0.0), 'd');
+    snarl.connect(out(a, 0), inp(b, 0));
+    snarl.connect(out(b, 0), inp(c, 0));
+    snarl.connect(out(b, 1), inp(c, 1));
+    snarl.connect(out(c, 0), inp(b, 1));
+    snarl.connect(out(c, 0), inp(d, 0));
+
+    let ids = snarl.duplicate_nodes(&[b, c, b, NodeId(42)], vec2(0.0, 100.0));
+    assert_eq!(ids.len(), 2);
+    assert_eq!(snarl.node_count(), 6);
+    assert_eq!(snarl[ids[&b]], 'b');
+    assert_eq!(
+        snarl.get_node_info(ids[&b]).unwrap().pos,
+        pos2(100.0, 100.0)
+    );
+    assert!(!snarl.get_node_info(ids[&c]).unwrap().open);
+
+    let (b2, c2) = (ids[&b], ids[&c]);
+    let mut copied = snarl
+        .wires()
+        .filter(|(from, to)| from.node == b2 || from.node == c2 || to.node == b2 || to.node == c2)
+        .collect::<Vec<_>>();
+    copied.sort();
+    let mut expected = vec![
+        (out(b2, 0), inp(c2, 0)),
+        (out(b2, 1), inp(c2, 1)),
+        (out(c2, 0), inp(b2, 1)),
+    ];
+    expected.sort();
+    assert_eq!(copied, expected);
+    assert_eq!(snarl.wire_count(), 8);
+}
+
+#[test]
+fn duplicated_node_has_own_value_and_wires() {
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), vec![1]);
+    let b = snarl.insert_node_collapsed(pos2(100.0, 0.0), vec![2]);
+    let c = snarl.insert_node(pos2(200.0, 0.0), vec![3]);
+    snarl.connect(out(a, 0), inp(b, 0));
+    snarl.connect(out(a, 1), inp(b, 2));
+    snarl.connect(out(b, 0), inp(c, 0));
+
+    let plain = snarl.duplicate_node(b, vec2(0.0, 50.0));
+    let info = snarl.get_node_info(plain).unwrap();
+    assert_eq!(info.pos, pos2(100.0, 50.0));
+    assert!(!info.open);
+    assert_eq!(snarl.wires_of(plain).count(), 0);
+
+    let copy = snarl.duplicate_node_with_inputs(b, vec2(0.0, 100.0));
+    let mut inputs = snarl.incoming_wires(copy).collect::<Vec<_>>();
+    inputs.sort();
+    assert_eq!(
+        inputs,
+        [(out(a, 0), inp(copy, 0)), (out(a, 1), inp(copy, 2))]
+    );
+    assert_eq!(snarl.outgoing_wires(copy).count(), 0);
+
+    // Wires of the copy are its own.
+    snarl.drop_inputs(inp(copy, 0));
+    assert_eq!(snarl.in_pin_degree(inp(b, 0)), 1);
+
+    // Values are independent.
+    snarl[b].push(20);
+    assert_eq!(snarl[copy], [2]);
+    assert_eq!(snarl[plain], [2]);
+
+    assert_eq!(snarl.try_duplicate_node(NodeId(100), vec2(0.0, 0.0)), None);
+    assert_eq!(
+        snarl.try_duplicate_node_with_inputs(NodeId(100), vec2(0.0, 0.0)),
+        None
+    );
+    assert_eq!(snarl.node_count(), 5);
+}
+
+#[test]
+fn extract_nodes_remaps_ids_and_reports_severed_wires() {
+    let out = |node: NodeId, output: usize| OutPinId { node, output };
+    let inp = |node: NodeId, input: usize| InPinId { node, input };
+
+    let mut snarl = Snarl::new();
+    let [a, b, c, d] = [0, 1, 2, 3].map(|i| snarl.insert_node(pos2(i as f32, 0.0), i));
+    snarl.open_node(c, false);
+
+    // a -> b -> d -> c, a -> c and loop on d.
+    snarl.connect(out(a, 0), inp(b, 0));
+    snarl.connect(out(b, 0), inp(d, 0));
+    snarl.connect(out(d, 1), inp(c, 1));
+    snarl.connect(out(a, 1), inp(c, 0));
+    snarl.connect(out(d, 0), inp(d, 1));
+
+    // Missing and repeated ids are skipped.
+    let (extracted, severed) = snarl.extract_nodes(&[d, NodeId(10), c, d]);
+
+    let new_d = NodeId(0);
+    let new_c = NodeId(1);
+    assert_eq!(extracted.node_count(), 2);
+    assert_eq!(extracted[new_d], 3);
+    assert_eq!(extracted[new_c], 2);
+    assert_eq!(extracted.get_node_info(new_d).unwrap().pos, pos2(3.0, 0.0));
+    assert!(!extracted.get_node_info(new_c).unwrap().open);
+
+    let mut wires = extracted.wires().collect::<Vec<_>>();
+    wires.sort_unstable();
+    assert_eq!(
+        wires,
+        [
+            (out(new_d, 0), inp(new_d, 1)),
+            (out(new_d, 1), inp(new_c, 1)),
+        ]
+    );
+
+    assert_eq!(severed, [(out(a, 1), inp(c, 0)), (out(b, 0), inp(d, 0))]);
+
+    assert_eq!(snarl.node_count(), 2);
+    assert_eq!(snarl.wires().collect::<Vec<_>>(), [(out(a, 0), inp(b, 0))]);
+}
+
+#[test]
+fn connected_components_split_when_link_is_removed() {
+    let out = |node: NodeId| OutPinId { node, output: 0 };
+    let inp = |node: NodeId| InPinId { node, input: 0 };
+
+    // Chains a -> b -> c and d -> e, plus unconnected f.
+    let mut snarl = Snarl::new();
+    let [a, b, c, d, e, f] = [0, 1, 2, 3, 4, 5].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+    snarl.connect(out(a), inp(b));
+    snarl.connect(out(b), inp(c));
+    snarl.connect(out(e), inp(d));
+
+    assert_eq!(
+        snarl.connected_components(),
+        [vec![a, b, c], vec![d, e], vec![f]]
+    );
+
+    // Link chains, then split the first one.
+    snarl.connect(out(c), inp(e));
+    assert_eq!(snarl.connected_components(), [vec![a, b, c, d, e], vec![f]]);
+
+    snarl.disconnect(out(a), inp(b));
+    let components = snarl.connected_components();
+    assert_eq!(components, [vec![a], vec![b, c, d, e], vec![f]]);
+    assert_eq!(
+        components.iter().map(Vec::len).sum::<usize>(),
+        snarl.node_count()
+    );
+}
+
+#[test]
+fn merge_keeps_internal_wires() {
+    let mut snarl = Snarl::new();
+    let x = snarl.insert_node(pos2(0.0, 0.0), "x");
+    let y = snarl.insert_node(pos2(0.0, 0.0), "y");
+    snarl.remove_node(x);
+
+    let mut other = Snarl::new();
+    let a = other.insert_node(pos2(0.0, 0.0), "a");
+    let b = other.insert_node(pos2(100.0, 0.0), "b");
+    other.connect(
+        OutPinId { node: a, output: 1 },
+        InPinId { node: b, input: 0 },
+    );
+
+    let ids = snarl.merge(other, vec2(5.0, 5.0));
+    assert_eq!(ids.len(), 2);
+    assert!(!ids.contains(&y));
+    assert_eq!(snarl[ids[0]], "a");
+    assert_eq!(snarl[ids[1]], "b");
+    assert_eq!(snarl.get_node_info(ids[1]).unwrap().pos, pos2(105.0, 5.0));
+    assert_eq!(
+        snarl.wires().collect::<Vec<_>>(),
+        [(
+            OutPinId {
+                node: ids[0],
+                output: 1
+            },
+            InPinId {
+                node: ids[1],
+                input: 0
+            }
+        )]
+    );
+}
+
+#[test]
+fn insert_snarl_remaps_wires_to_new_nodes() {
+    let mut rng = Lcg(7);
+    let mut snarl = random_snarl(&mut rng);
+    // Leave a hole, so the slab reuses an index.
+    snarl.remove_node(NodeId(0));
+    let old_nodes = snarl.node_ids().map(|(id, _)| id).collect::<Vec<_>>();
+    let old_wires = snarl.wires().collect::<Vec<_>>();
+
+    let other = random_snarl(&mut rng);
+    let other_wires = other.wires().collect::<Vec<_>>();
+    let other_count = other.node_count();
+    let other_pos = other.get_node_info(NodeId(0)).unwrap().pos;
+
+    let ids = snarl.insert_snarl(other, vec2(10.0, 20.0));
+
+    assert_eq!(ids.len(), other_count);
+    assert!(ids.values().all(|id| !old_nodes.contains(id)));
+    assert_eq!(
+        snarl.get_node_info(ids[&NodeId(0)]).unwrap().pos,
+        other_pos + vec2(10.0, 20.0)
+    );
+
+    let mut expected = old_wires;
+    expected.extend(other_wires.iter().map(|(from, to)| {
+        (
+            OutPinId {
+                node: ids[&from.node],
+                output: from.output,
+            },
+            InPinId {
+                node: ids[&to.node],
+                input: to.input,
+            },
+        )
+    }));
+    expected.sort_unstable();
+
+    let mut wires = snarl.wires().collect::<Vec<_>>();
+    wires.sort_unstable();
+    assert_eq!(wires, expected);
+}
+
+#[test]
+fn wires_of_node_in_diamond() {
+    // a -> b -> d
+    // a -> c -> d
+    let mut snarl = Snarl::new();
+    let [a, b, c, d] = [0, 1, 2, 3].map(|i| snarl.insert_node(pos2(0.0, 0.0), i));
+
+    let wire = |from: NodeId, to: NodeId, input: usize| {
+        (
+            OutPinId {
+                node: from,
+                output: 0,
+            },
+            InPinId { node: to, input },
+        )
+    };
+    let wires = [wire(a, b, 0), wire(a, c, 0), wire(b, d, 0), wire(c, d, 1)];
+    for (from, to) in wires {
+        snarl.connect(from, to);
+    }
+
+    let sorted = |iter: &mut dyn Iterator<Item = (OutPinId, InPinId)>| {
+        let mut wires = iter.collect::<Vec<_>>();
+        wires.sort_unstable();
+        wires
+    };
+
+    assert_eq!(sorted(&mut snarl.incoming_wires(a)), []);
+    assert_eq!(sorted(&mut snarl.outgoing_wires(a)), [wires[0], wires[1]]);
+    assert_eq!(sorted(&mut snarl.incoming_wires(d)), [wires[2], wires[3]]);
+    assert_eq!(sorted(&mut snarl.outgoing_wires(d)), []);
+
+    for node in [a, b, c, d] {
+        let incoming = sorted(&mut snarl.incoming_wires(node));
+        let outgoing = sorted(&mut snarl.outgoing_wires(node));
+        assert!(incoming.iter().all(|wire| !outgoing.contains(wire)));
+
+        let mut both = [incoming, outgoing].concat();
+        both.sort_unstable();
+        assert_eq!(sorted(&mut snarl.wires_of(node)), both);
+    }
+
+    // Stale id has no wires.
+    snarl.remove_node(b);
+    assert_eq!(snarl.wires_of(b).count(), 0);
+    assert_eq!(sorted(&mut snarl.wires_of(a)), [wires[1]]);
+}
+
+#[test]
+fn diff_reports_changes() {
+    let mut rng = Lcg(7);
+
+    for _ in 0..100 {
+        let old = random_snarl(&mut rng);
+        assert!(old.diff(&old, |a, b| a == b).is_empty());
+
+        let ids = old.node_ids().map(|(id, _)| id).collect::<Vec<_>>();
+        let mut new = old.clone();
+
+        let moved = ids[rng.next(ids.len())];
+        new.get_node_info_mut(moved).unwrap().pos += vec2(10.0, 0.0);
+
+        let changed = ids[rng.next(ids.len())];
+        *new.get_node_mut(changed).unwrap() += 1;
+
+        let removed_wires = new.wires().take(rng.next(3)).collect::<Vec<_>>();
+        for (from, to) in &removed_wires {
+            new.disconnect(*from, *to);
+        }
+
+        let added = new.insert_node(pos2(0.0, 0.0), 0);
+        new.connect(
+            OutPinId {
+                node: added,
+                output: 0,
+            },
+            InPinId {
+                node: moved,
+                input: 0,
+            },
+        );
+
+        let diff = old.diff(&new, |a, b| a == b);
+        assert_eq!(diff.added_nodes, [added]);
+        assert!(diff.removed_nodes.is_empty());
+        assert_eq!(diff.moved_nodes, [(moved, vec2(10.0, 0.0))]);
+        assert_eq!(diff.changed_nodes, [changed]);
+        assert_eq!(diff.added_wires.len(), 1);
+        assert_eq!(diff.removed_wires.len(), removed_wires.len());
+        assert_eq!(diff.len(), 4 + removed_wires.len());
+
+        // Reverse diff swaps additions and removals.
+        let reverse = new.diff(&old, |a, b| a == b);
+        assert_eq!(reverse.removed_nodes, diff.added_nodes);
+        assert_eq!(reverse.added_wires, diff.removed_wires);
+        assert_eq!(reverse.moved_nodes, [(moved, vec2(-10.0, 0.0))]);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialized_format_is_stable() {
+    // Same document must be produced with and without `ui` feature.
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(1.0, 2.0), 10);
+    let b = snarl.insert_node_collapsed(pos2(-3.5, 4.0), 20);
+    snarl.connect(
+        OutPinId { node: a, output: 0 },
+        InPinId { node: b, input: 1 },
+    );
+
+    let json = serde_json::to_string(&snarl).unwrap();
+    assert_eq!(
+        json,
+        concat!(
+            r#"{"nodes":{"#,
+            r#""0":{"value":10,"pos":{"x":1.0,"y":2.0},"open":true},"#,
+            r#""1":{"value":20,"pos":{"x":-3.5,"y":4.0},"open":false}},"#,
+            r#""wires":[{"out_pin":{"node":0,"output":0},"in_pin":{"node":1,"input":1}}]}"#,
+        )
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn dangling_wires_are_reported_and_pruned() {
+    let json = concat!(
+        r#"{"nodes":{"#,
+        r#""0":{"value":10,"pos":{"x":0.0,"y":0.0},"open":true},"#,
+        r#""2":{"value":20,"pos":{"x":100.0,"y":0.0},"open":true}},"#,
+        r#""wires":["#,
+        r#"{"out_pin":{"node":0,"output":0},"in_pin":{"node":2,"input":0}},"#,
+        r#"{"out_pin":{"node":2,"output":1},"in_pin":{"node":5,"input":0}},"#,
+        r#"{"out_pin":{"node":1,"output":0},"in_pin":{"node":0,"input":3}}"#,
+        r#"]}"#,
+    );
+    let mut snarl = serde_json::from_str::<Snarl<i32>>(json).unwrap();
+
+    assert_eq!(
+        snarl.validate(),
+        Err(vec![
+            (
+                OutPinId {
+                    node: NodeId(1),
+                    output: 0
+                },
+                InPinId {
+                    node: NodeId(0),
+                    input: 3
+                }
+            ),
+            (
+                OutPinId {
+                    node: NodeId(2),
+                    output: 1
+                },
+                InPinId {
+                    node: NodeId(5),
+                    input: 0
+                }
+            ),
+        ])
+    );
+
+    assert_eq!(snarl.prune_invalid_wires(), 2);
+    assert_eq!(snarl.prune_invalid_wires(), 0);
+    assert_eq!(snarl.validate(), Ok(()));
+    assert_eq!(snarl.wire_count(), 1);
+}
+
+#[test]
+fn nodes_with_ids_are_built_at_once() {
+    let ids = [7, 2, 12, 3].map(NodeId);
+    let nodes = ids
+        .iter()
+        .enumerate()
+        .map(|(value, &id)| (id, pos2(value as f32, 0.0), value));
+
+    let snarl = Snarl::from_nodes_with_ids(nodes.clone()).unwrap();
+    let mut one_by_one = Snarl::new();
+    for (id, pos, value) in nodes {
+        assert!(one_by_one.insert_node_with_id(id, pos, value));
+    }
+    assert_eq!(
+        snarl.node_ids().map(|(id, &v)| (id, v)).collect::<Vec<_>>(),
+        one_by_one
+            .node_ids()
+            .map(|(id, &v)| (id, v))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(snarl.get_node_info(ids[2]).unwrap().pos, pos2(2.0, 0.0));
+    assert_eq!(snarl.wire_count(), 0);
+
+    // Duplicate and huge ids are reported.
+    let duplicate = [
+        (NodeId(3), pos2(0.0, 0.0), 0),
+        (NodeId(3), pos2(0.0, 0.0), 1),
+    ];
+    assert_eq!(
+        Snarl::from_nodes_with_ids(duplicate).unwrap_err(),
+        NodeId(3)
+    );
+    let huge = [
+        (NodeId(0), pos2(0.0, 0.0), 0),
+        (NodeId(usize::MAX), pos2(0.0, 0.0), 1),
+    ];
+    assert_eq!(
+        Snarl::from_nodes_with_ids(huge).unwrap_err(),
+        NodeId(usize::MAX)
+    );
+
+    assert!(Snarl::<()>::from_nodes_with_ids([]).unwrap().is_empty());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn topological_order_ignores_dangling_wires() {
+    let json = concat!(
+        r#"{"nodes":{"#,
+        r#""0":{"value":10,"pos":{"x":0.0,"y":0.0},"open":true},"#,
+        r#""2":{"value":20,"pos":{"x":100.0,"y":0.0},"open":true}},"#,
+        r#""wires":["#,
+        r#"{"out_pin":{"node":0,"output":0},"in_pin":{"node":2,"input":0}},"#,
+        r#"{"out_pin":{"node":2,"output":1},"in_pin":{"node":5,"input":0}},"#,
+        r#"{"out_pin":{"node":5,"output":0},"in_pin":{"node":5,"input":1}},"#,
+        r#"{"out_pin":{"node":1,"output":0},"in_pin":{"node":0,"input":3}}"#,
+        r#"]}"#,
+    );
+    let snarl = serde_json::from_str::<Snarl<i32>>(json).unwrap();
+
+    assert_eq!(snarl.topological_order(), Ok(vec![NodeId(0), NodeId(2)]));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn wires_are_serialized_sorted() {
+    let out = |node: usize, output: usize| OutPinId {
+        node: NodeId(node),
+        output,
+    };
+    let inp = |node: usize, input: usize| InPinId {
+        node: NodeId(node),
+        input,
+    };
+
+    let mut wires = Vec::new();
+    for from in 0..8 {
+        for to in 0..8 {
+            wires.push((out(from, to % 3), inp(to, from % 2)));
+        }
+    }
+
+    let graph = |wires: &[(OutPinId, InPinId)]| {
+        let mut snarl = Snarl::new();
+        for i in 0..8 {
+            snarl.insert_node(pos2(0.0, 0.0), i);
+        }
+        for &(from, to) in wires {
+            snarl.connect(from, to);
+        }
+        snarl
+    };
+
+    let forward = graph(&wires);
+    wires.reverse();
+    let mut backward = graph(&wires);
+    // Leave removed entries behind in the set.
+    backward.connect(out(7, 7), inp(7, 7));
+    backward.disconnect(out(7, 7), inp(7, 7));
+
+    let json = serde_json::to_string(&forward).unwrap();
+    assert_eq!(json, serde_json::to_string(&forward).unwrap());
+    assert_eq!(json, serde_json::to_string(&backward).unwrap());
+
+    let value = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+    let serialized = value["wires"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|wire| {
+            (
+                out(
+                    wire["out_pin"]["node"].as_u64().unwrap() as usize,
+                    wire["out_pin"]["output"].as_u64().unwrap() as usize,
+                ),
+                inp(
+                    wire["in_pin"]["node"].as_u64().unwrap() as usize,
+                    wire["in_pin"]["input"].as_u64().unwrap() as usize,
+                ),
+            )
+        })
+        .collect::<Vec<_>>();
+    wires.sort_unstable();
+    wires.dedup();
+    assert_eq!(serialized, wires);
+}
+
+/// Reader that yields items produced by `item` one after another, never ending.
+/// Item `0` is expected to be a prefix before repeated items.
+#[cfg(feature = "serde")]
+struct Endless<F> {
+    item: F,
+    items: usize,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+#[cfg(feature = "serde")]
+impl<F> Endless<F> {
+    const fn new(item: F) -> Self {
+        Endless {
+            item,
+            items: 0,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<F> std::io::Read for Endless<F>
+where
+    F: FnMut(usize) -> String,
+{
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        if self.pos == self.buf.len() {
+            self.buf = (self.item)(self.items).into_bytes();
+            self.items += 1;
+            self.pos = 0;
+        }
+
+        let len = out.len().min(self.buf.len() - self.pos);
+        out[..len].copy_from_slice(&self.buf[self.pos..][..len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn deserialize_with_limits_rejects_endless_input_early() {
+    use crate::{DeserializeError, LimitExceeded, SnarlLimits};
+
+    let limits = SnarlLimits {
+        max_nodes: 50,
+        max_wires: 100,
+        ..SnarlLimits::new()
+    };
+
+    let mut wires = Endless::new(|i| match i {
+        0 => r#"{"nodes":{},"wires":["#.to_owned(),
+        _ => format!(r#"{{"out_pin":{{"node":0,"output":0}},"in_pin":{{"node":1,"input":{i}}}}},"#),
+    });
+    let mut de = serde_json::Deserializer::from_reader(&mut wires);
+    let err = Snarl::<u64>::deserialize_with_limits(&mut de, limits).unwrap_err();
+    assert!(matches!(
+        err,
+        DeserializeError::Limit(LimitExceeded::Wires { limit: 100 })
+    ));
+    // Prefix, wires up to the one that exceeds the limit and a peek into the next one.
+    assert!(wires.items <= 103, "{} items read", wires.items);
+
+    let mut nodes = Endless::new(|i| match i {
+        0 => r#"{"nodes":{"#.to_owned(),
+        _ => format!(
+            r#""{}":{{"value":0,"pos":{{"x":0.0,"y":0.0}},"open":true}},"#,
+            i - 1
+        ),
+    });
+    let mut de = serde_json::Deserializer::from_reader(&mut nodes);
+    let err = Snarl::<u64>::deserialize_with_limits(&mut de, limits).unwrap_err();
+    assert!(matches!(
+        err,
+        DeserializeError::Limit(LimitExceeded::Nodes { limit: 50 })
+    ));
+    assert!(nodes.items <= 53, "{} items read", nodes.items);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn deserialize_with_limits_checks_ids_and_coordinates() {
+    use crate::{DeserializeError, LimitExceeded, SnarlLimits};
+
+    let load = |json: &str| {
+        let mut de = serde_json::Deserializer::from_str(json);
+        Snarl::<u64>::deserialize_with_limits(&mut de, SnarlLimits::new())
+    };
+
+    // Storage would grow up to the node id.
+    let err = load(
+        r#"{"nodes":{"1000000000000":{"value":0,"pos":{"x":0.0,"y":0.0},"open":true}},"wires":[]}"#,
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        DeserializeError::Limit(LimitExceeded::NodeId { .. })
+    ));
+
+    let err =
+        load(r#"{"nodes":{"0":{"value":0,"pos":{"x":1e30,"y":0.0},"open":true}},"wires":[]}"#)
+            .unwrap_err();
+    assert!(matches!(
+        err,
+        DeserializeError::Limit(LimitExceeded::Coordinate { .. })
+    ));
+
+    // Malformed input is reported as deserialization error.
+    let err = load(r#"{"nodes":[]}"#).unwrap_err();
+    assert!(matches!(err, DeserializeError::Deserialize(_)));
+
+    // Graphs within limits load the same as with `Deserialize`.
+    let mut rng = Lcg(7);
+    for _ in 0..20 {
+        let snarl = random_snarl(&mut rng);
+        let json = serde_json::to_string(&snarl).unwrap();
+        let loaded = load(&json).unwrap();
+
+        assert_eq!(loaded.counts(), snarl.counts());
+        assert_eq!(
+            loaded
+                .nodes_ids_data()
+                .map(|(id, node)| (id, node.value))
+                .collect::<Vec<_>>(),
+            snarl
+                .nodes_ids_data()
+                .map(|(id, node)| (id, node.value))
+                .collect::<Vec<_>>()
+        );
+
+        let mut wires = loaded.wires().collect::<Vec<_>>();
+        let mut expected = snarl.wires().collect::<Vec<_>>();
+        wires.sort();
+        expected.sort();
+        assert_eq!(wires, expected);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_roundtrip_preserves_graph() {
+    let mut snarl = Snarl::new();
+    let source = snarl.insert_node(pos2(-100.0, 20.0), "source".to_owned());
+    let removed = snarl.insert_node(pos2(0.0, 0.0), "removed".to_owned());
+    let left = snarl.insert_node(pos2(50.5, -30.0), "left".to_owned());
+    let right = snarl.insert_node_collapsed(pos2(50.0, 80.25), "right".to_owned());
+    let sink = snarl.insert_node(pos2(200.0, 10.0), "sink".to_owned());
+
+    // Leave a gap in node ids.
+    snarl.remove_node(removed);
+
+    let out = |node, output| OutPinId { node, output };
+    let inp = |node, input| InPinId { node, input };
+
+    // Fan-out from source and fan-in to sink.
+    snarl.connect(out(source, 0), inp(left, 0));
+    snarl.connect(out(source, 0), inp(right, 0));
+    snarl.connect(out(source, 1), inp(right, 1));
+    snarl.connect(out(left, 0), inp(sink, 0));
+    snarl.connect(out(right, 0), inp(sink, 0));
+    snarl.connect(out(right, 2), inp(sink, 1));
+
+    let json = serde_json::to_string(&snarl).unwrap();
+    let loaded: Snarl<String> = serde_json::from_str(&json).unwrap();
+
+    // Node ids are preserved, including gaps left by removed nodes.
+    let nodes = |snarl: &Snarl<String>| {
+        snarl
+            .nodes_ids_data()
+            .map(|(id, node)| (id, node.value.clone(), node.pos, node.open))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(nodes(&loaded), nodes(&snarl));
+    assert!(loaded.get_node(removed).is_none());
+
+    let mut wires = loaded.wires().collect::<Vec<_>>();
+    let mut expected = snarl.wires().collect::<Vec<_>>();
+    wires.sort_unstable();
+    expected.sort_unstable();
+    assert_eq!(wires, expected);
+    assert_eq!(wires.len(), 6);
+
+    // Removed id is reused by the next inserted node, same as before serialization.
+    let mut loaded = loaded;
+    assert_eq!(loaded.insert_node(pos2(0.0, 0.0), String::new()), removed);
+}
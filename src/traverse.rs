@@ -0,0 +1,403 @@
+//! Traversing graph along wires.
+
+use std::{collections::VecDeque, fmt};
+
+use ahash::{HashMap, HashSet};
+
+use crate::{InPinId, NodeId, OutPinId, Snarl, Wire};
+
+/// Error returned by [`Snarl::topological_order`] when the graph has a cycle.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Cycle {
+    /// Nodes of the cycle in wire order.
+    /// Each node is connected to the next one and the last node is connected to the first.
+    /// Starts with the smallest node id.
+    /// Node connected to itself forms a cycle of one node.
+    pub nodes: Vec<NodeId>,
+}
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("graph has a cycle through nodes")?;
+        for node in &self.nodes {
+            write!(f, " {}", node.0)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Cycle {}
+
+impl<T> Snarl<T> {
+    /// Iterates over wires between existing nodes.
+    ///
+    /// Wires of a deserialized graph may refer to missing nodes, see [`Snarl::validate`].
+    /// Traversals skip them.
+    fn existing_wires(&self) -> impl Iterator<Item = Wire> + '_ {
+        self.wires.iter().filter(|wire| {
+            self.nodes.contains(wire.out_pin.node.0) && self.nodes.contains(wire.in_pin.node.0)
+        })
+    }
+
+    /// Returns successors of each node, i.e. nodes connected to its outputs.
+    ///
+    /// Nodes connected with several wires are listed once.
+    fn successor_map(&self) -> HashMap<NodeId, Vec<NodeId>> {
+        let mut successors = HashMap::<NodeId, Vec<NodeId>>::default();
+        for wire in self.existing_wires() {
+            let next = successors.entry(wire.out_pin.node).or_default();
+            if !next.contains(&wire.in_pin.node) {
+                next.push(wire.in_pin.node);
+            }
+        }
+        successors
+    }
+
+    /// Returns predecessors of each node, i.e. nodes connected to its inputs.
+    ///
+    /// Nodes connected with several wires are listed once.
+    fn predecessor_map(&self) -> HashMap<NodeId, Vec<NodeId>> {
+        let mut predecessors = HashMap::<NodeId, Vec<NodeId>>::default();
+        for wire in self.existing_wires() {
+            let prev = predecessors.entry(wire.in_pin.node).or_default();
+            if !prev.contains(&wire.out_pin.node) {
+                prev.push(wire.out_pin.node);
+            }
+        }
+        predecessors
+    }
+
+    /// Returns nodes reachable from `start` in breadth-first order, excluding `start`.
+    fn reachable(start: NodeId, adjacent: &HashMap<NodeId, Vec<NodeId>>) -> Vec<NodeId> {
+        let mut visited = HashSet::default();
+        visited.insert(start);
+
+        let mut order = Vec::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            let Some(next) = adjacent.get(&node) else {
+                continue;
+            };
+            for &next in next {
+                if visited.insert(next) {
+                    order.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        order
+    }
+
+    /// Iterates over nodes directly connected to the node, in either direction.
+    ///
+    /// Nodes connected with several wires are listed once, in no particular order.
+    /// Wires to missing nodes are ignored.
+    /// The node itself is included only if it is wired to itself.
+    /// Use [`Snarl::successors`] and [`Snarl::predecessors`] to follow wire direction.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), ());
+    /// let c = snarl.insert_node(pos2(200.0, 0.0), ());
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    /// snarl.connect(OutPinId { node: a, output: 1 }, InPinId { node: b, input: 1 });
+    /// snarl.connect(OutPinId { node: b, output: 0 }, InPinId { node: c, input: 0 });
+    ///
+    /// let mut neighbors = snarl.neighbors(b).collect::<Vec<_>>();
+    /// neighbors.sort();
+    /// assert_eq!(neighbors, [a, c]);
+    /// ```
+    pub fn neighbors(&self, node: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let mut seen = HashSet::default();
+        self.existing_wires()
+            .filter_map(move |wire| {
+                if wire.out_pin.node == node {
+                    Some(wire.in_pin.node)
+                } else if wire.in_pin.node == node {
+                    Some(wire.out_pin.node)
+                } else {
+                    None
+                }
+            })
+            .filter(move |&next| seen.insert(next))
+    }
+
+    /// Iterates over nodes connected to outputs of the node.
+    ///
+    /// Nodes connected with several wires are listed once, in no particular order.
+    /// Wires to missing nodes are ignored.
+    pub fn successors(&self, node: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let mut seen = HashSet::default();
+        self.existing_wires()
+            .filter(move |wire| wire.out_pin.node == node)
+            .map(|wire| wire.in_pin.node)
+            .filter(move |&next| seen.insert(next))
+    }
+
+    /// Iterates over nodes connected to inputs of the node.
+    ///
+    /// Nodes connected with several wires are listed once, in no particular order.
+    /// Wires to missing nodes are ignored.
+    pub fn predecessors(&self, node: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let mut seen = HashSet::default();
+        self.existing_wires()
+            .filter(move |wire| wire.in_pin.node == node)
+            .map(|wire| wire.out_pin.node)
+            .filter(move |&prev| seen.insert(prev))
+    }
+
+    /// Iterates over nodes the node depends on,
+    /// i.e. nodes connected to its inputs, nodes connected to their inputs and so on.
+    ///
+    /// Nodes are visited in breadth-first order, each node once.
+    /// The node itself is not included, even if it is part of a cycle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), ());
+    /// let c = snarl.insert_node(pos2(200.0, 0.0), ());
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    /// snarl.connect(OutPinId { node: b, output: 0 }, InPinId { node: c, input: 0 });
+    ///
+    /// assert_eq!(snarl.ancestors(c).collect::<Vec<_>>(), [b, a]);
+    /// ```
+    pub fn ancestors(&self, node: NodeId) -> impl Iterator<Item = NodeId> {
+        Self::reachable(node, &self.predecessor_map()).into_iter()
+    }
+
+    /// Iterates over nodes depending on the node,
+    /// i.e. nodes connected to its outputs, nodes connected to their outputs and so on.
+    ///
+    /// Nodes are visited in breadth-first order, each node once.
+    /// The node itself is not included, even if it is part of a cycle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), ());
+    /// let c = snarl.insert_node(pos2(200.0, 0.0), ());
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    /// snarl.connect(OutPinId { node: b, output: 0 }, InPinId { node: c, input: 0 });
+    ///
+    /// assert_eq!(snarl.descendants(a).collect::<Vec<_>>(), [b, c]);
+    /// ```
+    pub fn descendants(&self, node: NodeId) -> impl Iterator<Item = NodeId> {
+        Self::reachable(node, &self.successor_map()).into_iter()
+    }
+
+    /// Returns true if connecting `from` output to `to` input would create a cycle.
+    ///
+    /// Wires are treated as directed edges from output's node to input's node,
+    /// and all pins of a node are treated as a single vertex.
+    /// Connecting node to itself is always a cycle.
+    ///
+    /// Use it in [`SnarlViewer::connect`](crate::ui::SnarlViewer::connect)
+    /// to reject connections in graphs that must stay acyclic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), ());
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    ///
+    /// assert!(snarl.would_create_cycle(OutPinId { node: b, output: 0 }, InPinId { node: a, input: 0 }));
+    /// assert!(!snarl.would_create_cycle(OutPinId { node: a, output: 1 }, InPinId { node: b, input: 1 }));
+    /// ```
+    #[must_use]
+    pub fn would_create_cycle(&self, from: OutPinId, to: InPinId) -> bool {
+        if from.node == to.node {
+            return true;
+        }
+
+        // New wire closes a cycle if `from` node is reachable from `to` node.
+        let successors = self.successor_map();
+        let mut visited = HashSet::default();
+        let mut stack = vec![to.node];
+
+        while let Some(node) = stack.pop() {
+            if node == from.node {
+                return true;
+            }
+            if !visited.insert(node) {
+                continue;
+            }
+            if let Some(next) = successors.get(&node) {
+                stack.extend(next.iter().copied());
+            }
+        }
+
+        false
+    }
+
+    /// Returns all nodes ordered so that each node comes after nodes connected to its inputs.
+    ///
+    /// Wires are treated as directed edges from output's node to input's node,
+    /// and all pins of a node are treated as a single vertex.
+    /// Nodes without wires and disconnected parts of the graph are included as well.
+    /// Wires to missing nodes are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Cycle`] with nodes of a cycle if the graph has one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), ());
+    /// snarl.connect(OutPinId { node: b, output: 0 }, InPinId { node: a, input: 0 });
+    ///
+    /// assert_eq!(snarl.topological_order(), Ok(vec![b, a]));
+    ///
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 0 });
+    /// assert_eq!(snarl.topological_order().unwrap_err().nodes, [a, b]);
+    /// ```
+    #[doc(alias = "topological_sort")]
+    pub fn topological_order(&self) -> Result<Vec<NodeId>, Cycle> {
+        let successors = self.successor_map();
+
+        let mut in_degree = HashMap::<NodeId, usize>::default();
+        for next in successors.values() {
+            for &node in next {
+                *in_degree.entry(node).or_default() += 1;
+            }
+        }
+
+        let mut ready = self
+            .nodes
+            .iter()
+            .map(|(idx, _)| NodeId(idx))
+            .filter(|node| !in_degree.contains_key(node))
+            .collect::<VecDeque<_>>();
+
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(node) = ready.pop_front() {
+            order.push(node);
+
+            let Some(next) = successors.get(&node) else {
+                continue;
+            };
+            for &next in next {
+                let degree = in_degree.get_mut(&next).unwrap();
+                *degree -= 1;
+                if *degree == 0 {
+                    in_degree.remove(&next);
+                    ready.push_back(next);
+                }
+            }
+        }
+
+        if in_degree.is_empty() {
+            return Ok(order);
+        }
+
+        // Each remaining node has a remaining predecessor.
+        // Walking back along them must revisit a node, which lies on a cycle.
+        let mut predecessors = HashMap::default();
+        for (&node, next) in &successors {
+            for &next in next {
+                if in_degree.contains_key(&node) && in_degree.contains_key(&next) {
+                    predecessors.insert(next, node);
+                }
+            }
+        }
+
+        let mut visited = HashSet::default();
+        let mut path = Vec::new();
+        let mut node = *in_degree.keys().min().unwrap();
+        while visited.insert(node) {
+            path.push(node);
+            node = predecessors[&node];
+        }
+
+        // Path goes against wires, so the cycle is reversed.
+        let start = path.iter().position(|&n| n == node).unwrap();
+        let mut nodes = path.split_off(start);
+        nodes.reverse();
+
+        let first = (0..nodes.len()).min_by_key(|&idx| nodes[idx]).unwrap();
+        nodes.rotate_left(first);
+
+        Err(Cycle { nodes })
+    }
+
+    /// Splits nodes into groups connected with wires.
+    ///
+    /// Wires are treated as undirected edges.
+    /// Each node appears in exactly one group and node without wires forms a group of its own.
+    /// Wires to missing nodes are ignored.
+    /// Groups are ordered by their smallest node id and nodes in each group are sorted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui_snarl::{Snarl, InPinId, OutPinId};
+    /// # use emath::pos2;
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(pos2(100.0, 0.0), ());
+    /// let c = snarl.insert_node(pos2(200.0, 0.0), ());
+    /// snarl.connect(OutPinId { node: c, output: 0 }, InPinId { node: a, input: 0 });
+    ///
+    /// assert_eq!(snarl.connected_components(), [vec![a, c], vec![b]]);
+    /// ```
+    #[must_use]
+    pub fn connected_components(&self) -> Vec<Vec<NodeId>> {
+        let mut neighbors = HashMap::<NodeId, Vec<NodeId>>::default();
+        for wire in self.existing_wires() {
+            neighbors
+                .entry(wire.out_pin.node)
+                .or_default()
+                .push(wire.in_pin.node);
+            neighbors
+                .entry(wire.in_pin.node)
+                .or_default()
+                .push(wire.out_pin.node);
+        }
+
+        let mut visited = HashSet::default();
+        let mut components = Vec::new();
+
+        for (idx, _) in &self.nodes {
+            let start = NodeId(idx);
+            if !visited.insert(start) {
+                continue;
+            }
+
+            let mut component = Vec::new();
+            let mut stack = vec![start];
+            while let Some(node) = stack.pop() {
+                component.push(node);
+                if let Some(next) = neighbors.get(&node) {
+                    stack.extend(next.iter().copied().filter(|&next| visited.insert(next)));
+                }
+            }
+
+            component.sort_unstable();
+            components.push(component);
+        }
+
+        components
+    }
+}
@@ -1,34 +1,45 @@
 //! This module provides functionality for showing [`Snarl`] graph in [`Ui`].
 
-use std::{collections::HashMap, hash::Hash};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
 
 use egui::{
     collapsing_header::paint_default_icon, epaint::Shadow, pos2, vec2, Align, Color32, Frame, Id,
-    Layout, Margin, Modifiers, PointerButton, Pos2, Rect, Rounding, Sense, Shape, Stroke, Style,
-    Ui, UiBuilder, Vec2,
+    KeyboardShortcut, Layout, Margin, Modifiers, PointerButton, Pos2, Rect, Response, Rounding,
+    Sense, Shape, Stroke, Style, TextWrapMode, Ui, UiBuilder, Vec2,
 };
 
 use crate::{InPin, InPinId, Node, NodeId, OutPin, OutPinId, Snarl};
 
 mod background_pattern;
+mod effect;
+mod header;
 mod pin;
 mod state;
+#[cfg(test)]
+mod tests;
 mod viewer;
 mod wire;
 mod zoom;
 
 use self::{
-    pin::AnyPin,
-    state::{NewWires, NodeState, SnarlState},
-    wire::{draw_wire, hit_wire, pick_wire_style},
+    state::{NewWires, NodeState, SnarlState, WireInsertion},
+    wire::{
+        draw_wire, hit_wire, pick_wire_frame_size, pick_wire_style, wire_intersects_rect,
+        wire_midpoint,
+    },
     zoom::Zoom,
 };
 
 pub use self::{
-    background_pattern::{BackgroundPattern, Grid, Viewport},
-    pin::{AnyPins, PinInfo, PinShape},
+    background_pattern::{BackgroundPattern, Grid, Viewport, ViewportChangeReason},
+    effect::{Effect, Effects},
+    header::{show_node_header, NodeIcon},
+    pin::{AnyPin, AnyPins, PinInfo, PinShape},
     viewer::SnarlViewer,
-    wire::{WireLayer, WireStyle},
+    wire::{WireColorMode, WireEndMarker, WireInfo, WireLayer, WireStyle},
 };
 
 /// Controls how header, pins, body and footer are laid out in the node.
@@ -101,6 +112,47 @@ pub struct SelectionStyle {
     pub stroke: Stroke,
 }
 
+/// Activity of the node shown as a slim strip under the node's header.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NodeActivity {
+    /// Progress of the node's work from 0.0 to 1.0.
+    Progress(f32),
+
+    /// Work of unknown duration.
+    /// Strip is animated.
+    Spinner,
+}
+
+/// Controls style of node activity strip.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "egui-probe", derive(egui_probe::EguiProbe))]
+pub struct ProgressStyle {
+    /// Thickness of the strip.
+    pub height: f32,
+
+    /// Color of the done part of the strip.
+    pub fill: Color32,
+
+    /// Color of the rest of the strip.
+    pub background: Color32,
+}
+
+/// Controls style of breakpoint gutter in node's header.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "egui-probe", derive(egui_probe::EguiProbe))]
+pub struct BreakpointStyle {
+    /// Radius of the breakpoint circle.
+    pub radius: f32,
+
+    /// Fill of the active breakpoint.
+    pub fill: Color32,
+
+    /// Outline of the breakpoint circle.
+    pub stroke: Stroke,
+}
+
 /// Controls how pins are placed in the node.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -120,6 +172,31 @@ pub enum PinPlacement {
     },
 }
 
+/// Controls whether nodes are raised above others when interacted with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "egui-probe", derive(egui_probe::EguiProbe))]
+pub enum RaisePolicy {
+    /// Node is drawn on top of others after being clicked or dragged.
+    #[default]
+    Always,
+
+    /// Nodes are never raised and keep their draw order.
+    Never,
+}
+
+/// Point that stays in place on screen when viewport scale is changed
+/// with [`Snarl::set_zoom`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ZoomAnchor {
+    /// Center of the viewport.
+    #[default]
+    Center,
+
+    /// Point in graph space.
+    Graph(Pos2),
+}
+
 /// Style for rendering Snarl.
 #[derive(Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -168,6 +245,38 @@ pub struct SnarlStyle {
     )]
     pub header_drag_space: Option<Vec2>,
 
+    /// Distance in screen points the pointer must move from where it was pressed
+    /// before dragging moves the node.
+    /// Smaller motions are not applied, so jittery clicks don't move nodes.
+    ///
+    /// Defaults to 0, so only egui's own click-or-drag decision applies.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub drag_threshold: Option<f32>,
+
+    /// Nodes with opacity below this value can't be interacted with.
+    /// Their widgets are disabled and they can't be selected, dragged or wired.
+    /// See [`SnarlViewer::node_opacity`].
+    ///
+    /// Defaults to 0, so all nodes are interactive.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub min_interactive_opacity: Option<f32>,
+
+    /// Controls whether clicked or dragged node is drawn on top of others.
+    /// [`SnarlViewer::allow_raise`] can veto raising individual nodes.
+    ///
+    /// Defaults to [`RaisePolicy::Always`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub raise_on_interact: Option<RaisePolicy>,
+
     /// Whether nodes can be collapsed.
     /// If true, headers will have collapsing button.
     /// When collapsed, node will not show its pins, body and footer.
@@ -177,6 +286,57 @@ pub struct SnarlStyle {
     )]
     pub collapsible: Option<bool>,
 
+    /// Whether pins of collapsed nodes are shown as stubs.
+    ///
+    /// If true, pins of collapsed node are distributed along the edges of its header
+    /// with reduced size, and wires are attached to them.
+    /// Pins stay interactive, so wires can be connected and dropped.
+    /// If false, all pins of collapsed node are placed in the middle of the header's edges.
+    ///
+    /// Defaults to false.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub collapsed_pin_stubs: Option<bool>,
+
+    /// Modifiers that must be held when clicking node's collapse icon
+    /// to open or collapse all selected nodes, or all nodes if none are selected.
+    ///
+    /// Defaults to [`Modifiers::ALT`].
+    #[cfg_attr(feature = "egui-probe", egui_probe(skip))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub collapse_all_modifiers: Option<Modifiers>,
+
+    /// Modifiers that must be held when starting to drag from a connected pin
+    /// to detach its wire and drag the loose end to another pin.
+    ///
+    /// Dragging from a pin with several wires detaches only the wire to the node nearest to the pointer.
+    /// Dropping the loose end on empty space opens the dropped wire menu as usual.
+    /// Takes precedence over dragging all wires of the pin with command modifier.
+    /// Defaults to `None` - wires are not detached.
+    #[cfg_attr(feature = "egui-probe", egui_probe(skip))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub detach_wire_modifiers: Option<Modifiers>,
+
+    /// Whether node layouts are mirrored for right-to-left locales.
+    ///
+    /// If true, input pins are placed on the right side of nodes and output pins on the left side.
+    /// Header, body and footer are laid out from right to left, and wires leave output pins to the left.
+    ///
+    /// Defaults to layout direction of the `Ui` where snarl is shown.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub mirrored: Option<bool>,
+
     /// Size of pins.
     #[cfg_attr(feature = "egui-probe", egui_probe(range = 0.0..))]
     #[cfg_attr(
@@ -250,6 +410,40 @@ pub struct SnarlStyle {
     )]
     pub wire_style: Option<WireStyle>,
 
+    /// Marker drawn at the input end of wires to show direction of the flow.
+    /// Defaults to [`WireEndMarker::None`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub wire_end_marker: Option<WireEndMarker>,
+
+    /// Controls how wires are colored from colors of their pins.
+    /// Defaults to [`WireColorMode::Mix`].
+    ///
+    /// With [`WireColorMode::Gradient`] wires get [`WireInfo::end_color`],
+    /// which [`SnarlViewer::wire_style`] may clear to draw the wire in a single color.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub wire_color_mode: Option<WireColorMode>,
+
+    /// Speed of dashes flowing along wires from outputs to inputs, in points per second.
+    /// When set, wires are drawn as marching dashes and keep requesting repaint while visible.
+    /// Defaults to no flow.
+    ///
+    /// [`WireStyle::Dashed`] wires keep their dashes and move them with this speed instead of their own,
+    /// [`WireStyle::Dotted`] wires move their dots.
+    /// Zero speed stops the flow, including dashes of [`WireStyle::Dashed`] wires.
+    ///
+    /// Use [`PinInfo::with_wire_flow`] to animate or stop only specific wires.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub wire_flow: Option<f32>,
+
     /// Layer where wires are rendered.
     #[cfg_attr(
         feature = "serde",
@@ -276,6 +470,16 @@ pub struct SnarlStyle {
     )]
     pub bg_pattern: Option<BackgroundPattern>,
 
+    /// Background patterns drawn one over another.
+    /// First pattern is drawn at the bottom, last one is on top.
+    ///
+    /// If set, it is used instead of [`SnarlStyle::bg_pattern`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub bg_patterns: Option<Vec<BackgroundPattern>>,
+
     /// Stroke for background pattern.
     /// Defaults to `ui.visuals().widgets.noninteractive.bg_stroke`.
     #[cfg_attr(
@@ -300,6 +504,32 @@ pub struct SnarlStyle {
     )]
     pub max_scale: Option<f32>,
 
+    /// Discrete viewport scales allowed when zooming, e.g. `[0.25, 0.5, 0.75, 1.0]`.
+    ///
+    /// When set, viewport scale snaps to the step nearest to the scale that
+    /// continuous zooming would reach, so small pinch motions accumulate until
+    /// the next step becomes nearest. Initial scale is snapped as well.
+    /// Steps outside of [`SnarlStyle::min_scale`] and [`SnarlStyle::max_scale`] are ignored.
+    ///
+    /// Defaults to `None` - continuous zooming.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub zoom_steps: Option<Vec<f32>>,
+
+    /// Factor by which [`SnarlStyle::zoom_in_shortcut`] and [`SnarlStyle::zoom_out_shortcut`]
+    /// change viewport scale.
+    /// When [`SnarlStyle::zoom_steps`] are set, shortcuts move to the next step instead.
+    ///
+    /// Defaults to `1.25`.
+    #[cfg_attr(feature = "egui-probe", egui_probe(range = 1.0..))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub zoom_step_factor: Option<f32>,
+
     /// Velocity of viewport scale when scaling with mouse wheel.
     #[cfg_attr(feature = "egui-probe", egui_probe(range = 0.0..))]
     #[cfg_attr(
@@ -334,6 +564,15 @@ pub struct SnarlStyle {
     /// If set to false, nodes intersecting with selection rect will be selected.
     pub select_rect_contained: Option<bool>,
 
+    /// Flag to control whether rect selection selects wires too.
+    /// Wires are selected by their bounding box the same way as nodes.
+    /// Defaults to `false`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub select_wires: Option<bool>,
+
     /// Style for node selection.
     #[cfg_attr(
         feature = "serde",
@@ -341,6 +580,114 @@ pub struct SnarlStyle {
     )]
     pub select_style: Option<SelectionStyle>,
 
+    /// Style for node activity strip shown under the header.
+    /// See [`SnarlViewer::node_progress`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub progress_style: Option<ProgressStyle>,
+
+    /// Style for breakpoint gutter in node's header.
+    /// See [`SnarlViewer::breakpoint_state`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub breakpoint_style: Option<BreakpointStyle>,
+
+    /// Keyboard shortcut that toggles breakpoints of selected nodes
+    /// with [`SnarlViewer::toggle_breakpoint`].
+    ///
+    /// Shortcut is ignored while a widget wants keyboard input.
+    /// Defaults to `None` - no shortcut.
+    #[cfg_attr(feature = "egui-probe", egui_probe(skip))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub breakpoint_shortcut: Option<KeyboardShortcut>,
+
+    /// Keyboard shortcut that zooms in by [`SnarlStyle::zoom_step_factor`]
+    /// keeping center of the viewport in place.
+    ///
+    /// Zoom shortcuts work only while pointer is over the graph
+    /// and are ignored while a widget wants keyboard input.
+    /// Defaults to `None` - no shortcut.
+    #[cfg_attr(feature = "egui-probe", egui_probe(skip))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub zoom_in_shortcut: Option<KeyboardShortcut>,
+
+    /// Keyboard shortcut that zooms out by [`SnarlStyle::zoom_step_factor`]
+    /// keeping center of the viewport in place.
+    ///
+    /// Defaults to `None` - no shortcut.
+    #[cfg_attr(feature = "egui-probe", egui_probe(skip))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub zoom_out_shortcut: Option<KeyboardShortcut>,
+
+    /// Keyboard shortcut that resets viewport scale to `1.0`
+    /// keeping center of the viewport in place.
+    ///
+    /// Defaults to `None` - no shortcut.
+    #[cfg_attr(feature = "egui-probe", egui_probe(skip))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub zoom_reset_shortcut: Option<KeyboardShortcut>,
+
+    /// Keyboard shortcut that fits all nodes into the viewport.
+    ///
+    /// Viewport is never zoomed in above `1.0` to fit nodes.
+    /// Defaults to `None` - no shortcut.
+    #[cfg_attr(feature = "egui-probe", egui_probe(skip))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub zoom_fit_shortcut: Option<KeyboardShortcut>,
+
+    /// Keyboard shortcut that fits selected nodes into the viewport.
+    ///
+    /// Viewport is never zoomed in above `1.0` to fit nodes.
+    /// Defaults to `None` - no shortcut.
+    #[cfg_attr(feature = "egui-probe", egui_probe(skip))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub zoom_fit_selection_shortcut: Option<KeyboardShortcut>,
+
+    /// Keyboard shortcut that removes selected nodes with [`SnarlViewer::remove_node`].
+    /// Selected wires are removed first with [`SnarlViewer::disconnect`].
+    ///
+    /// Shortcut works only while pointer is over the graph
+    /// and is ignored while a widget wants keyboard input.
+    /// Defaults to `None` - no shortcut.
+    #[cfg_attr(feature = "egui-probe", egui_probe(skip))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub delete_selected_shortcut: Option<KeyboardShortcut>,
+
+    /// Color of the dragged wire while it is over a pin it can't be connected to.
+    ///
+    /// See [`SnarlViewer::can_connect`].
+    /// Defaults to `None` - wire keeps color of its pin.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Option::is_none", default)
+    )]
+    pub invalid_wire_color: Option<Color32>,
+
     #[doc(hidden)]
     #[cfg_attr(feature = "egui-probe", egui_probe(skip))]
     #[cfg_attr(feature = "serde", serde(skip_serializing, default))]
@@ -405,6 +752,16 @@ impl SnarlStyle {
         self.wire_style.zoomed(scale).unwrap_or(WireStyle::Bezier5)
     }
 
+    fn get_wire_end_marker(&self, scale: f32) -> WireEndMarker {
+        self.wire_end_marker
+            .zoomed(scale)
+            .unwrap_or(WireEndMarker::None)
+    }
+
+    fn get_wire_color_mode(&self) -> WireColorMode {
+        self.wire_color_mode.unwrap_or(WireColorMode::Mix)
+    }
+
     fn get_wire_layer(&self) -> WireLayer {
         self.wire_layer.unwrap_or(WireLayer::BehindNodes)
     }
@@ -415,10 +772,35 @@ impl SnarlStyle {
             .unwrap_or_else(|| vec2(style.spacing.icon_width, style.spacing.icon_width))
     }
 
+    fn get_drag_threshold(&self) -> f32 {
+        self.drag_threshold.unwrap_or(0.0).max(0.0)
+    }
+
+    fn get_min_interactive_opacity(&self) -> f32 {
+        self.min_interactive_opacity.unwrap_or(0.0)
+    }
+
+    fn get_raise_on_interact(&self) -> RaisePolicy {
+        self.raise_on_interact.unwrap_or_default()
+    }
+
     fn get_collapsible(&self) -> bool {
         self.collapsible.unwrap_or(true)
     }
 
+    fn get_collapsed_pin_stubs(&self) -> bool {
+        self.collapsed_pin_stubs.unwrap_or(false)
+    }
+
+    fn get_collapse_all_modifiers(&self) -> Modifiers {
+        self.collapse_all_modifiers.unwrap_or(Modifiers::ALT)
+    }
+
+    fn get_mirrored(&self, layout: &Layout) -> bool {
+        self.mirrored
+            .unwrap_or_else(|| layout.prefer_right_to_left())
+    }
+
     fn get_bg_frame(&self, style: &Style) -> Frame {
         self.bg_frame.unwrap_or_else(|| Frame::canvas(style))
     }
@@ -437,6 +819,49 @@ impl SnarlStyle {
         self.max_scale.unwrap_or(5.0)
     }
 
+    /// Returns allowed zoom step nearest to `scale`.
+    /// Returns `scale` as is if there are no steps.
+    fn snap_scale(&self, scale: f32) -> f32 {
+        let Some(steps) = &self.zoom_steps else {
+            return scale;
+        };
+
+        let range = self.get_min_scale()..=self.get_max_scale();
+        steps
+            .iter()
+            .copied()
+            .filter(|step| range.contains(step))
+            .min_by(|a, b| (a - scale).abs().total_cmp(&(b - scale).abs()))
+            .unwrap_or(scale)
+    }
+
+    /// Returns scale one zoom step in or out from `scale`.
+    fn step_scale(&self, scale: f32, zoom_in: bool) -> f32 {
+        let range = self.get_min_scale()..=self.get_max_scale();
+
+        let stepped = match &self.zoom_steps {
+            None => {
+                let factor = self.zoom_step_factor.unwrap_or(1.25);
+                if zoom_in {
+                    scale * factor
+                } else {
+                    scale / factor
+                }
+            }
+            Some(steps) => {
+                let steps = steps.iter().copied().filter(|step| range.contains(step));
+                let next = if zoom_in {
+                    steps.filter(|&step| step > scale).min_by(f32::total_cmp)
+                } else {
+                    steps.filter(|&step| step < scale).max_by(f32::total_cmp)
+                };
+                next.unwrap_or(scale)
+            }
+        };
+
+        stepped.clamp(*range.start(), *range.end())
+    }
+
     fn get_scale_velocity(&self) -> f32 {
         self.scale_velocity.unwrap_or(1.0)
     }
@@ -475,6 +900,10 @@ impl SnarlStyle {
         self.select_rect_contained.unwrap_or(false)
     }
 
+    fn get_select_wires(&self) -> bool {
+        self.select_wires.unwrap_or(false)
+    }
+
     fn get_select_style(&self, scale: f32, style: &Style) -> SelectionStyle {
         self.select_style
             .zoomed(scale)
@@ -485,6 +914,24 @@ impl SnarlStyle {
                 stroke: self.get_select_stroke(scale, style),
             })
     }
+
+    fn get_progress_style(&self, scale: f32, style: &Style) -> ProgressStyle {
+        self.progress_style.zoomed(scale).unwrap_or(ProgressStyle {
+            height: 3.0 * scale,
+            fill: style.visuals.selection.bg_fill,
+            background: style.visuals.extreme_bg_color,
+        })
+    }
+
+    fn get_breakpoint_style(&self, scale: f32, style: &Style) -> BreakpointStyle {
+        self.breakpoint_style
+            .zoomed(scale)
+            .unwrap_or(BreakpointStyle {
+                radius: style.spacing.icon_width * 0.4,
+                fill: style.visuals.error_fg_color,
+                stroke: style.visuals.widgets.noninteractive.fg_stroke,
+            })
+    }
 }
 
 #[cfg(feature = "serde")]
@@ -552,16 +999,29 @@ impl SnarlStyle {
             downscale_wire_frame: None,
             upscale_wire_frame: None,
             wire_style: None,
+            wire_end_marker: None,
+            wire_color_mode: None,
+            wire_flow: None,
             wire_layer: None,
             header_drag_space: None,
+            drag_threshold: None,
+            min_interactive_opacity: None,
+            raise_on_interact: None,
             collapsible: None,
+            collapsed_pin_stubs: None,
+            collapse_all_modifiers: None,
+            detach_wire_modifiers: None,
+            mirrored: None,
 
             bg_frame: None,
             bg_pattern: None,
+            bg_patterns: None,
             bg_pattern_stroke: None,
 
             min_scale: None,
             max_scale: None,
+            zoom_steps: None,
+            zoom_step_factor: None,
             scale_velocity: None,
             node_frame: None,
             header_frame: None,
@@ -569,7 +1029,18 @@ impl SnarlStyle {
             select_stoke: None,
             select_fill: None,
             select_rect_contained: None,
+            select_wires: None,
             select_style: None,
+            progress_style: None,
+            breakpoint_style: None,
+            breakpoint_shortcut: None,
+            zoom_in_shortcut: None,
+            zoom_out_shortcut: None,
+            zoom_reset_shortcut: None,
+            zoom_fit_shortcut: None,
+            zoom_fit_selection_shortcut: None,
+            delete_selected_shortcut: None,
+            invalid_wire_color: None,
 
             _non_exhaustive: (),
         }
@@ -593,7 +1064,6 @@ struct Input {
 }
 
 struct DrawNodeResponse {
-    node_moved: Option<(NodeId, Vec2)>,
     node_to_top: Option<NodeId>,
     drag_released: bool,
     pin_hovered: Option<AnyPin>,
@@ -612,46 +1082,273 @@ struct DrawBodyResponse {
 
 struct PinResponse {
     pos: Pos2,
+    size: f32,
     pin_color: Color32,
     wire_style: Option<WireStyle>,
+    wire_end_marker: Option<WireEndMarker>,
+    wire_flow: Option<f32>,
+    wire_frame_size: Option<f32>,
+    side: PinSide,
 }
 
-impl<T> Snarl<T> {
-    fn draw_background<V>(
-        &self,
-        viewer: &mut V,
-        style: &SnarlStyle,
-        snarl_state: &SnarlState,
-        viewport: &Rect,
-        ui: &Ui,
-    ) where
-        V: SnarlViewer<T>,
-    {
-        let viewport = Viewport {
-            rect: *viewport,
-            scale: snarl_state.scale(),
-            offset: snarl_state.offset(),
-        };
+/// Side of the node where pin is placed.
+///
+/// See [`SnarlViewer::pin_side`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PinSide {
+    /// Pin is placed on the left side of the node.
+    Left,
 
-        viewer.draw_background(
-            style.bg_pattern.as_ref(),
-            &viewport,
-            style,
-            ui.style(),
-            ui.painter(),
-            self,
-        );
+    /// Pin is placed on the right side of the node.
+    Right,
+}
+
+impl PinSide {
+    /// Returns the other side.
+    #[must_use]
+    pub const fn opposite(self) -> Self {
+        match self {
+            PinSide::Left => PinSide::Right,
+            PinSide::Right => PinSide::Left,
+        }
     }
 
-    /// Render [`Snarl`] using given viewer and style into the [`Ui`].
-    pub fn show<V>(&mut self, viewer: &mut V, style: &SnarlStyle, id_salt: impl Hash, ui: &mut Ui)
-    where
-        V: SnarlViewer<T>,
-    {
+    /// Alignment of the pins column.
+    const fn align(self) -> Align {
+        match self {
+            PinSide::Left => Align::Min,
+            PinSide::Right => Align::Max,
+        }
+    }
+
+    /// Layout of a row with pin content.
+    /// Content starts from the side of the pin.
+    fn row_layout(self) -> Layout {
+        match self {
+            PinSide::Left => Layout::left_to_right(Align::Min),
+            PinSide::Right => Layout::right_to_left(Align::Min),
+        }
+    }
+}
+
+/// Places pins of collapsing node along the header's edge.
+#[derive(Clone, Copy)]
+struct PinStubs {
+    header_top: f32,
+    header_height: f32,
+    openness: f32,
+}
+
+impl PinStubs {
+    /// Returns position on the Y axis and size of the pin
+    /// interpolated between its stub and normal placement.
+    #[allow(clippy::cast_precision_loss)]
+    fn place(&self, idx: usize, count: usize, y: f32, size: f32) -> (f32, f32) {
+        let step = self.header_height / (count + 1) as f32;
+        let stub_y = step.mul_add((idx + 1) as f32, self.header_top);
+        let stub_size = size.min(step);
+
+        (
+            egui::lerp(stub_y..=y, self.openness),
+            egui::lerp(stub_size..=size, self.openness),
+        )
+    }
+}
+
+/// Sides and positions of node's pins.
+struct PinLayout {
+    /// Side of each input pin.
+    input_sides: Vec<PinSide>,
+
+    /// Side of each output pin.
+    output_sides: Vec<PinSide>,
+
+    /// Side where inputs are placed by default.
+    /// Outputs are placed on the opposite side by default.
+    input_side: PinSide,
+
+    /// Pins' center by X axis on the left side.
+    left_x: f32,
+
+    /// Pins' center by X axis on the right side.
+    right_x: f32,
+
+    /// Spacing required for pins on the left side.
+    left_spacing: Option<f32>,
+
+    /// Spacing required for pins on the right side.
+    right_spacing: Option<f32>,
+}
+
+impl PinLayout {
+    /// Returns pins' center by X axis on the side.
+    const fn x(&self, side: PinSide) -> f32 {
+        match side {
+            PinSide::Left => self.left_x,
+            PinSide::Right => self.right_x,
+        }
+    }
+
+    /// Returns spacing required for pins on the side.
+    const fn spacing(&self, side: PinSide) -> Option<f32> {
+        match side {
+            PinSide::Left => self.left_spacing,
+            PinSide::Right => self.right_spacing,
+        }
+    }
+
+    /// Returns index of the input among pins on its side and number of pins on that side.
+    /// Pins that are on the side by default go first.
+    fn input_stub(&self, idx: usize) -> (usize, usize) {
+        let side = self.input_sides[idx];
+        let inputs_before = self.input_sides[..idx]
+            .iter()
+            .filter(|s| **s == side)
+            .count();
+        let inputs = self.input_sides.iter().filter(|s| **s == side).count();
+        let outputs = self.output_sides.iter().filter(|s| **s == side).count();
+
+        if side == self.input_side {
+            (inputs_before, inputs + outputs)
+        } else {
+            (outputs + inputs_before, inputs + outputs)
+        }
+    }
+
+    /// Returns index of the output among pins on its side and number of pins on that side.
+    /// Pins that are on the side by default go first.
+    fn output_stub(&self, idx: usize) -> (usize, usize) {
+        let side = self.output_sides[idx];
+        let outputs_before = self.output_sides[..idx]
+            .iter()
+            .filter(|s| **s == side)
+            .count();
+        let inputs = self.input_sides.iter().filter(|s| **s == side).count();
+        let outputs = self.output_sides.iter().filter(|s| **s == side).count();
+
+        if side == self.input_side {
+            (inputs + outputs_before, inputs + outputs)
+        } else {
+            (outputs_before, inputs + outputs)
+        }
+    }
+}
+
+impl<T> Snarl<T> {
+    fn draw_background<V>(
+        &self,
+        viewer: &mut V,
+        style: &SnarlStyle,
+        snarl_state: &SnarlState,
+        viewport: &Rect,
+        ui: &Ui,
+    ) where
+        V: SnarlViewer<T>,
+    {
+        let viewport = Viewport {
+            rect: *viewport,
+            scale: snarl_state.scale(),
+            offset: snarl_state.offset(),
+        };
+
+        match &style.bg_patterns {
+            Some(patterns) => {
+                // Patterns are drawn from bottom to top.
+                for pattern in patterns {
+                    viewer.draw_background(
+                        Some(pattern),
+                        &viewport,
+                        style,
+                        ui.style(),
+                        ui.painter(),
+                        self,
+                    );
+                }
+            }
+            None => {
+                viewer.draw_background(
+                    style.bg_pattern.as_ref(),
+                    &viewport,
+                    style,
+                    ui.style(),
+                    ui.painter(),
+                    self,
+                );
+            }
+        }
+    }
+
+    /// Changes viewport scale with zoom shortcuts from the style.
+    fn zoom_by_shortcuts(
+        &self,
+        style: &SnarlStyle,
+        snarl_id: Id,
+        viewport: Rect,
+        snarl_state: &mut SnarlState,
+        ui: &Ui,
+    ) {
+        let pressed = |shortcut: &Option<KeyboardShortcut>| match shortcut {
+            Some(shortcut) => ui.input_mut(|i| i.consume_shortcut(shortcut)),
+            None => false,
+        };
+
+        let scale = snarl_state.scale();
+        let center = ZoomAnchor::Center;
+
+        if pressed(&style.zoom_in_shortcut) {
+            let scale = style.step_scale(scale, true);
+            snarl_state.zoom_to(scale, center, ViewportChangeReason::UserZoom, ui.ctx());
+        }
+        if pressed(&style.zoom_out_shortcut) {
+            let scale = style.step_scale(scale, false);
+            snarl_state.zoom_to(scale, center, ViewportChangeReason::UserZoom, ui.ctx());
+        }
+        if pressed(&style.zoom_reset_shortcut) {
+            let scale =
+                style.snap_scale(1.0f32.clamp(style.get_min_scale(), style.get_max_scale()));
+            snarl_state.zoom_to(scale, center, ViewportChangeReason::UserZoom, ui.ctx());
+        }
+        if pressed(&style.zoom_fit_shortcut) {
+            let rect = self.nodes_graph_rect(
+                self.nodes.iter().map(|(idx, _)| NodeId(idx)),
+                snarl_id,
+                ui.ctx(),
+            );
+            snarl_state.fit_rect(
+                rect,
+                viewport,
+                style,
+                ViewportChangeReason::UserZoom,
+                ui.ctx(),
+            );
+        }
+        if pressed(&style.zoom_fit_selection_shortcut) {
+            let rect = self.nodes_graph_rect(
+                snarl_state.selected_nodes().iter().copied(),
+                snarl_id,
+                ui.ctx(),
+            );
+            snarl_state.fit_rect(
+                rect,
+                viewport,
+                style,
+                ViewportChangeReason::UserZoom,
+                ui.ctx(),
+            );
+        }
+    }
+
+    /// Render [`Snarl`] using given viewer and style into the [`Ui`].
+    pub fn show<V>(&mut self, viewer: &mut V, style: &SnarlStyle, id_salt: impl Hash, ui: &mut Ui)
+    where
+        V: SnarlViewer<T>,
+    {
         #![allow(clippy::too_many_lines)]
 
         let snarl_id = ui.make_persistent_id(id_salt);
 
+        let mirrored = style.get_mirrored(ui.layout());
+
         // Draw background pattern.
         let bg_frame = style.get_bg_frame(ui.style());
 
@@ -665,11 +1362,17 @@ impl<T> Snarl<T> {
         });
 
         bg_frame.show(ui, |ui| {
-            let mut node_moved = None;
             let mut node_to_top = None;
 
             let mut bg_r = ui.allocate_rect(ui.max_rect(), Sense::click_and_drag());
             let viewport = bg_r.rect;
+
+            if !viewport.is_positive() {
+                // Nothing is visible, e.g. panel is collapsed.
+                // State is not touched, so the graph is fitted once there's room for it.
+                return;
+            }
+
             ui.set_clip_rect(viewport);
 
             let pivot = input.hover_pos.unwrap_or_else(|| viewport.center());
@@ -677,6 +1380,9 @@ impl<T> Snarl<T> {
             let mut snarl_state =
                 SnarlState::load(ui.ctx(), snarl_id, pivot, viewport, self, style);
 
+            snarl_state.apply_view_request(self, viewport, style, ui.ctx());
+            let following = snarl_state.follow_node(self, ui.ctx());
+
             ui.style_mut().zoom(snarl_state.scale());
 
             // let mut node_style: Style = (**ui.style()).clone();
@@ -693,27 +1399,86 @@ impl<T> Snarl<T> {
                 WireLayer::AboveNodes => None,
             };
 
-            // Zooming
+            // Zooming is suspended while a context menu is open
+            // to keep menu position consistent with the graph.
             match input.hover_pos {
                 Some(hover_pos)
-                    if viewport.contains(hover_pos) && ui.rect_contains_pointer(viewport) =>
+                    if !snarl_state.is_menu_open()
+                        && viewport.contains(hover_pos)
+                        && ui.rect_contains_pointer(viewport)
+                        && input.zoom_delta != 1.0 =>
                 {
-                    if input.zoom_delta != 1.0 {
-                        let new_scale = (snarl_state.scale()
-                            * input.zoom_delta.powf(style.get_scale_velocity()))
-                        .clamp(style.get_min_scale(), style.get_max_scale());
+                    let new_scale = (snarl_state.scale()
+                        * input.zoom_delta.powf(style.get_scale_velocity()))
+                    .clamp(style.get_min_scale(), style.get_max_scale());
 
-                        snarl_state.set_scale(new_scale);
-                    }
+                    snarl_state.set_scale(new_scale, ViewportChangeReason::UserZoom);
                 }
                 _ => {}
             }
+            // Zoom shortcuts are handled only while pointer is over the graph.
+            if input.hover_pos.is_some_and(|pos| viewport.contains(pos))
+                && ui.rect_contains_pointer(viewport)
+                && !ui.ctx().wants_keyboard_input()
+            {
+                self.zoom_by_shortcuts(style, snarl_id, viewport, &mut snarl_state, ui);
+            }
+
             let mut input_info = HashMap::new();
             let mut output_info = HashMap::new();
 
             let mut pin_hovered = None;
 
-            let draw_order = snarl_state.update_draw_order(self);
+            // Nodes are removed before drawing, so their wires and UI state are gone in the same frame.
+            if let Some(shortcut) = &style.delete_selected_shortcut {
+                if input.hover_pos.is_some_and(|pos| viewport.contains(pos))
+                    && ui.rect_contains_pointer(viewport)
+                    && !ui.ctx().wants_keyboard_input()
+                    && ui.input_mut(|i| i.consume_shortcut(shortcut))
+                {
+                    for (out_pin, in_pin) in snarl_state.selected_wires().to_vec() {
+                        if self.connected(out_pin, in_pin) {
+                            viewer.disconnect(
+                                &OutPin::new(self, out_pin),
+                                &InPin::new(self, in_pin),
+                                self,
+                            );
+                        }
+                    }
+                    snarl_state.prune_selected_wires(self);
+
+                    for node in snarl_state.selected_nodes().to_vec() {
+                        if self.nodes.contains(node.0) {
+                            viewer.remove_node(node, self);
+                        }
+                    }
+                    snarl_state.prune_selected_nodes(self);
+                }
+            }
+
+            let draw_order = snarl_state.update_draw_order(self, ui.ctx());
+
+            // Dragged nodes are moved before drawing,
+            // so they and their wires follow the pointer without a frame of lag.
+            self.drag_nodes(viewer, ui, snarl_id, &snarl_state, style, &input);
+
+            // Breakpoints are toggled before drawing, so nodes show new state in the same frame.
+            if let Some(shortcut) = &style.breakpoint_shortcut {
+                if !ui.ctx().wants_keyboard_input()
+                    && ui.input_mut(|i| i.consume_shortcut(shortcut))
+                {
+                    for &node in snarl_state.selected_nodes() {
+                        let has_breakpoint = self
+                            .nodes
+                            .get(node.0)
+                            .is_some_and(|info| viewer.breakpoint_state(&info.value).is_some());
+
+                        if has_breakpoint {
+                            viewer.toggle_breakpoint(node, self);
+                        }
+                    }
+                }
+            }
             let mut drag_released = false;
 
             let mut centers_sum = vec2(0.0, 0.0);
@@ -721,11 +1486,36 @@ impl<T> Snarl<T> {
 
             let mut node_rects = Vec::new();
 
+            // Opacity of nodes that are not fully opaque, applied to their wires as well.
+            let mut node_opacities = HashMap::new();
+
+            // Node dragged alone may be dropped onto a wire.
+            let dragged_frame = ui
+                .ctx()
+                .dragged_id()
+                .filter(|_| ui.input(|i| i.pointer.button_down(PointerButton::Primary)))
+                .filter(|_| !input.modifiers.shift && !input.modifiers.command);
+            let mut dragged_node_rect = None;
+
             for node_idx in draw_order {
                 if !self.nodes.contains(node_idx.0) {
                     continue;
                 }
 
+                let opacity = viewer
+                    .node_opacity(&self.nodes[node_idx.0].value)
+                    .clamp(0.0, 1.0);
+                let interactive = opacity >= style.get_min_interactive_opacity();
+
+                if opacity < 1.0 {
+                    node_opacities.insert(node_idx, opacity);
+                }
+
+                // Opacity is applied to everything drawn for the node,
+                // including frame, header, pins and content.
+                let ui_opacity = ui.opacity();
+                ui.multiply_opacity(opacity);
+
                 // show_node(node_idx);
                 let response = self.draw_node(
                     ui,
@@ -737,15 +1527,16 @@ impl<T> Snarl<T> {
                     &mut input_info,
                     &input,
                     &mut output_info,
+                    mirrored,
+                    interactive,
                 );
 
+                ui.set_opacity(ui_opacity);
+
                 if let Some(response) = response {
                     if let Some(v) = response.node_to_top {
                         node_to_top = Some(v);
                     }
-                    if let Some(v) = response.node_moved {
-                        node_moved = Some(v);
-                    }
                     if let Some(v) = response.pin_hovered {
                         pin_hovered = Some(v);
                     }
@@ -754,9 +1545,23 @@ impl<T> Snarl<T> {
                     centers_sum += response.final_rect.center().to_vec2();
                     centers_weight += 1;
 
-                    if snarl_state.is_rect_selection() {
+                    if interactive && snarl_state.is_rect_selection() {
                         node_rects.push((node_idx, response.final_rect));
                     }
+
+                    let dragged_alone = !snarl_state.selected_nodes().contains(&node_idx)
+                        || snarl_state.selected_nodes().len() == 1;
+
+                    if interactive
+                        && dragged_alone
+                        && dragged_frame
+                            == Some(NodeState::node_id(snarl_id, node_idx).with("frame"))
+                    {
+                        dragged_node_rect = Some((
+                            node_idx,
+                            snarl_state.graph_rect_to_screen(response.final_rect, viewport),
+                        ));
+                    }
                 }
             }
 
@@ -764,6 +1569,18 @@ impl<T> Snarl<T> {
             let mut hovered_wire_disconnect = false;
             let mut wire_shapes = Vec::new();
             let mut wire_hit = false;
+            let mut wire_widgets = Vec::new();
+            let mut wire_rects = Vec::new();
+            let select_style = style.get_select_style(snarl_state.scale(), ui.style());
+
+            // Wires under the dragged node and the one it would be inserted onto.
+            let mut insert_candidates = Vec::new();
+            let wire_insertion = snarl_state.wire_insertion(ui.ctx());
+
+            // Wire tooltip is not shown while a node is dragged over wires.
+            let node_dragged = dragged_node_rect.is_some() || wire_insertion.is_some();
+
+            let time = ui.input(|i| i.time);
 
             for wire in self.wires.iter() {
                 let Some(from_r) = output_info.get(&wire.out_pin) else {
@@ -773,7 +1590,52 @@ impl<T> Snarl<T> {
                     continue;
                 };
 
-                if !wire_hit && !snarl_state.has_new_wires() && bg_r.hovered() && !bg_r.dragged() {
+                // Wire is as opaque as the more transparent of its nodes.
+                let opacity = f32::min(
+                    node_opacities
+                        .get(&wire.out_pin.node)
+                        .copied()
+                        .unwrap_or(1.0),
+                    node_opacities
+                        .get(&wire.in_pin.node)
+                        .copied()
+                        .unwrap_or(1.0),
+                );
+                let interactive = opacity >= style.get_min_interactive_opacity();
+
+                let wire_frame_size = pick_wire_frame_size(
+                    wire_frame_size,
+                    from_r.wire_frame_size.zoomed(snarl_state.scale()),
+                    to_r.wire_frame_size.zoomed(snarl_state.scale()),
+                );
+
+                let (color, end_color) = match style.get_wire_color_mode() {
+                    WireColorMode::Mix => (mix_colors(from_r.pin_color, to_r.pin_color), None),
+                    WireColorMode::Gradient => (from_r.pin_color, Some(to_r.pin_color)),
+                };
+
+                let wire_info = viewer.wire_style(
+                    &wire.out_pin,
+                    &wire.in_pin,
+                    WireInfo {
+                        color,
+                        end_color,
+                        width: wire_width,
+                        style: pick_wire_style(
+                            style.get_wire_style(snarl_state.scale()),
+                            from_r.wire_style.zoomed(snarl_state.scale()),
+                            to_r.wire_style.zoomed(snarl_state.scale()),
+                        ),
+                    },
+                    self,
+                );
+
+                if interactive
+                    && !wire_hit
+                    && !snarl_state.has_new_wires()
+                    && bg_r.hovered()
+                    && !bg_r.dragged()
+                {
                     // Try to find hovered wire
                     // If not draggin new wire
                     // And not hovering over item above.
@@ -786,13 +1648,10 @@ impl<T> Snarl<T> {
                             style.get_downscale_wire_frame(),
                             from_r.pos,
                             to_r.pos,
-                            wire_width.max(1.5),
-                            pick_wire_style(
-                                style.get_wire_style(snarl_state.scale()),
-                                from_r.wire_style,
-                                to_r.wire_style,
-                            )
-                            .zoomed(snarl_state.scale()),
+                            from_r.side,
+                            to_r.side,
+                            wire_info.width.max(1.5),
+                            wire_info.style,
                         );
 
                         if wire_hit {
@@ -801,6 +1660,16 @@ impl<T> Snarl<T> {
                             //Remove hovered wire by second click
                             hovered_wire_disconnect |= bg_r.clicked_by(PointerButton::Secondary);
 
+                            // Wires are selected with the same modifiers as nodes.
+                            if bg_r.clicked_by(PointerButton::Primary) {
+                                let wire = (wire.out_pin, wire.in_pin);
+                                if input.modifiers.shift {
+                                    snarl_state.select_one_wire(input.modifiers.command, wire);
+                                } else if input.modifiers.command {
+                                    snarl_state.deselect_one_wire(wire);
+                                }
+                            }
+
                             // Background is not hovered then.
                             bg_r.hovered = false;
                             bg_r.clicked = false;
@@ -808,13 +1677,113 @@ impl<T> Snarl<T> {
                     }
                 }
 
-                let color = mix_colors(from_r.pin_color, to_r.pin_color);
+                if let Some((node, rect)) = dragged_node_rect {
+                    if wire.out_pin.node != node
+                        && wire.in_pin.node != node
+                        && wire_intersects_rect(
+                            rect,
+                            wire_frame_size,
+                            style.get_upscale_wire_frame(),
+                            style.get_downscale_wire_frame(),
+                            from_r.pos,
+                            to_r.pos,
+                            from_r.side,
+                            to_r.side,
+                            wire_info.width,
+                            wire_info.style,
+                        )
+                    {
+                        insert_candidates.push((wire.out_pin, wire.in_pin));
+                    }
+                }
+
+                // Wire the dragged node would be inserted onto is highlighted as hovered.
+                if wire_insertion.is_some_and(|insertion| {
+                    insertion.from == wire.out_pin && insertion.to == wire.in_pin
+                }) {
+                    hovered_wire = Some(wire);
+                }
+
+                let (mut color, mut end_color) = (wire_info.color, wire_info.end_color);
 
-                let mut draw_width = wire_width;
+                // Hover and pulse are applied on top of the viewer's style.
+                let mut draw_width = wire_info.width;
                 if hovered_wire == Some(wire) {
                     draw_width *= 1.5;
                 }
 
+                if viewer.wire_pulse(&wire.out_pin, &wire.in_pin, self) {
+                    snarl_state.start_wire_pulse(wire.out_pin, wire.in_pin, time);
+                }
+
+                if let Some(pulse) = snarl_state.wire_pulse(wire.out_pin, wire.in_pin, time) {
+                    color = color.lerp_to_gamma(Color32::WHITE, pulse * 0.7);
+                    end_color =
+                        end_color.map(|color| color.lerp_to_gamma(Color32::WHITE, pulse * 0.7));
+                    draw_width *= pulse.mul_add(0.5, 1.0);
+                }
+
+                if opacity < 1.0 {
+                    color = color.gamma_multiply(opacity);
+                    end_color = end_color.map(|color| color.gamma_multiply(opacity));
+                }
+
+                if interactive && style.get_select_wires() && snarl_state.is_rect_selection() {
+                    let (_, bb) = wire_midpoint(
+                        wire_frame_size,
+                        style.get_upscale_wire_frame(),
+                        style.get_downscale_wire_frame(),
+                        from_r.pos,
+                        to_r.pos,
+                        from_r.side,
+                        to_r.side,
+                        wire_info.style,
+                    );
+                    wire_rects.push((
+                        (wire.out_pin, wire.in_pin),
+                        snarl_state.screen_rect_to_graph(bb, viewport),
+                    ));
+                }
+
+                let flow = to_r
+                    .wire_flow
+                    .or(from_r.wire_flow)
+                    .or(style.wire_flow)
+                    .zoomed(snarl_state.scale());
+
+                // Selection is drawn underneath the wire.
+                if snarl_state
+                    .selected_wires()
+                    .contains(&(wire.out_pin, wire.in_pin))
+                {
+                    draw_wire(
+                        ui,
+                        &mut wire_shapes,
+                        wire_frame_size,
+                        style.get_upscale_wire_frame(),
+                        style.get_downscale_wire_frame(),
+                        from_r.pos,
+                        to_r.pos,
+                        from_r.side,
+                        to_r.side,
+                        Stroke::new(
+                            select_style.stroke.width.mul_add(2.0, draw_width),
+                            select_style.stroke.color,
+                        ),
+                        None,
+                        wire_info.style,
+                        // Patterns of dashed and dotted wires move along with the wire.
+                        flow.filter(|_| {
+                            matches!(
+                                wire_info.style,
+                                WireStyle::Dashed { .. } | WireStyle::Dotted { .. }
+                            )
+                        }),
+                        WireEndMarker::None,
+                        0.0,
+                    );
+                }
+
                 draw_wire(
                     ui,
                     &mut wire_shapes,
@@ -823,13 +1792,108 @@ impl<T> Snarl<T> {
                     style.get_downscale_wire_frame(),
                     from_r.pos,
                     to_r.pos,
+                    from_r.side,
+                    to_r.side,
                     Stroke::new(draw_width, color),
-                    pick_wire_style(
-                        style.get_wire_style(snarl_state.scale()),
-                        from_r.wire_style.zoomed(snarl_state.scale()),
-                        to_r.wire_style.zoomed(snarl_state.scale()),
-                    ),
+                    end_color,
+                    wire_info.style,
+                    flow,
+                    to_r.wire_end_marker
+                        .or(from_r.wire_end_marker)
+                        .zoomed(snarl_state.scale())
+                        .unwrap_or_else(|| style.get_wire_end_marker(snarl_state.scale())),
+                    to_r.size * 0.5,
                 );
+
+                if viewer.has_wire_widget(&wire.out_pin, &wire.in_pin, self) {
+                    let (midpoint, bb) = wire_midpoint(
+                        wire_frame_size,
+                        style.get_upscale_wire_frame(),
+                        style.get_downscale_wire_frame(),
+                        from_r.pos,
+                        to_r.pos,
+                        from_r.side,
+                        to_r.side,
+                        wire_info.style,
+                    );
+
+                    if ui.is_rect_visible(bb) {
+                        wire_widgets.push((wire.out_pin, wire.in_pin, midpoint, interactive));
+                    }
+                }
+            }
+
+            // Node is inserted only onto a single wire it overlaps.
+            let wire_insertion = match (dragged_node_rect, &insert_candidates[..]) {
+                (Some((node, _)), &[(from, to)]) => viewer
+                    .can_insert_on_wire(node, from, to, self)
+                    .map(|(input, output)| WireInsertion {
+                        node,
+                        from,
+                        to,
+                        input,
+                        output,
+                    }),
+                _ => None,
+            };
+            snarl_state.set_wire_insertion(wire_insertion, ui.ctx());
+
+            if snarl_state.prune_wire_pulses(time) {
+                ui.ctx().request_repaint();
+            }
+
+            // Only sizes of widgets shown in this frame are kept.
+            let old_sizes = snarl_state.take_wire_widget_sizes(ui.ctx());
+            let mut wire_widget_sizes = HashMap::with_capacity(wire_widgets.len());
+            for (out_pin, in_pin, midpoint, interactive) in wire_widgets {
+                // Previous widget may have removed the wire.
+                if !self.connected(out_pin, in_pin) {
+                    continue;
+                }
+
+                let size = old_sizes
+                    .get(&(out_pin, in_pin))
+                    .copied()
+                    .unwrap_or(Vec2::ZERO);
+                let new_size = self.show_wire_widget(
+                    viewer,
+                    out_pin,
+                    in_pin,
+                    midpoint,
+                    size,
+                    interactive,
+                    snarl_id,
+                    &snarl_state,
+                    ui,
+                );
+                if new_size != size {
+                    ui.ctx().request_repaint();
+                }
+                wire_widget_sizes.insert((out_pin, in_pin), new_size);
+            }
+            snarl_state.set_wire_widget_sizes(wire_widget_sizes, ui.ctx());
+
+            if let Some(wire) = hovered_wire {
+                if !hovered_wire_disconnect
+                    && !node_dragged
+                    && viewer.has_wire_hover(&wire.out_pin, &wire.in_pin, self)
+                {
+                    egui::show_tooltip_at_pointer(
+                        ui.ctx(),
+                        ui.layer_id(),
+                        ui.id()
+                            .with(("snarl-wire-hover", wire.out_pin, wire.in_pin)),
+                        |ui| {
+                            viewer.show_wire_hover(
+                                &OutPin::new(self, wire.out_pin),
+                                &InPin::new(self, wire.in_pin),
+                                ui,
+                                snarl_state.scale(),
+                                self,
+                            );
+                        },
+                    );
+                }
             }
 
             //Remove hovered wire by second click
@@ -848,11 +1912,11 @@ impl<T> Snarl<T> {
             }
 
             if bg_r.dragged_by(PointerButton::Primary) {
-                if snarl_state.is_rect_selection() && input.hover_pos.is_some() {
-                    let screen_pos = input.hover_pos.unwrap();
+                if let (true, Some(screen_pos)) = (snarl_state.is_rect_selection(), input.hover_pos)
+                {
                     let graph_pos = snarl_state.screen_pos_to_graph(screen_pos, viewport);
                     snarl_state.update_rect_selection(graph_pos);
-                } else {
+                } else if !snarl_state.is_menu_open() && !following {
                     snarl_state.pan(-bg_r.drag_delta());
                 }
             }
@@ -873,10 +1937,28 @@ impl<T> Snarl<T> {
                         }
                     });
 
+                    let select_wires = wire_rects.into_iter().filter_map(|(wire, rect)| {
+                        let select = if style.get_select_rect_contained() {
+                            select_rect.contains_rect(rect)
+                        } else {
+                            select_rect.intersects(rect)
+                        };
+
+                        if select {
+                            Some(wire)
+                        } else {
+                            None
+                        }
+                    });
+
                     if input.modifiers.command {
                         snarl_state.deselect_many_nodes(select_nodes);
+                        snarl_state.deselect_many_wires(select_wires);
                     } else {
                         snarl_state.select_many_nodes(!input.modifiers.shift, select_nodes);
+                        if style.get_select_wires() {
+                            snarl_state.select_many_wires(!input.modifiers.shift, select_wires);
+                        }
                     }
 
                     snarl_state.stop_rect_selection();
@@ -906,16 +1988,20 @@ impl<T> Snarl<T> {
             }
 
             // Do centering unless no nodes are present.
-            if style.get_centering() && bg_r.double_clicked() && centers_weight > 0 {
+            if style.get_centering() && !following && bg_r.double_clicked() && centers_weight > 0 {
                 #[allow(clippy::cast_precision_loss)]
                 {
                     centers_sum /= centers_weight as f32;
                 }
-                snarl_state.set_offset(centers_sum * snarl_state.scale());
+                snarl_state.set_offset(
+                    centers_sum * snarl_state.scale(),
+                    ViewportChangeReason::DoubleClickCenter,
+                );
             }
 
             if input.modifiers.command && bg_r.clicked_by(PointerButton::Primary) {
                 snarl_state.deselect_all_nodes();
+                snarl_state.deselect_all_wires();
             }
 
             // Wire end position will be overridden when link graph menu is opened.
@@ -945,12 +2031,20 @@ impl<T> Snarl<T> {
                             );
                         }
                     }
-                    (Some(new_wires), None) if bg_r.hovered() => {
-                        // A new pin is dropped without connecting it anywhere. This
+                    (Some(new_wires), None)
+                        if (bg_r.hovered() || snarl_state.is_wire_target_rejected())
+                            && !new_wires.is_empty() =>
+                    {
+                        // New pins are dropped without connecting them anywhere. This
                         // will open a pop-up window for creating a new node.
+                        // Dropping on a pin that rejects the wires is handled the same way.
+                        // All dragged pins are passed to the menu, so a new node
+                        // can be connected to all of them at once.
                         snarl_state.revert_take_wires(new_wires);
 
                         // Force open context menu.
+                        // Background is not hovered when wires are dropped on a node.
+                        bg_r.hovered = true;
                         bg_r.long_touched = true;
                     }
                     _ => {}
@@ -970,6 +2064,7 @@ impl<T> Snarl<T> {
                     if viewer.has_dropped_wire_menu(pins, self) {
                         bg_r.context_menu(|ui| {
                             is_menu_visible = true;
+                            snarl_state.set_menu_open();
                             if !snarl_state.is_link_menu_open() {
                                 // Mark link menu is now visible.
                                 snarl_state.open_link_menu();
@@ -997,6 +2092,7 @@ impl<T> Snarl<T> {
                 {
                     bg_r.context_menu(|ui| {
                         is_menu_visible = true;
+                        snarl_state.set_menu_open();
                         if !snarl_state.is_link_menu_open() {
                             // Mark link menu is now visible.
                             snarl_state.open_link_menu();
@@ -1017,46 +2113,71 @@ impl<T> Snarl<T> {
                 snarl_state.close_link_menu();
             }
 
+            let new_wire_color = style
+                .invalid_wire_color
+                .filter(|_| snarl_state.is_wire_target_rejected());
+
             match snarl_state.new_wires() {
                 None => {}
                 Some(NewWires::In(pins)) => {
                     for pin in pins {
                         let from_pos = wire_end_pos;
-                        let to_r = &input_info[pin];
+                        let Some(to_r) = input_info.get(pin) else {
+                            continue;
+                        };
 
                         draw_wire(
                             ui,
                             &mut wire_shapes,
-                            wire_frame_size,
+                            to_r.wire_frame_size
+                                .zoomed(snarl_state.scale())
+                                .unwrap_or(wire_frame_size),
                             style.get_upscale_wire_frame(),
                             style.get_downscale_wire_frame(),
                             from_pos,
                             to_r.pos,
-                            Stroke::new(wire_width, to_r.pin_color),
+                            to_r.side.opposite(),
+                            to_r.side,
+                            Stroke::new(wire_width, new_wire_color.unwrap_or(to_r.pin_color)),
+                            None,
                             to_r.wire_style
                                 .zoomed(snarl_state.scale())
                                 .unwrap_or_else(|| style.get_wire_style(snarl_state.scale())),
+                            None,
+                            WireEndMarker::None,
+                            0.0,
                         );
                     }
                 }
                 Some(NewWires::Out(pins)) => {
                     for pin in pins {
-                        let from_r = &output_info[pin];
+                        let Some(from_r) = output_info.get(pin) else {
+                            continue;
+                        };
                         let to_pos = wire_end_pos;
 
                         draw_wire(
                             ui,
                             &mut wire_shapes,
-                            wire_frame_size,
+                            from_r
+                                .wire_frame_size
+                                .zoomed(snarl_state.scale())
+                                .unwrap_or(wire_frame_size),
                             style.get_upscale_wire_frame(),
                             style.get_downscale_wire_frame(),
                             from_r.pos,
                             to_pos,
-                            Stroke::new(wire_width, from_r.pin_color),
+                            from_r.side,
+                            from_r.side.opposite(),
+                            Stroke::new(wire_width, new_wire_color.unwrap_or(from_r.pin_color)),
+                            None,
                             from_r
                                 .wire_style
                                 .zoomed(snarl_state.scale())
                                 .unwrap_or_else(|| style.get_wire_style(snarl_state.scale())),
+                            None,
+                            WireEndMarker::None,
+                            0.0,
                         );
                     }
                 }
@@ -1080,25 +2201,186 @@ impl<T> Snarl<T> {
                 }
             }
 
-            if let Some((node, delta)) = node_moved {
-                if self.nodes.contains(node.0) {
-                    ui.ctx().request_repaint();
-                    if snarl_state.selected_nodes().contains(&node) {
-                        for node in snarl_state.selected_nodes() {
-                            let node = &mut self.nodes[node.0];
-                            node.pos += delta;
-                        }
-                    } else {
-                        let node = &mut self.nodes[node.0];
-                        node.pos += delta;
-                    }
-                }
+            if let Some((prev, viewport, reason)) = snarl_state.viewport_change(viewport) {
+                viewer.viewport_changed(&prev, &viewport, reason, self);
+            }
+
+            let effects = viewer.take_effects();
+            if !effects.is_empty() {
+                ui.ctx().request_repaint();
+                self.apply_effects(effects);
             }
 
             snarl_state.store(self, ui.ctx());
         });
     }
 
+    /// Returns input pins that have no wires connected.
+    ///
+    /// Number of inputs of each node is provided by the `viewer`,
+    /// since graph itself doesn't know it.
+    /// Pins are ordered by node id and then by input index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::Ui;
+    /// # use egui_snarl::{InPin, InPinId, OutPin, OutPinId, Snarl, ui::{PinInfo, SnarlViewer}};
+    /// struct Viewer;
+    ///
+    /// impl SnarlViewer<()> for Viewer {
+    ///     fn title(&mut self, _: &()) -> String { String::new() }
+    ///     fn inputs(&mut self, _: &()) -> usize { 2 }
+    ///     fn outputs(&mut self, _: &()) -> usize { 1 }
+    ///     fn show_input(&mut self, _: &InPin, _: &mut Ui, _: f32, _: &mut Snarl<()>) -> PinInfo { PinInfo::circle() }
+    ///     fn show_output(&mut self, _: &OutPin, _: &mut Ui, _: f32, _: &mut Snarl<()>) -> PinInfo { PinInfo::circle() }
+    /// }
+    ///
+    /// let mut snarl = Snarl::new();
+    /// let a = snarl.insert_node(egui::pos2(0.0, 0.0), ());
+    /// let b = snarl.insert_node(egui::pos2(100.0, 0.0), ());
+    /// snarl.connect(OutPinId { node: a, output: 0 }, InPinId { node: b, input: 1 });
+    ///
+    /// assert_eq!(
+    ///     snarl.unconnected_inputs(&mut Viewer),
+    ///     [InPinId { node: a, input: 0 }, InPinId { node: a, input: 1 }, InPinId { node: b, input: 0 }],
+    /// );
+    /// assert_eq!(snarl.unconnected_outputs(&mut Viewer), [OutPinId { node: b, output: 0 }]);
+    /// ```
+    pub fn unconnected_inputs<V>(&self, viewer: &mut V) -> Vec<InPinId>
+    where
+        V: SnarlViewer<T>,
+    {
+        let connected = self
+            .wires()
+            .map(|(_, in_pin)| in_pin)
+            .collect::<HashSet<_>>();
+
+        let mut pins = Vec::new();
+        for (node, info) in self.nodes_ids_data() {
+            for input in 0..viewer.inputs(&info.value) {
+                let pin = InPinId { node, input };
+                if !connected.contains(&pin) {
+                    pins.push(pin);
+                }
+            }
+        }
+        pins
+    }
+
+    /// Returns output pins that have no wires connected.
+    ///
+    /// Number of outputs of each node is provided by the `viewer`,
+    /// since graph itself doesn't know it.
+    /// Pins are ordered by node id and then by output index.
+    ///
+    /// See [`Snarl::unconnected_inputs`] for example.
+    pub fn unconnected_outputs<V>(&self, viewer: &mut V) -> Vec<OutPinId>
+    where
+        V: SnarlViewer<T>,
+    {
+        let connected = self
+            .wires()
+            .map(|(out_pin, _)| out_pin)
+            .collect::<HashSet<_>>();
+
+        let mut pins = Vec::new();
+        for (node, info) in self.nodes_ids_data() {
+            for output in 0..viewer.outputs(&info.value) {
+                let pin = OutPinId { node, output };
+                if !connected.contains(&pin) {
+                    pins.push(pin);
+                }
+            }
+        }
+        pins
+    }
+
+    /// Shows viewer's widget centered on the wire's midpoint.
+    ///
+    /// Widget is centered using its `size` from the previous frame.
+    /// Returns size of the widget in this frame.
+    #[allow(clippy::too_many_arguments)]
+    fn show_wire_widget<V>(
+        &mut self,
+        viewer: &mut V,
+        out_pin: OutPinId,
+        in_pin: InPinId,
+        midpoint: Pos2,
+        size: Vec2,
+        interactive: bool,
+        snarl_id: Id,
+        snarl_state: &SnarlState,
+        ui: &mut Ui,
+    ) -> Vec2
+    where
+        V: SnarlViewer<T>,
+    {
+        let id = snarl_id.with(("wire-widget", out_pin, in_pin));
+        let rect = Rect::from_center_size(midpoint, size);
+
+        // Widget covers the wire and the background, so they don't react to the pointer over it.
+        ui.interact(rect, id, Sense::click_and_drag());
+
+        let mut widget_ui = ui.new_child(
+            UiBuilder::new()
+                .max_rect(rect)
+                .layout(Layout::top_down(Align::Center))
+                .id_salt(id),
+        );
+        widget_ui.style_mut().wrap_mode = Some(TextWrapMode::Extend);
+
+        if !interactive {
+            widget_ui.disable();
+        }
+
+        viewer.show_wire_widget(
+            &OutPin::new(self, out_pin),
+            &InPin::new(self, in_pin),
+            &mut widget_ui,
+            snarl_state.scale(),
+            self,
+        );
+
+        widget_ui.min_rect().size()
+    }
+
+    /// Checks if new wires dragged from outputs can be connected to the input pin.
+    fn accepts_new_wires_in<V>(
+        &self,
+        viewer: &mut V,
+        in_pin: &InPin,
+        snarl_state: &SnarlState,
+    ) -> bool
+    where
+        V: SnarlViewer<T>,
+    {
+        match snarl_state.new_wires() {
+            Some(NewWires::Out(out_pins)) => out_pins
+                .iter()
+                .all(|&out_pin| viewer.can_connect(&OutPin::new(self, out_pin), in_pin, self)),
+            _ => true,
+        }
+    }
+
+    /// Checks if new wires dragged from inputs can be connected to the output pin.
+    fn accepts_new_wires_out<V>(
+        &self,
+        viewer: &mut V,
+        out_pin: &OutPin,
+        snarl_state: &SnarlState,
+    ) -> bool
+    where
+        V: SnarlViewer<T>,
+    {
+        match snarl_state.new_wires() {
+            Some(NewWires::In(in_pins)) => in_pins
+                .iter()
+                .all(|&in_pin| viewer.can_connect(out_pin, &InPin::new(self, in_pin), self)),
+            _ => true,
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn draw_inputs<V>(
         &mut self,
@@ -1111,9 +2393,10 @@ impl<T> Snarl<T> {
         inputs_rect: Rect,
         clip_rect: Rect,
         viewport: Rect,
-        input_x: f32,
+        side: Option<PinSide>,
+        pin_layout: &PinLayout,
         min_pin_y: f32,
-        input_spacing: Option<f32>,
+        pin_stubs: Option<PinStubs>,
         snarl_state: &mut SnarlState,
         input: &Input,
         input_positions: &mut HashMap<InPinId, PinResponse>,
@@ -1124,20 +2407,28 @@ impl<T> Snarl<T> {
         let mut drag_released = false;
         let mut pin_hovered = None;
 
-        // Input pins on the left.
+        // Only pins on the `side` are shown if it is set.
+        let column_side = side.unwrap_or(pin_layout.input_side);
+
+        // Input pins on the left, or on the right if mirrored.
         let inputs_ui = &mut ui.new_child(
             UiBuilder::new()
                 .max_rect(inputs_rect)
-                .layout(Layout::top_down(Align::Min))
-                .id_salt("inputs"),
+                .layout(Layout::top_down(column_side.align()))
+                .id_salt(("inputs", column_side)),
         );
 
         inputs_ui.set_clip_rect(clip_rect.intersect(viewport));
 
-        for in_pin in inputs {
+        for (idx, in_pin) in inputs.iter().enumerate() {
+            let pin_side = pin_layout.input_sides[idx];
+            if side.is_some_and(|side| side != pin_side) {
+                continue;
+            }
+
             // Show input pin.
-            inputs_ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
-                if let Some(input_spacing) = input_spacing {
+            inputs_ui.with_layout(pin_side.row_layout(), |ui| {
+                if let Some(input_spacing) = pin_layout.spacing(pin_side) {
                     ui.allocate_space(vec2(input_spacing, pin_size));
                 }
 
@@ -1150,14 +2441,28 @@ impl<T> Snarl<T> {
                     return;
                 }
 
+                if pin_info.invisible {
+                    // Row content is laid out, but there is no pin to interact with.
+                    return;
+                }
+
                 let y1 = ui.min_rect().max.y;
 
                 // ui.end_row();
 
                 // Centered vertically.
-                let y = min_pin_y.max((y0 + y1) * 0.5);
+                let mut y = min_pin_y.max((y0 + y1) * 0.5);
+                let mut pin_size = pin_size;
 
-                let pin_pos = pos2(input_x, y);
+                if let Some(pin_stubs) = pin_stubs {
+                    let (stub_idx, stub_count) = pin_layout.input_stub(idx);
+                    (y, pin_size) = pin_stubs.place(stub_idx, stub_count, y, pin_size);
+                }
+
+                let pin_pos = pos2(pin_layout.x(pin_side), y);
+
+                // Row with pin's content, used to detect hovering of the pin.
+                let row_rect = ui.min_rect().intersect(ui.clip_rect());
 
                 // Interact with pin shape.
                 ui.set_clip_rect(viewport);
@@ -1181,8 +2486,32 @@ impl<T> Snarl<T> {
                         }
                     }
                 }
+                let detach = !in_pin.remotes.is_empty()
+                    && style
+                        .detach_wire_modifiers
+                        .is_some_and(|modifiers| input.modifiers.matches_logically(modifiers));
+
                 if r.drag_started_by(PointerButton::Primary) {
-                    if input.modifiers.command {
+                    if detach {
+                        let pointer = input.interact_pos.unwrap_or(pin_pos);
+                        if let Some(remote) = snarl_state.nearest_remote(
+                            self,
+                            &in_pin.remotes,
+                            |pin| pin.node,
+                            snarl_state.screen_pos_to_graph(pointer, viewport),
+                            ui.ctx(),
+                        ) {
+                            viewer.disconnect(
+                                &OutPin::new(self, remote),
+                                &InPin::new(self, in_pin.id),
+                                self,
+                            );
+                            // Viewer may refuse to disconnect the wire.
+                            if !self.connected(remote, in_pin.id) {
+                                snarl_state.start_new_wires_out(&[remote]);
+                            }
+                        }
+                    } else if input.modifiers.command {
                         snarl_state.start_new_wires_out(&in_pin.remotes);
                         if !input.modifiers.shift {
                             self.drop_inputs(in_pin.id);
@@ -1199,10 +2528,26 @@ impl<T> Snarl<T> {
                     drag_released = true;
                 }
 
+                // Pins of non-interactive nodes are not hovered, so wires can't be dropped on them.
+                let mut hovered = ui.is_enabled()
+                    && input
+                        .hover_pos
+                        .is_some_and(|pos| row_rect.contains(pos) || r.rect.contains(pos));
+
+                // Nor are pins that can't accept dragged wires.
+                if hovered && !self.accepts_new_wires_in(viewer, in_pin, snarl_state) {
+                    snarl_state.reject_wire_target();
+                    hovered = false;
+                }
+
+                if hovered {
+                    snarl_state.set_hovered_pin(AnyPin::In(in_pin.id));
+                }
+
                 let mut visual_pin_size = pin_size;
 
                 match input.hover_pos {
-                    Some(hover_pos) if r.rect.contains(hover_pos) => {
+                    Some(hover_pos) if hovered && r.rect.contains(hover_pos) => {
                         if input.modifiers.shift {
                             snarl_state.add_new_wire_in(in_pin.id);
                         } else if input.secondary_pressed {
@@ -1220,7 +2565,7 @@ impl<T> Snarl<T> {
                 let pin_color = viewer.draw_input_pin(
                     in_pin,
                     &pin_info,
-                    r.rect.center(),
+                    pin_pos,
                     visual_pin_size,
                     style,
                     ui.style(),
@@ -1229,12 +2574,22 @@ impl<T> Snarl<T> {
                     self,
                 );
 
+                // Wire is attached exactly where the pin is drawn.
+                // Position comes from layout, so clipping of the pin never moves the wire.
                 input_positions.insert(
                     in_pin.id,
                     PinResponse {
-                        pos: r.rect.center(),
+                        pos: pin_pos,
+                        size: pin_info
+                            .size
+                            .zoomed(snarl_state.scale())
+                            .unwrap_or(pin_size),
                         pin_color,
                         wire_style: pin_info.wire_style,
+                        wire_end_marker: pin_info.wire_end_marker,
+                        wire_flow: pin_info.wire_flow,
+                        wire_frame_size: pin_info.wire_frame_size,
+                        side: pin_side,
                     },
                 );
             });
@@ -1262,9 +2617,10 @@ impl<T> Snarl<T> {
         outputs_rect: Rect,
         clip_rect: Rect,
         viewport: Rect,
-        output_x: f32,
+        side: Option<PinSide>,
+        pin_layout: &PinLayout,
         min_pin_y: f32,
-        output_spacing: Option<f32>,
+        pin_stubs: Option<PinStubs>,
         snarl_state: &mut SnarlState,
         input: &Input,
         output_positions: &mut HashMap<OutPinId, PinResponse>,
@@ -1275,21 +2631,29 @@ impl<T> Snarl<T> {
         let mut drag_released = false;
         let mut pin_hovered = None;
 
+        // Only pins on the `side` are shown if it is set.
+        let column_side = side.unwrap_or(pin_layout.input_side.opposite());
+
         let outputs_ui = &mut ui.new_child(
             UiBuilder::new()
                 .max_rect(outputs_rect)
-                .layout(Layout::top_down(Align::Max))
-                .id_salt("outputs"),
+                .layout(Layout::top_down(column_side.align()))
+                .id_salt(("outputs", column_side)),
         );
 
         outputs_ui.set_clip_rect(clip_rect.intersect(viewport));
 
-        // Output pins on the right.
-        for out_pin in outputs {
+        // Output pins on the right, or on the left if mirrored.
+        for (idx, out_pin) in outputs.iter().enumerate() {
+            let pin_side = pin_layout.output_sides[idx];
+            if side.is_some_and(|side| side != pin_side) {
+                continue;
+            }
+
             // Show output pin.
-            outputs_ui.with_layout(Layout::right_to_left(Align::Min), |ui| {
+            outputs_ui.with_layout(pin_side.row_layout(), |ui| {
                 // Allocate space for pin shape.
-                if let Some(output_spacing) = output_spacing {
+                if let Some(output_spacing) = pin_layout.spacing(pin_side) {
                     ui.allocate_space(vec2(output_spacing, pin_size));
                 }
 
@@ -1302,14 +2666,28 @@ impl<T> Snarl<T> {
                     return;
                 }
 
+                if pin_info.invisible {
+                    // Row content is laid out, but there is no pin to interact with.
+                    return;
+                }
+
                 let y1 = ui.min_rect().max.y;
 
                 // ui.end_row();
 
                 // Centered vertically.
-                let y = min_pin_y.max((y0 + y1) * 0.5);
+                let mut y = min_pin_y.max((y0 + y1) * 0.5);
+                let mut pin_size = pin_size;
+
+                if let Some(pin_stubs) = pin_stubs {
+                    let (stub_idx, stub_count) = pin_layout.output_stub(idx);
+                    (y, pin_size) = pin_stubs.place(stub_idx, stub_count, y, pin_size);
+                }
+
+                let pin_pos = pos2(pin_layout.x(pin_side), y);
 
-                let pin_pos = pos2(output_x, y);
+                // Row with pin's content, used to detect hovering of the pin.
+                let row_rect = ui.min_rect().intersect(ui.clip_rect());
 
                 ui.set_clip_rect(viewport);
 
@@ -1332,8 +2710,32 @@ impl<T> Snarl<T> {
                         }
                     }
                 }
+                let detach = !out_pin.remotes.is_empty()
+                    && style
+                        .detach_wire_modifiers
+                        .is_some_and(|modifiers| input.modifiers.matches_logically(modifiers));
+
                 if r.drag_started_by(PointerButton::Primary) {
-                    if input.modifiers.command {
+                    if detach {
+                        let pointer = input.interact_pos.unwrap_or(pin_pos);
+                        if let Some(remote) = snarl_state.nearest_remote(
+                            self,
+                            &out_pin.remotes,
+                            |pin| pin.node,
+                            snarl_state.screen_pos_to_graph(pointer, viewport),
+                            ui.ctx(),
+                        ) {
+                            viewer.disconnect(
+                                &OutPin::new(self, out_pin.id),
+                                &InPin::new(self, remote),
+                                self,
+                            );
+                            // Viewer may refuse to disconnect the wire.
+                            if !self.connected(out_pin.id, remote) {
+                                snarl_state.start_new_wires_in(&[remote]);
+                            }
+                        }
+                    } else if input.modifiers.command {
                         snarl_state.start_new_wires_in(&out_pin.remotes);
 
                         if !input.modifiers.shift {
@@ -1351,9 +2753,25 @@ impl<T> Snarl<T> {
                     drag_released = true;
                 }
 
+                // Pins of non-interactive nodes are not hovered, so wires can't be dropped on them.
+                let mut hovered = ui.is_enabled()
+                    && input
+                        .hover_pos
+                        .is_some_and(|pos| row_rect.contains(pos) || r.rect.contains(pos));
+
+                // Nor are pins that can't accept dragged wires.
+                if hovered && !self.accepts_new_wires_out(viewer, out_pin, snarl_state) {
+                    snarl_state.reject_wire_target();
+                    hovered = false;
+                }
+
+                if hovered {
+                    snarl_state.set_hovered_pin(AnyPin::Out(out_pin.id));
+                }
+
                 let mut visual_pin_size = pin_size;
                 match input.hover_pos {
-                    Some(hover_pos) if r.rect.contains(hover_pos) => {
+                    Some(hover_pos) if hovered && r.rect.contains(hover_pos) => {
                         if input.modifiers.shift {
                             snarl_state.add_new_wire_out(out_pin.id);
                         } else if input.secondary_pressed {
@@ -1371,7 +2789,7 @@ impl<T> Snarl<T> {
                 let pin_color = viewer.draw_output_pin(
                     out_pin,
                     &pin_info,
-                    r.rect.center(),
+                    pin_pos,
                     visual_pin_size,
                     style,
                     ui.style(),
@@ -1380,12 +2798,22 @@ impl<T> Snarl<T> {
                     self,
                 );
 
+                // Wire is attached exactly where the pin is drawn.
+                // Position comes from layout, so clipping of the pin never moves the wire.
                 output_positions.insert(
                     out_pin.id,
                     PinResponse {
-                        pos: r.rect.center(),
+                        pos: pin_pos,
+                        size: pin_info
+                            .size
+                            .zoomed(snarl_state.scale())
+                            .unwrap_or(pin_size),
                         pin_color,
                         wire_style: pin_info.wire_style,
+                        wire_end_marker: pin_info.wire_end_marker,
+                        wire_flow: pin_info.wire_flow,
+                        wire_frame_size: pin_info.wire_frame_size,
+                        side: pin_side,
                     },
                 );
             });
@@ -1400,6 +2828,122 @@ impl<T> Snarl<T> {
         }
     }
 
+    /// Draws pins placed on the `side` of the node in one column.
+    /// Pins that are on this side by default go first, followed by pins moved from the other side.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_pins_column<V>(
+        &mut self,
+        viewer: &mut V,
+        node: NodeId,
+        inputs: &[InPin],
+        outputs: &[OutPin],
+        pin_size: f32,
+        style: &SnarlStyle,
+        ui: &mut Ui,
+        column_rect: Rect,
+        clip_rect: Rect,
+        viewport: Rect,
+        side: PinSide,
+        pin_layout: &PinLayout,
+        min_pin_y: f32,
+        pin_stubs: Option<PinStubs>,
+        snarl_state: &mut SnarlState,
+        input: &Input,
+        input_positions: &mut HashMap<InPinId, PinResponse>,
+        output_positions: &mut HashMap<OutPinId, PinResponse>,
+    ) -> DrawPinsResponse
+    where
+        V: SnarlViewer<T>,
+    {
+        let inputs_first = pin_layout.input_side == side;
+
+        let mut drag_released = false;
+        let mut pin_hovered = None;
+        let mut final_rect = Rect::NOTHING;
+        let mut rect = column_rect;
+
+        for show_inputs in [inputs_first, !inputs_first] {
+            // Pins that are on this side by default are always shown,
+            // so the column is placed the same way when there are no pins moved here.
+            let moved = show_inputs != inputs_first;
+            if moved {
+                let sides = if show_inputs {
+                    &pin_layout.input_sides
+                } else {
+                    &pin_layout.output_sides
+                };
+                if !sides.contains(&side) {
+                    continue;
+                }
+            }
+
+            let r = if show_inputs {
+                self.draw_inputs(
+                    viewer,
+                    node,
+                    inputs,
+                    pin_size,
+                    style,
+                    ui,
+                    rect,
+                    clip_rect,
+                    viewport,
+                    Some(side),
+                    pin_layout,
+                    min_pin_y,
+                    pin_stubs,
+                    snarl_state,
+                    input,
+                    input_positions,
+                )
+            } else {
+                self.draw_outputs(
+                    viewer,
+                    node,
+                    outputs,
+                    pin_size,
+                    style,
+                    ui,
+                    rect,
+                    clip_rect,
+                    viewport,
+                    Some(side),
+                    pin_layout,
+                    min_pin_y,
+                    pin_stubs,
+                    snarl_state,
+                    input,
+                    output_positions,
+                )
+            };
+
+            drag_released |= r.drag_released;
+
+            if r.pin_hovered.is_some() {
+                pin_hovered = r.pin_hovered;
+            }
+
+            final_rect = final_rect.union(r.final_rect);
+
+            if !self.nodes.contains(node.0) {
+                // If removed
+                break;
+            }
+
+            if r.final_rect.height() > 0.0 {
+                rect = rect.intersect(Rect::everything_below(
+                    r.final_rect.bottom() + ui.spacing().item_spacing.y,
+                ));
+            }
+        }
+
+        DrawPinsResponse {
+            drag_released,
+            pin_hovered,
+            final_rect,
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn draw_body<V>(
         &mut self,
@@ -1411,6 +2955,7 @@ impl<T> Snarl<T> {
         body_rect: Rect,
         clip_rect: Rect,
         viewport: Rect,
+        mirrored: bool,
         snarl_state: &SnarlState,
     ) -> DrawBodyResponse
     where
@@ -1419,7 +2964,7 @@ impl<T> Snarl<T> {
         let mut body_ui = ui.new_child(
             UiBuilder::new()
                 .max_rect(body_rect)
-                .layout(Layout::left_to_right(Align::Min))
+                .layout(horizontal_layout(mirrored))
                 .id_salt("body"),
         );
         body_ui.set_clip_rect(clip_rect.intersect(viewport));
@@ -1441,6 +2986,128 @@ impl<T> Snarl<T> {
     }
 
     //First step for split big function to parts
+    /// Moves node dragged by its frame with primary button.
+    /// All selected nodes are moved if dragged node is selected.
+    fn drag_nodes<V>(
+        &mut self,
+        viewer: &mut V,
+        ui: &Ui,
+        snarl_id: Id,
+        snarl_state: &SnarlState,
+        style: &SnarlStyle,
+        input: &Input,
+    ) where
+        V: SnarlViewer<T>,
+    {
+        if ui.input(|i| i.pointer.primary_pressed()) {
+            snarl_state.reset_drag_threshold(ui.ctx());
+        }
+
+        let Some(dragged_id) = ui
+            .ctx()
+            .dragged_id()
+            .filter(|_| ui.input(|i| i.pointer.button_down(PointerButton::Primary)))
+        else {
+            let mut moved = snarl_state.take_moved_nodes(ui.ctx());
+            moved.retain(|node| self.nodes.contains(node.0));
+
+            // Node dropped onto a wire is inserted before the drag is reported,
+            // so the viewer sees the final graph.
+            if let Some(insertion) = snarl_state.take_wire_insertion(ui.ctx()) {
+                self.insert_on_wire(viewer, insertion);
+            }
+
+            if !moved.is_empty() {
+                viewer.node_drag_finished(&moved, self);
+            }
+            return;
+        };
+
+        if input.modifiers.shift || input.modifiers.command {
+            return;
+        }
+
+        let Some(node) = self
+            .nodes
+            .iter()
+            .map(|(idx, _)| NodeId(idx))
+            .find(|node| NodeState::node_id(snarl_id, *node).with("frame") == dragged_id)
+        else {
+            return;
+        };
+
+        let threshold = style.get_drag_threshold();
+        let mut delta = ui.input(|i| i.pointer.delta());
+        if threshold > 0.0 && !snarl_state.is_drag_threshold_crossed(ui.ctx()) {
+            // Once crossed, node follows the pointer even if it returns close to where it was pressed.
+            let Some(moved) = drag_motion_past_threshold(ui, threshold) else {
+                return;
+            };
+            snarl_state.cross_drag_threshold(ui.ctx());
+            delta = moved;
+        }
+
+        let delta = snarl_state.screen_vec_to_graph(delta);
+
+        #[allow(clippy::float_cmp)]
+        if delta == Vec2::ZERO {
+            return;
+        }
+
+        ui.ctx().request_repaint();
+
+        let nodes = if snarl_state.selected_nodes().contains(&node) {
+            snarl_state.selected_nodes()
+        } else {
+            std::slice::from_ref(&node)
+        };
+
+        for &node in nodes {
+            let Some(info) = self.nodes.get_mut(node.0) else {
+                continue;
+            };
+
+            let old_pos = info.pos;
+            info.pos += delta;
+            let new_pos = info.pos;
+
+            snarl_state.add_moved_node(node, ui.ctx());
+            viewer.node_moved(node, old_pos, new_pos, self);
+        }
+    }
+
+    /// Replaces the wire with two wires through the node.
+    fn insert_on_wire<V>(&mut self, viewer: &mut V, insertion: WireInsertion)
+    where
+        V: SnarlViewer<T>,
+    {
+        let WireInsertion {
+            node,
+            from,
+            to,
+            input,
+            output,
+        } = insertion;
+
+        let Some(info) = self.nodes.get(node.0) else {
+            return;
+        };
+
+        if !self.connected(from, to)
+            || input >= viewer.inputs(&info.value)
+            || output >= viewer.outputs(&info.value)
+        {
+            return;
+        }
+
+        let node_in = InPinId { node, input };
+        let node_out = OutPinId { node, output };
+
+        viewer.disconnect(&OutPin::new(self, from), &InPin::new(self, to), self);
+        viewer.connect(&OutPin::new(self, from), &InPin::new(self, node_in), self);
+        viewer.connect(&OutPin::new(self, node_out), &InPin::new(self, to), self);
+    }
+
     /// Draw one node. Return Pins info
     #[inline]
     #[allow(clippy::too_many_lines)]
@@ -1456,6 +3123,8 @@ impl<T> Snarl<T> {
         input_positions: &mut HashMap<InPinId, PinResponse>,
         input: &Input,
         output_positions: &mut HashMap<OutPinId, PinResponse>,
+        mirrored: bool,
+        interactive: bool,
     ) -> Option<DrawNodeResponse>
     where
         V: SnarlViewer<T>,
@@ -1472,18 +3141,38 @@ impl<T> Snarl<T> {
         let inputs_count = viewer.inputs(value);
         let outputs_count = viewer.outputs(value);
 
-        let inputs = (0..inputs_count)
-            .map(|idx| InPin::new(self, InPinId { node, input: idx }))
-            .collect::<Vec<_>>();
+        // Generate persistent id for the node.
+        let node_id = NodeState::node_id(snarl_id, node);
 
-        let outputs = (0..outputs_count)
-            .map(|idx| OutPin::new(self, OutPinId { node, output: idx }))
-            .collect::<Vec<_>>();
+        let input_keys = (0..inputs_count)
+            .map(|idx| viewer.input_key(value, idx))
+            .collect::<Option<Vec<_>>>();
 
-        let node_pos = snarl_state.graph_pos_to_screen(pos, viewport);
+        if let Some(input_keys) = input_keys {
+            if let Some(old_keys) =
+                NodeState::update_input_keys(ui.ctx(), node_id, input_keys.clone())
+            {
+                self.remap_inputs_by_key(node, &old_keys, &input_keys);
+            }
+        }
 
-        // Generate persistent id for the node.
-        let node_id = snarl_id.with(("snarl-node", node));
+        let hovered_pin = snarl_state.hovered_pin();
+
+        let (mut inputs, mut outputs) = self.node_pins(node, inputs_count, outputs_count);
+
+        for pin in &mut inputs {
+            pin.hovered = hovered_pin == Some(AnyPin::In(pin.id));
+            pin.wire_target =
+                pin.hovered && matches!(snarl_state.new_wires(), Some(NewWires::Out(_)));
+        }
+
+        for pin in &mut outputs {
+            pin.hovered = hovered_pin == Some(AnyPin::Out(pin.id));
+            pin.wire_target =
+                pin.hovered && matches!(snarl_state.new_wires(), Some(NewWires::In(_)));
+        }
+
+        let node_pos = snarl_state.graph_pos_to_screen(pos, viewport);
 
         let openness = ui.ctx().animate_bool(node_id, open);
 
@@ -1492,7 +3181,6 @@ impl<T> Snarl<T> {
         let node_rect = node_state.node_rect(node_pos, openness);
 
         let mut node_to_top = None;
-        let mut node_moved = None;
         let mut drag_released = false;
         let mut pin_hovered = None;
 
@@ -1538,19 +3226,17 @@ impl<T> Snarl<T> {
             .max(Vec2::ZERO);
 
         // Interact with node frame.
+        // Non-interactive node only senses hover, so it can't be selected, dragged or raised.
         let r = ui.interact(
             node_frame_rect,
             node_id.with("frame"),
-            Sense::click_and_drag(),
+            if interactive {
+                Sense::click_and_drag()
+            } else {
+                Sense::hover()
+            },
         );
 
-        if !input.modifiers.shift
-            && !input.modifiers.command
-            && r.dragged_by(PointerButton::Primary)
-        {
-            node_moved = Some((node, snarl_state.screen_vec_to_graph(r.drag_delta())));
-        }
-
         if r.clicked_by(PointerButton::Primary) || r.dragged_by(PointerButton::Primary) {
             if input.modifiers.shift {
                 snarl_state.select_one_node(input.modifiers.command, node);
@@ -1559,13 +3245,38 @@ impl<T> Snarl<T> {
             }
         }
 
-        if r.clicked() || r.dragged() {
+        if (r.clicked() || r.dragged())
+            && style.get_raise_on_interact() == RaisePolicy::Always
+            && viewer.allow_raise(&self.nodes[node.0].value)
+        {
             node_to_top = Some(node);
         }
 
         if viewer.has_node_menu(&self.nodes[node.0].value) {
+            // Remember pin under the pointer when menu is opened,
+            // since pointer moves away from it to the menu.
+            let menu_pin_id = snarl_id.with("node-menu-pin");
+            if r.secondary_clicked() || r.long_touched() {
+                let clicked_pin = hovered_pin.filter(|pin| pin.node() == node);
+                ui.ctx()
+                    .data_mut(|d| d.insert_temp::<Option<AnyPin>>(menu_pin_id, clicked_pin));
+            }
+
             r.context_menu(|ui| {
-                viewer.show_node_menu(node, &inputs, &outputs, ui, snarl_state.scale(), self);
+                snarl_state.set_menu_open();
+                let clicked_pin = ui
+                    .ctx()
+                    .data(|d| d.get_temp::<Option<AnyPin>>(menu_pin_id))
+                    .flatten();
+                viewer.show_node_menu_for_pin(
+                    node,
+                    clicked_pin,
+                    &inputs,
+                    &outputs,
+                    ui,
+                    snarl_state.scale(),
+                    self,
+                );
             });
         }
 
@@ -1594,13 +3305,17 @@ impl<T> Snarl<T> {
                 .id_salt(node_id),
         );
 
+        if !interactive {
+            node_ui.disable();
+        }
+
         let mut new_pins_size = Vec2::ZERO;
 
         let r = node_frame.show(node_ui, |ui| {
             let min_pin_y = node_state.header_height().mul_add(0.5, node_rect.min.y);
 
-            // Input pins' center side by X axis.
-            let input_x = match pin_placement {
+            // Left pins' center side by X axis.
+            let left_x = match pin_placement {
                 PinPlacement::Inside => {
                     pin_size.mul_add(0.5, node_frame_rect.left() + node_frame.inner_margin.left)
                 }
@@ -1611,8 +3326,8 @@ impl<T> Snarl<T> {
                 ),
             };
 
-            // Input pins' spacing required.
-            let input_spacing = match pin_placement {
+            // Left pins' spacing required.
+            let left_spacing = match pin_placement {
                 PinPlacement::Inside => Some(pin_size),
                 PinPlacement::Edge => Some(
                     pin_size
@@ -1622,8 +3337,8 @@ impl<T> Snarl<T> {
                 PinPlacement::Outside { .. } => None,
             };
 
-            // Output pins' center side by X axis.
-            let output_x = match pin_placement {
+            // Right pins' center side by X axis.
+            let right_x = match pin_placement {
                 PinPlacement::Inside => pin_size.mul_add(
                     -0.5,
                     node_frame_rect.right() - node_frame.inner_margin.right,
@@ -1635,8 +3350,8 @@ impl<T> Snarl<T> {
                 ),
             };
 
-            // Output pins' spacing required.
-            let output_spacing = match pin_placement {
+            // Right pins' spacing required.
+            let right_spacing = match pin_placement {
                 PinPlacement::Inside => Some(pin_size),
                 PinPlacement::Edge => Some(
                     pin_size
@@ -1646,12 +3361,49 @@ impl<T> Snarl<T> {
                 PinPlacement::Outside { .. } => None,
             };
 
+            // Inputs are on the left and outputs are on the right, unless mirrored.
+            // Viewer may move each pin to the other side.
+            let input_side = if mirrored {
+                PinSide::Right
+            } else {
+                PinSide::Left
+            };
+
+            let pin_layout = PinLayout {
+                input_sides: inputs
+                    .iter()
+                    .map(|pin| viewer.pin_side(input_side, node, AnyPin::In(pin.id), self))
+                    .collect(),
+                output_sides: outputs
+                    .iter()
+                    .map(|pin| {
+                        viewer.pin_side(input_side.opposite(), node, AnyPin::Out(pin.id), self)
+                    })
+                    .collect(),
+                input_side,
+                left_x,
+                right_x,
+                left_spacing,
+                right_spacing,
+            };
+
             // Input/output pin block
 
             if (openness < 1.0 && open) || (openness > 0.0 && !open) {
                 ui.ctx().request_repaint();
             }
 
+            // Pins of collapsing node are moved to stubs on the header's edges.
+            let pin_stubs = if openness < 1.0 && style.get_collapsed_pin_stubs() {
+                Some(PinStubs {
+                    header_top: node_rect.min.y,
+                    header_height: node_state.header_height(),
+                    openness,
+                })
+            } else {
+                None
+            };
+
             // Pins are placed under the header and must not go outside of the header frame.
             let payload_rect = Rect::from_min_max(
                 pos2(
@@ -1673,23 +3425,26 @@ impl<T> Snarl<T> {
 
             let pins_rect = match node_layout {
                 NodeLayout::Basic => {
-                    // Show input pins.
-                    let r = self.draw_inputs(
+                    // Show pins on the left side.
+                    let r = self.draw_pins_column(
                         viewer,
                         node,
                         &inputs,
+                        &outputs,
                         pin_size,
                         style,
                         ui,
                         payload_rect,
                         payload_clip_rect,
                         viewport,
-                        input_x,
+                        PinSide::Left,
+                        &pin_layout,
                         min_pin_y,
-                        input_spacing,
+                        pin_stubs,
                         snarl_state,
                         input,
                         input_positions,
+                        output_positions,
                     );
 
                     drag_released |= r.drag_released;
@@ -1698,19 +3453,19 @@ impl<T> Snarl<T> {
                         pin_hovered = r.pin_hovered;
                     }
 
-                    let inputs_rect = r.final_rect;
-                    let inputs_size = inputs_rect.size();
+                    let left_rect = r.final_rect;
+                    let left_size = left_rect.size();
 
                     if !self.nodes.contains(node.0) {
                         // If removed
                         return;
                     }
 
-                    // Show output pins.
-
-                    let r = self.draw_outputs(
+                    // Show pins on the right side.
+                    let r = self.draw_pins_column(
                         viewer,
                         node,
+                        &inputs,
                         &outputs,
                         pin_size,
                         style,
@@ -1718,11 +3473,13 @@ impl<T> Snarl<T> {
                         payload_rect,
                         payload_clip_rect,
                         viewport,
-                        output_x,
+                        PinSide::Right,
+                        &pin_layout,
                         min_pin_y,
-                        output_spacing,
+                        pin_stubs,
                         snarl_state,
                         input,
+                        input_positions,
                         output_positions,
                     );
 
@@ -1732,8 +3489,8 @@ impl<T> Snarl<T> {
                         pin_hovered = r.pin_hovered;
                     }
 
-                    let outputs_rect = r.final_rect;
-                    let outputs_size = outputs_rect.size();
+                    let right_rect = r.final_rect;
+                    let right_size = right_rect.size();
 
                     if !self.nodes.contains(node.0) {
                         // If removed
@@ -1741,16 +3498,16 @@ impl<T> Snarl<T> {
                     }
 
                     new_pins_size = vec2(
-                        inputs_size.x + outputs_size.x + ui.spacing().item_spacing.x,
-                        f32::max(inputs_size.y, outputs_size.y),
+                        left_size.x + right_size.x + ui.spacing().item_spacing.x,
+                        f32::max(left_size.y, right_size.y),
                     );
 
-                    let mut pins_rect = inputs_rect.union(outputs_rect);
+                    let mut pins_rect = left_rect.union(right_rect);
 
                     // Show body if there's one.
                     if viewer.has_body(&self.nodes.get(node.0).unwrap().value) {
-                        let body_left = inputs_rect.right() + ui.spacing().item_spacing.x;
-                        let body_right = outputs_rect.left() - ui.spacing().item_spacing.x;
+                        let body_left = left_rect.right() + ui.spacing().item_spacing.x;
+                        let body_right = right_rect.left() - ui.spacing().item_spacing.x;
                         let body_top = payload_rect.top();
                         let body_bottom = payload_rect.bottom();
 
@@ -1768,6 +3525,7 @@ impl<T> Snarl<T> {
                             body_rect,
                             payload_clip_rect,
                             viewport,
+                            mirrored,
                             snarl_state,
                         );
 
@@ -1776,6 +3534,48 @@ impl<T> Snarl<T> {
 
                         pins_rect = pins_rect.union(body_rect);
 
+                        if !self.nodes.contains(node.0) {
+                            // If removed
+                            return;
+                        }
+                    } else {
+                        // Center content is placed in the gap between columns.
+                        // It widens the node, but never makes it taller than pin columns.
+                        let center_left = left_rect.right() + ui.spacing().item_spacing.x;
+                        let center_right = right_rect.left() - ui.spacing().item_spacing.x;
+                        let center_rect = Rect::from_min_max(
+                            pos2(center_left, pins_rect.top()),
+                            pos2(center_right.max(center_left), pins_rect.bottom()),
+                        );
+
+                        let mut center_ui = ui.new_child(
+                            UiBuilder::new()
+                                .max_rect(center_rect)
+                                .layout(
+                                    horizontal_layout(mirrored)
+                                        .with_main_align(Align::Center)
+                                        .with_cross_align(Align::Center),
+                                )
+                                .id_salt("center"),
+                        );
+                        center_ui.set_clip_rect(
+                            center_rect.intersect(payload_clip_rect).intersect(viewport),
+                        );
+
+                        viewer.show_center(
+                            node,
+                            &inputs,
+                            &outputs,
+                            &mut center_ui,
+                            snarl_state.scale(),
+                            self,
+                        );
+
+                        let center_width = center_ui.min_rect().width();
+                        if center_width > 0.0 {
+                            new_pins_size.x += center_width + ui.spacing().item_spacing.x;
+                        }
+
                         if !self.nodes.contains(node.0) {
                             // If removed
                             return;
@@ -1798,9 +3598,10 @@ impl<T> Snarl<T> {
                         inputs_rect,
                         payload_clip_rect,
                         viewport,
-                        input_x,
+                        None,
+                        &pin_layout,
                         min_pin_y,
-                        input_spacing,
+                        pin_stubs,
                         snarl_state,
                         input,
                         input_positions,
@@ -1838,6 +3639,7 @@ impl<T> Snarl<T> {
                             body_rect,
                             payload_clip_rect,
                             viewport,
+                            mirrored,
                             snarl_state,
                         );
 
@@ -1869,9 +3671,10 @@ impl<T> Snarl<T> {
                         outputs_rect,
                         payload_clip_rect,
                         viewport,
-                        output_x,
+                        None,
+                        &pin_layout,
                         min_pin_y,
-                        output_spacing,
+                        pin_stubs,
                         snarl_state,
                         input,
                         output_positions,
@@ -1911,9 +3714,10 @@ impl<T> Snarl<T> {
                         outputs_rect,
                         payload_clip_rect,
                         viewport,
-                        output_x,
+                        None,
+                        &pin_layout,
                         min_pin_y,
-                        output_spacing,
+                        pin_stubs,
                         snarl_state,
                         input,
                         output_positions,
@@ -1951,6 +3755,7 @@ impl<T> Snarl<T> {
                             body_rect,
                             payload_clip_rect,
                             viewport,
+                            mirrored,
                             snarl_state,
                         );
 
@@ -1982,9 +3787,10 @@ impl<T> Snarl<T> {
                         inputs_rect,
                         payload_clip_rect,
                         viewport,
-                        input_x,
+                        None,
+                        &pin_layout,
                         min_pin_y,
-                        input_spacing,
+                        pin_stubs,
                         snarl_state,
                         input,
                         input_positions,
@@ -2026,7 +3832,7 @@ impl<T> Snarl<T> {
                 let mut footer_ui = ui.new_child(
                     UiBuilder::new()
                         .max_rect(footer_rect)
-                        .layout(Layout::left_to_right(Align::Min))
+                        .layout(horizontal_layout(mirrored))
                         .id_salt("footer"),
                 );
                 footer_ui.set_clip_rect(payload_clip_rect.intersect(viewport));
@@ -2066,18 +3872,61 @@ impl<T> Snarl<T> {
                     .id_salt("header"),
             );
 
+            if !interactive {
+                header_ui.disable();
+            }
+
+            let breakpoint = viewer.breakpoint_state(&self.nodes[node.0].value);
+
             header_frame.show(header_ui, |ui: &mut Ui| {
-                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                ui.with_layout(horizontal_layout(mirrored), |ui| {
+                    if let Some(active) = breakpoint {
+                        // Gutter senses only clicks, so node is not selected, raised or dragged.
+                        let (_, r) = ui.allocate_exact_size(
+                            vec2(ui.spacing().icon_width, ui.spacing().icon_width),
+                            Sense::click(),
+                        );
+                        paint_breakpoint(
+                            ui,
+                            active,
+                            &r,
+                            &style.get_breakpoint_style(snarl_state.scale(), ui.style()),
+                        );
+
+                        if r.clicked_by(PointerButton::Primary) {
+                            viewer.toggle_breakpoint(node, self);
+                        }
+                    }
+
                     if style.get_collapsible() {
                         let (_, r) = ui.allocate_exact_size(
                             vec2(ui.spacing().icon_width, ui.spacing().icon_width),
                             Sense::click(),
                         );
-                        paint_default_icon(ui, openness, &r);
+                        paint_collapse_icon(ui, openness, mirrored, &r);
 
                         if r.clicked_by(PointerButton::Primary) {
                             // Toggle node's openness.
                             self.open_node(node, !open);
+
+                            if input
+                                .modifiers
+                                .matches_logically(style.get_collapse_all_modifiers())
+                            {
+                                // Selected nodes or all nodes follow clicked one.
+                                let selected = snarl_state
+                                    .selected_nodes()
+                                    .iter()
+                                    .copied()
+                                    .filter(|node| self.nodes.contains(node.0))
+                                    .collect::<Vec<_>>();
+
+                                if selected.is_empty() {
+                                    self.open_all(!open);
+                                } else {
+                                    self.open_nodes(&selected, !open);
+                                }
+                            }
                         }
                     }
 
@@ -2099,6 +3948,24 @@ impl<T> Snarl<T> {
                 ));
             });
 
+            if let Some(activity) = viewer.node_progress(&self.nodes[node.0].value) {
+                // Strip spans the header frame's width right under it,
+                // it is drawn over node's content and doesn't affect layout.
+                let header_max_rect = node_rect + header_frame.total_margin();
+                let strip_rect = Rect::from_min_max(
+                    pos2(header_max_rect.left(), header_frame_rect.bottom()),
+                    pos2(header_max_rect.right(), header_frame_rect.bottom()),
+                );
+
+                draw_activity_strip(
+                    ui,
+                    activity,
+                    strip_rect,
+                    header_frame.rounding,
+                    &style.get_progress_style(snarl_state.scale(), ui.style()),
+                );
+            }
+
             ui.expand_to_include_rect(header_rect);
             let header_size = header_rect.size();
             node_state.set_header_height(header_size.y);
@@ -2131,7 +3998,6 @@ impl<T> Snarl<T> {
 
         node_state.store(ui.ctx());
         Some(DrawNodeResponse {
-            node_moved,
             node_to_top,
             drag_released,
             pin_hovered,
@@ -2140,6 +4006,127 @@ impl<T> Snarl<T> {
     }
 }
 
+/// Draws node activity strip with top edge at `rect`'s top.
+///
+/// Strip is inset from sides by header's bottom corner rounding,
+/// so it never sticks out of rounded frames.
+fn draw_activity_strip(
+    ui: &Ui,
+    activity: NodeActivity,
+    rect: Rect,
+    rounding: Rounding,
+    style: &ProgressStyle,
+) {
+    let inset = rounding.sw.max(rounding.se);
+    let strip = Rect::from_min_max(
+        pos2(rect.left() + inset, rect.top()),
+        pos2(rect.right() - inset, rect.top() + style.height.max(0.0)),
+    );
+
+    if !strip.is_positive() {
+        return;
+    }
+
+    let strip_rounding = Rounding::same(strip.height() * 0.5);
+    ui.painter()
+        .rect_filled(strip, strip_rounding, style.background);
+
+    let (from, to) = match activity {
+        NodeActivity::Progress(progress) => (0.0, progress.clamp(0.0, 1.0)),
+        NodeActivity::Spinner => {
+            // Segment of third of the strip runs from left to right once a second.
+            #[allow(clippy::cast_possible_truncation)]
+            let phase = ui.input(|i| i.time).fract() as f32;
+            ui.ctx().request_repaint();
+
+            let head = phase * 4.0 / 3.0;
+            ((head - 1.0 / 3.0).max(0.0), head.min(1.0))
+        }
+    };
+
+    if to > from {
+        let done = Rect::from_min_max(
+            pos2(strip.left() + strip.width() * from, strip.top()),
+            pos2(strip.left() + strip.width() * to, strip.bottom()),
+        );
+        ui.painter().rect_filled(done, strip_rounding, style.fill);
+    }
+}
+
+/// Paints breakpoint circle in the center of the gutter.
+/// Inactive breakpoint is only outlined, with faint fill when hovered.
+fn paint_breakpoint(ui: &Ui, active: bool, response: &Response, style: &BreakpointStyle) {
+    let fill = if active {
+        style.fill
+    } else if response.hovered() {
+        style.fill.gamma_multiply(0.3)
+    } else {
+        Color32::TRANSPARENT
+    };
+
+    ui.painter()
+        .circle(response.rect.center(), style.radius, fill, style.stroke);
+}
+
+/// Layout for horizontally placed content.
+fn horizontal_layout(mirrored: bool) -> Layout {
+    if mirrored {
+        Layout::right_to_left(Align::Min)
+    } else {
+        Layout::left_to_right(Align::Min)
+    }
+}
+
+/// Paints collapsing triangle that points away from header content when node is collapsed.
+///
+/// Same as [`paint_default_icon`] but mirrored if needed.
+fn paint_collapse_icon(ui: &mut Ui, openness: f32, mirrored: bool, response: &Response) {
+    if !mirrored {
+        paint_default_icon(ui, openness, response);
+        return;
+    }
+
+    let visuals = ui.style().interact(response);
+
+    let rect = response.rect;
+    let rect = Rect::from_center_size(rect.center(), rect.size() * 0.75);
+    let rect = rect.expand(visuals.expansion);
+    let mut points = vec![rect.left_top(), rect.right_top(), rect.center_bottom()];
+    let rotation = egui::emath::Rot2::from_angle(egui::remap(
+        openness,
+        0.0..=1.0,
+        std::f32::consts::FRAC_PI_2..=0.0,
+    ));
+    for p in &mut points {
+        *p = rect.center() + rotation * (*p - rect.center());
+    }
+
+    ui.painter().add(Shape::convex_polygon(
+        points,
+        visuals.fg_stroke.color,
+        Stroke::NONE,
+    ));
+}
+
+/// Returns whole motion since press once pointer is farther than `threshold` from where it was pressed,
+/// so node catches up with the pointer when threshold is crossed.
+///
+/// Returns `None` while pointer is within `threshold`.
+fn drag_motion_past_threshold(ui: &Ui, threshold: f32) -> Option<Vec2> {
+    let (Some(origin), Some(pos)) =
+        ui.input(|i| (i.pointer.press_origin(), i.pointer.interact_pos()))
+    else {
+        return Some(ui.input(|i| i.pointer.delta()));
+    };
+
+    let moved = pos - origin;
+    if moved.length() <= threshold {
+        return None;
+    }
+
+    Some(moved)
+}
+
 const fn mix_colors(a: Color32, b: Color32) -> Color32 {
     Color32::from_rgba_premultiplied(
         ((a.r() as u32 + b.r() as u32) / 2) as u8,
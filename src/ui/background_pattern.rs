@@ -3,6 +3,7 @@ use egui::{emath::Rot2, vec2, Painter, Pos2, Rect, Style, Vec2};
 use super::SnarlStyle;
 
 /// Viewport is a rectangle in graph space that is visible on screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Viewport {
     /// Screen-space rectangle.
     pub rect: Rect,
@@ -58,6 +59,31 @@ impl Viewport {
     }
 }
 
+/// Reason why the viewport was changed.
+///
+/// Reported to [`SnarlViewer::viewport_changed`](super::SnarlViewer::viewport_changed).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ViewportChangeReason {
+    /// Viewport was panned by dragging the background.
+    UserPan,
+
+    /// Viewport was zoomed by user input.
+    UserZoom,
+
+    /// Viewport was centered on nodes by double click on background.
+    DoubleClickCenter,
+
+    /// Viewport was changed programmatically.
+    Programmatic,
+
+    /// Viewport was moved to keep followed node centered.
+    /// See [`Snarl::follow_node`](crate::Snarl::follow_node).
+    FollowNode,
+
+    /// Viewport scale was clamped to fit into [`SnarlStyle::min_scale`] and [`SnarlStyle::max_scale`].
+    Clamped,
+}
+
 ///Grid background pattern.
 ///Use `SnarlStyle::background_pattern_stroke` for change stroke options
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -1,37 +1,74 @@
-use std::cell::RefCell;
-
 use egui::Pos2;
 
-use crate::{wire_pins, InPinId, Node, OutPinId, Snarl};
+use crate::{InPinId, NodeId, OutPinId, Snarl};
 
+/// Deferred change of the [`Snarl`].
+///
+/// Effects that refer to nodes that no longer exist are ignored when applied.
 pub enum Effect<T> {
     /// Adds a new node to the Snarl.
-    InsertNode { pos: Pos2, node: T },
+    InsertNode {
+        /// Position of the node in graph space.
+        pos: Pos2,
+
+        /// Node value.
+        node: T,
+    },
 
     /// Removes a node from snarl.
-    RemoveNode { node: NodeId },
+    RemoveNode {
+        /// Node to remove.
+        node: NodeId,
+    },
 
     /// Opens/closes a node.
-    OpenNode { node: NodeId, open: bool },
+    OpenNode {
+        /// Node to open or close.
+        node: NodeId,
+
+        /// Whether node is opened.
+        open: bool,
+    },
 
     /// Adds connection between two nodes.
-    Connect { from: OutPinId, to: InPinId },
+    Connect {
+        /// Output pin of the wire.
+        from: OutPinId,
+
+        /// Input pin of the wire.
+        to: InPinId,
+    },
 
     /// Removes connection between two nodes.
-    Disconnect { from: OutPinId, to: InPinId },
+    Disconnect {
+        /// Output pin of the wire.
+        from: OutPinId,
+
+        /// Input pin of the wire.
+        to: InPinId,
+    },
 
     /// Removes all connections from the output pin.
-    DropOutputs { pin: OutPinId },
+    DropOutputs {
+        /// Output pin to disconnect.
+        pin: OutPinId,
+    },
 
     /// Removes all connections to the input pin.
-    DropInputs { pin: InPinId },
+    DropInputs {
+        /// Input pin to disconnect.
+        pin: InPinId,
+    },
 
     /// Executes a closure with mutable reference to the Snarl.
+    #[allow(clippy::type_complexity)]
     Closure(Box<dyn FnOnce(&mut Snarl<T>)>),
 }
 
-/// Contained for deferred execution of effects.
-/// It is populated by [`SnarlViewer`] methods and then applied to the Snarl.
+/// Container for deferred execution of effects.
+/// It is populated by [`SnarlViewer`](super::SnarlViewer) methods and then applied to the Snarl
+/// with [`Snarl::apply_effects`] after the frame,
+/// when returned from [`SnarlViewer::take_effects`](super::SnarlViewer::take_effects).
 pub struct Effects<T> {
     effects: Vec<Effect<T>>,
 }
@@ -46,9 +83,9 @@ impl<T> Default for Effects<T> {
 }
 
 impl<T> Effects<T> {
+    /// Returns empty effects.
     #[inline(always)]
-    #[doc(hidden)]
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         Effects {
             effects: Vec::new(),
         }
@@ -91,13 +128,13 @@ impl<T> Effects<T> {
         self.effects.push(Effect::Disconnect { from, to });
     }
 
-    /// Removes all connections from the output pin.
+    /// Removes all connections to the input pin.
     #[inline(always)]
     pub fn drop_inputs(&mut self, pin: InPinId) {
         self.effects.push(Effect::DropInputs { pin });
     }
 
-    /// Removes all connections to the input pin.
+    /// Removes all connections from the output pin.
     #[inline(always)]
     pub fn drop_outputs(&mut self, pin: OutPinId) {
         self.effects.push(Effect::DropOutputs { pin });
@@ -105,54 +142,39 @@ impl<T> Effects<T> {
 }
 
 impl<T> Snarl<T> {
+    /// Applies all effects in order they were added.
     pub fn apply_effects(&mut self, effects: Effects<T>) {
-        if effects.effects.is_empty() {
-            return;
-        }
         for effect in effects.effects {
             self.apply_effect(effect);
         }
     }
 
+    /// Applies single effect.
+    /// Does nothing if the effect refers to a node that does not exist.
     pub fn apply_effect(&mut self, effect: Effect<T>) {
         match effect {
             Effect::InsertNode { node, pos } => {
-                let idx = self.nodes.insert(Node {
-                    value: RefCell::new(node),
-                    pos,
-                    open: true,
-                });
-                self.draw_order.push(idx);
+                self.insert_node(pos, node);
             }
             Effect::RemoveNode { node } => {
-                if self.nodes.contains(node) {
-                    self.remove_node(node);
-                }
+                let _ = self.try_remove_node(node);
             }
             Effect::OpenNode { node, open } => {
-                if self.nodes.contains(node) {
-                    self.nodes[node].open = open;
+                if let Some(node) = self.get_node_info_mut(node) {
+                    node.open = open;
                 }
             }
             Effect::Connect { from, to } => {
-                if self.nodes.contains(from.node) && self.nodes.contains(to.node) {
-                    self.wires.insert(wire_pins(from, to));
-                }
+                let _ = self.try_connect(from, to);
             }
             Effect::Disconnect { from, to } => {
-                if self.nodes.contains(from.node) && self.nodes.contains(to.node) {
-                    self.wires.remove(&wire_pins(from, to));
-                }
+                let _ = self.try_disconnect(from, to);
             }
             Effect::DropOutputs { pin } => {
-                if self.nodes.contains(pin.node) {
-                    self.wires.drop_outputs(pin);
-                }
+                let _ = self.try_drop_outputs(pin);
             }
             Effect::DropInputs { pin } => {
-                if self.nodes.contains(pin.node) {
-                    self.wires.drop_inputs(pin);
-                }
+                let _ = self.try_drop_inputs(pin);
             }
             Effect::Closure(f) => f(self),
         }
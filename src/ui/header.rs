@@ -0,0 +1,108 @@
+use egui::{
+    Align, Align2, Color32, FontId, ImageSource, Rect, Response, Sense, TextureId, Ui, Vec2,
+    WidgetText,
+};
+
+/// Icon shown before the title in the default node header.
+///
+/// See [`SnarlViewer::node_icon`](super::SnarlViewer::node_icon).
+#[derive(Clone, Debug)]
+pub enum NodeIcon {
+    /// Image loaded with egui's image loaders, e.g. from `egui_extras`.
+    /// Spinner is shown in its place while the image is loading.
+    Image(ImageSource<'static>),
+
+    /// Text, usually a single character or emoji.
+    Glyph {
+        /// Text of the icon.
+        text: String,
+
+        /// Font size at 100% zoom.
+        size: f32,
+    },
+
+    /// Region of already allocated texture.
+    Texture {
+        /// Texture to draw.
+        id: TextureId,
+
+        /// Region of the texture in normalized coordinates.
+        uv: Rect,
+    },
+}
+
+impl NodeIcon {
+    /// Creates icon from a character or emoji with specified font size.
+    pub fn glyph(text: impl Into<String>, size: f32) -> Self {
+        NodeIcon::Glyph {
+            text: text.into(),
+            size,
+        }
+    }
+
+    /// Creates icon showing the whole texture.
+    #[must_use]
+    pub fn texture(id: TextureId) -> Self {
+        NodeIcon::Texture {
+            id,
+            uv: Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+        }
+    }
+
+    /// Shows the icon in a square with side of `icon_width` from `ui`'s spacing.
+    /// Glyphs larger than the square widen it.
+    pub fn show(&self, ui: &mut Ui, scale: f32) -> Response {
+        let side = ui.spacing().icon_width;
+
+        match self {
+            NodeIcon::Image(source) => {
+                let (rect, r) = ui.allocate_exact_size(Vec2::splat(side), Sense::hover());
+                egui::Image::new(source.clone())
+                    .show_loading_spinner(true)
+                    .paint_at(ui, rect);
+                r
+            }
+            NodeIcon::Glyph { text, size } => {
+                let galley = ui.painter().layout_no_wrap(
+                    text.clone(),
+                    FontId::proportional(size * scale),
+                    ui.visuals().text_color(),
+                );
+                let (rect, r) =
+                    ui.allocate_exact_size(galley.size().max(Vec2::splat(side)), Sense::hover());
+                let pos = Align2::CENTER_CENTER
+                    .align_size_within_rect(galley.size(), rect)
+                    .min;
+                ui.painter().galley(pos, galley, Color32::PLACEHOLDER);
+                r
+            }
+            NodeIcon::Texture { id, uv } => {
+                let (rect, r) = ui.allocate_exact_size(Vec2::splat(side), Sense::hover());
+                ui.painter().image(*id, rect, *uv, Color32::WHITE);
+                r
+            }
+        }
+    }
+}
+
+/// Shows content of the default node header: optional icon followed by the title.
+///
+/// Elements are vertically centered and follow direction of `ui`'s layout.
+/// Custom [`SnarlViewer::show_header`](super::SnarlViewer::show_header)
+/// implementations may use it to keep the default look and add own widgets after it.
+///
+/// Returns response of the title.
+pub fn show_node_header(
+    ui: &mut Ui,
+    icon: Option<&NodeIcon>,
+    title: impl Into<WidgetText>,
+    scale: f32,
+) -> Response {
+    ui.with_layout(ui.layout().with_cross_align(Align::Center), |ui| {
+        if let Some(icon) = icon {
+            icon.show(ui, scale);
+        }
+        ui.label(title)
+    })
+    .inner
+}
@@ -1,18 +1,36 @@
 use egui::{epaint::PathShape, vec2, Color32, Painter, Pos2, Shape, Stroke, Style, Vec2};
 
-use crate::{InPinId, OutPinId};
+use crate::{InPinId, NodeId, OutPinId};
 
-use super::{zoom::Zoom, SnarlStyle, WireStyle};
+use super::{zoom::Zoom, SnarlStyle, WireEndMarker, WireStyle};
 
+/// Identifier of either input or output pin.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum AnyPin {
+    /// Output pin.
     Out(OutPinId),
+
+    /// Input pin.
     In(InPinId),
 }
 
+impl AnyPin {
+    /// Returns node the pin belongs to.
+    #[must_use]
+    pub const fn node(&self) -> NodeId {
+        match self {
+            AnyPin::Out(pin) => pin.node,
+            AnyPin::In(pin) => pin.node,
+        }
+    }
+}
+
 /// In the current context, these are the I/O pins of the 'source' node that the newly
 /// created node's I/O pins will connect to.
-#[derive(Debug)]
+///
+/// When several wires are dragged at once, e.g. all wires of a pin picked with command modifier,
+/// this contains all their source pins.
+#[derive(Clone, Copy, Debug)]
 pub enum AnyPins<'a> {
     /// Output pins.
     Out(&'a [OutPinId]),
@@ -20,6 +38,36 @@ pub enum AnyPins<'a> {
     In(&'a [InPinId]),
 }
 
+impl<'a> AnyPins<'a> {
+    /// Returns number of pins.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        match self {
+            AnyPins::Out(pins) => pins.len(),
+            AnyPins::In(pins) => pins.len(),
+        }
+    }
+
+    /// Returns true if there are no pins.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over pins.
+    pub fn iter(&self) -> impl Iterator<Item = AnyPin> + 'a {
+        let (outs, ins): (&[OutPinId], &[InPinId]) = match *self {
+            AnyPins::Out(pins) => (pins, &[]),
+            AnyPins::In(pins) => (&[], pins),
+        };
+
+        outs.iter()
+            .copied()
+            .map(AnyPin::Out)
+            .chain(ins.iter().copied().map(AnyPin::In))
+    }
+}
+
 /// Shape of a pin.
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -59,6 +107,31 @@ pub struct PinInfo {
 
     /// Style of the wire connected to the pin.
     pub wire_style: Option<WireStyle>,
+
+    /// Size of the frame that controls curvature of wires connected to the pin.
+    /// Overrides [`SnarlStyle::wire_frame_size`] for these wires.
+    ///
+    /// If both ends of a wire have the override, the larger one is used.
+    pub wire_frame_size: Option<f32>,
+
+    /// Marker drawn at the input end of wires connected to the pin.
+    /// Overrides [`SnarlStyle::wire_end_marker`] for these wires.
+    ///
+    /// If both ends of a wire have the override, the input pin's one is used.
+    pub wire_end_marker: Option<WireEndMarker>,
+
+    /// Speed of dashes flowing along wires connected to the pin.
+    /// Overrides [`SnarlStyle::wire_flow`] for these wires, zero speed stops the flow.
+    /// See [`SnarlStyle::wire_flow`] for how it applies to dashed and dotted wires.
+    ///
+    /// If both ends of a wire have the override, the input pin's one is used.
+    pub wire_flow: Option<f32>,
+
+    /// Hides the pin.
+    ///
+    /// Row content is still shown, but no pin is drawn and the row can't start or receive wires.
+    /// Wires connected to invisible pin are not drawn.
+    pub invisible: bool,
 }
 
 impl PinInfo {
@@ -97,6 +170,27 @@ impl PinInfo {
         self
     }
 
+    /// Sets the size of the frame that controls curvature of wires connected to the pin.
+    #[must_use]
+    pub const fn with_wire_frame_size(mut self, wire_frame_size: f32) -> Self {
+        self.wire_frame_size = Some(wire_frame_size);
+        self
+    }
+
+    /// Sets the marker drawn at the input end of wires connected to the pin.
+    #[must_use]
+    pub const fn with_wire_end_marker(mut self, wire_end_marker: WireEndMarker) -> Self {
+        self.wire_end_marker = Some(wire_end_marker);
+        self
+    }
+
+    /// Sets the speed of dashes flowing along wires connected to the pin.
+    #[must_use]
+    pub const fn with_wire_flow(mut self, wire_flow: f32) -> Self {
+        self.wire_flow = Some(wire_flow);
+        self
+    }
+
     /// Creates a circle pin.
     #[must_use]
     pub fn circle() -> Self {
@@ -133,6 +227,17 @@ impl PinInfo {
         }
     }
 
+    /// Creates an invisible pin.
+    ///
+    /// Use it for informational rows that must keep pin indices aligned with the model.
+    #[must_use]
+    pub fn invisible() -> Self {
+        PinInfo {
+            invisible: true,
+            ..Default::default()
+        }
+    }
+
     /// Returns the shape of the pin.
     #[must_use]
     pub fn get_shape(&self, snarl_style: &SnarlStyle) -> PinShape {
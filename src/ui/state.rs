@@ -1,10 +1,42 @@
-use std::hash::Hash;
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+};
 
 use egui::{ahash::HashSet, style::Spacing, Context, Id, Pos2, Rect, Ui, Vec2};
 
 use crate::{InPinId, NodeId, OutPinId, Snarl};
 
-use super::SnarlStyle;
+use super::{pin::AnyPin, SnarlStyle, Viewport, ViewportChangeReason, ZoomAnchor};
+
+/// Duration of the wire pulse in seconds.
+const WIRE_PULSE_DURATION: f64 = 0.5;
+
+/// Margin around nodes fitted into the viewport, in graph space.
+const FIT_MARGIN: f32 = 50.0;
+
+/// Returns viewport offset after changing scale from `scale` to `new_scale`,
+/// so that the anchor stays in place on screen.
+fn zoomed_offset(offset: Vec2, scale: f32, new_scale: f32, anchor: ZoomAnchor) -> Vec2 {
+    let anchor = match anchor {
+        // Center of the viewport shows this point of the graph.
+        ZoomAnchor::Center => offset / scale,
+        ZoomAnchor::Graph(pos) => pos.to_vec2(),
+    };
+    offset + anchor * (new_scale - scale)
+}
+
+/// Stores viewport change to be applied when the graph is shown next time.
+fn request_view(request: ViewRequest, snarl_id: Id, cx: &Context) {
+    cx.data_mut(|d| d.insert_temp(snarl_id, request));
+    cx.request_repaint();
+}
+
+/// Makes zoom animation jump to `scale`,
+/// so it doesn't move the viewport set explicitly.
+fn skip_zoom_animation(cx: &Context, snarl_id: Id, scale: f32) {
+    cx.animate_value_with_time(snarl_id.with("zoom-scale"), scale, 0.0);
+}
 
 /// Node UI state.
 pub struct NodeState {
@@ -24,7 +56,16 @@ struct NodeData {
     unscaled_header_height: f32,
 }
 
+/// Keys of node's inputs from the last frame.
+#[derive(Clone)]
+struct InputKeys(Vec<u64>);
+
 impl NodeState {
+    /// Returns id of the node's UI state in snarl with `snarl_id`.
+    pub fn node_id(snarl_id: Id, node: NodeId) -> Id {
+        snarl_id.with(("snarl-node", node))
+    }
+
     pub fn load(cx: &Context, id: Id, spacing: &Spacing, scale: f32) -> Self {
         cx.data_mut(|d| d.get_temp::<NodeData>(id)).map_or_else(
             || {
@@ -42,7 +83,51 @@ impl NodeState {
     }
 
     pub fn clear(self, cx: &Context) {
-        cx.data_mut(|d| d.remove::<Self>(self.id));
+        Self::forget(cx, self.id);
+    }
+
+    /// Removes state of the node with given id from the context memory.
+    pub fn forget(cx: &Context, id: Id) {
+        cx.data_mut(|d| {
+            d.remove::<NodeData>(id);
+            d.remove::<InputKeys>(id);
+        });
+    }
+
+    /// Returns graph space rect of the node at `pos` (excluding node frame margin)
+    /// using size measured in the last frame.
+    ///
+    /// Collapsed node measures to its header height.
+    /// Returns `None` if node was not shown yet.
+    pub fn graph_rect(cx: &Context, id: Id, pos: Pos2, open: bool) -> Option<Rect> {
+        let data = cx.data(|d| d.get_temp::<NodeData>(id))?;
+
+        let height = if open {
+            f32::max(data.unscaled_header_height, data.unscaled_size.y)
+        } else {
+            data.unscaled_header_height
+        };
+
+        Some(Rect::from_min_size(
+            pos,
+            egui::vec2(data.unscaled_size.x, height),
+        ))
+    }
+
+    /// Stores keys of node's inputs.
+    ///
+    /// Returns previously stored keys if they differ from new ones.
+    pub fn update_input_keys(cx: &Context, id: Id, keys: Vec<u64>) -> Option<Vec<u64>> {
+        cx.data_mut(|d| {
+            let old = d.get_temp::<InputKeys>(id);
+            match old {
+                Some(InputKeys(old)) if old == keys => None,
+                old => {
+                    d.insert_temp(id, InputKeys(keys));
+                    old.map(|InputKeys(old)| old)
+                }
+            }
+        })
     }
 
     pub fn store(&self, cx: &Context) {
@@ -110,6 +195,15 @@ pub enum NewWires {
     Out(Vec<OutPinId>),
 }
 
+impl NewWires {
+    pub fn is_empty(&self) -> bool {
+        match self {
+            NewWires::In(pins) => pins.is_empty(),
+            NewWires::Out(pins) => pins.is_empty(),
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 struct RectSelect {
     origin: Pos2,
@@ -125,6 +219,16 @@ pub struct SnarlState {
 
     target_scale: f32,
 
+    /// Reason of the last change of the target scale.
+    zoom_reason: ViewportChangeReason,
+
+    /// Offset and scale at the beginning of the frame.
+    prev_offset: Vec2,
+    prev_scale: f32,
+
+    /// Reason of the last change of the viewport in this frame.
+    viewport_change: Option<ViewportChangeReason>,
+
     new_wires: Option<NewWires>,
 
     id: Id,
@@ -143,6 +247,28 @@ pub struct SnarlState {
 
     /// List of currently selected nodes.
     selected_nodes: Vec<NodeId>,
+
+    /// List of currently selected wires.
+    selected_wires: Vec<(OutPinId, InPinId)>,
+
+    /// Pin hovered in the previous frame.
+    hovered_pin: Option<AnyPin>,
+
+    /// Pin hovered in this frame.
+    new_hovered_pin: Option<AnyPin>,
+
+    /// Flag indicating that a context menu was open in the previous frame.
+    menu_open: bool,
+
+    /// Flag indicating that a context menu is open in this frame.
+    new_menu_open: bool,
+
+    /// Wires that are pulsing and time when pulse started.
+    wire_pulses: Vec<(OutPinId, InPinId, f64)>,
+
+    /// Flag indicating that new wires are dragged over a pin that can't accept them.
+    /// Not stored, set while drawing nodes in this frame.
+    wire_target_rejected: bool,
 }
 
 #[derive(Clone)]
@@ -151,15 +277,76 @@ struct DrawOrder(Vec<NodeId>);
 #[derive(Clone)]
 struct SelectedNodes(Vec<NodeId>);
 
+#[derive(Clone)]
+struct SelectedWires(Vec<(OutPinId, InPinId)>);
+
+#[derive(Clone)]
+struct WirePulses(Vec<(OutPinId, InPinId, f64)>);
+
+#[derive(Clone, Default)]
+struct SelectionGroups(BTreeMap<String, Vec<NodeId>>);
+
+#[derive(Clone, Copy)]
+struct FollowNode(NodeId);
+
+/// Nodes moved by the ongoing drag.
+#[derive(Clone, Default)]
+struct MovedNodes(Vec<NodeId>);
+
+/// Sizes of wire widgets shown in the previous frame.
+#[derive(Clone, Default)]
+struct WireWidgetSizes(HashMap<(OutPinId, InPinId), Vec2>);
+
+/// Marks that the ongoing node drag has moved past the drag threshold.
+#[derive(Clone, Copy)]
+struct DragThresholdCrossed;
+
+/// Wire the dragged node is inserted onto when dropped.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct WireInsertion {
+    pub node: NodeId,
+    pub from: OutPinId,
+    pub to: InPinId,
+
+    /// Input of the node connected to `from`.
+    pub input: usize,
+
+    /// Output of the node connected to `to`.
+    pub output: usize,
+}
+
+/// Screen rect of the viewport in the last frame.
+#[derive(Clone, Copy)]
+struct ViewportRect(Rect);
+
+/// Viewport change requested by the application.
+/// Applied when the graph is shown next time, so it works before the first frame too.
+#[derive(Clone, Copy)]
+enum ViewRequest {
+    /// Show graph space point in the center of the viewport with given scale.
+    View { center: Pos2, scale: f32 },
+
+    /// Fit graph space rect into the viewport.
+    Rect(Rect),
+
+    /// Center viewport on the node, keeping scale.
+    Node(NodeId),
+}
+
 struct SnarlStateData {
     offset: Vec2,
     scale: f32,
     target_scale: f32,
+    zoom_reason: ViewportChangeReason,
     is_link_menu_open: bool,
+    menu_open: bool,
+    hovered_pin: Option<AnyPin>,
     draw_order: Vec<NodeId>,
     new_wires: Option<NewWires>,
     rect_selection: Option<RectSelect>,
     selected_nodes: Vec<NodeId>,
+    selected_wires: Vec<(OutPinId, InPinId)>,
+    wire_pulses: Vec<(OutPinId, InPinId, f64)>,
 }
 
 #[derive(Clone)]
@@ -167,7 +354,10 @@ struct SnarlStateDataHeader {
     offset: Vec2,
     scale: f32,
     target_scale: f32,
+    zoom_reason: ViewportChangeReason,
     is_link_menu_open: bool,
+    menu_open: bool,
+    hovered_pin: Option<AnyPin>,
 }
 
 impl SnarlStateData {
@@ -179,7 +369,10 @@ impl SnarlStateData {
                     offset: self.offset,
                     scale: self.scale,
                     target_scale: self.target_scale,
+                    zoom_reason: self.zoom_reason,
                     is_link_menu_open: self.is_link_menu_open,
+                    menu_open: self.menu_open,
+                    hovered_pin: self.hovered_pin,
                 },
             );
 
@@ -201,14 +394,35 @@ impl SnarlStateData {
                 d.insert_temp::<SelectedNodes>(id, SelectedNodes(self.selected_nodes));
             }
 
+            if self.selected_wires.is_empty() {
+                d.remove::<SelectedWires>(id);
+            } else {
+                d.insert_temp::<SelectedWires>(id, SelectedWires(self.selected_wires));
+            }
+
             if self.draw_order.is_empty() {
                 d.remove::<DrawOrder>(id);
             } else {
                 d.insert_temp::<DrawOrder>(id, DrawOrder(self.draw_order));
             }
+
+            if self.wire_pulses.is_empty() {
+                d.remove::<WirePulses>(id);
+            } else {
+                d.insert_temp::<WirePulses>(id, WirePulses(self.wire_pulses));
+            }
         });
     }
 
+    /// Returns true if viewport transform is usable.
+    fn is_finite(&self) -> bool {
+        self.offset.is_finite()
+            && self.scale.is_finite()
+            && self.scale > 0.0
+            && self.target_scale.is_finite()
+            && self.target_scale > 0.0
+    }
+
     fn load(cx: &Context, id: Id) -> Option<Self> {
         cx.data(|d| {
             let small = d.get_temp::<SnarlStateDataHeader>(id)?;
@@ -216,17 +430,24 @@ impl SnarlStateData {
             let rect_selection = d.get_temp(id);
 
             let selected_nodes = d.get_temp(id).unwrap_or(SelectedNodes(Vec::new())).0;
+            let selected_wires = d.get_temp(id).unwrap_or(SelectedWires(Vec::new())).0;
             let draw_order = d.get_temp(id).unwrap_or(DrawOrder(Vec::new())).0;
+            let wire_pulses = d.get_temp(id).unwrap_or(WirePulses(Vec::new())).0;
 
             Some(SnarlStateData {
                 offset: small.offset,
                 scale: small.scale,
                 target_scale: small.target_scale,
+                zoom_reason: small.zoom_reason,
                 is_link_menu_open: small.is_link_menu_open,
+                menu_open: small.menu_open,
+                hovered_pin: small.hovered_pin,
                 new_wires,
                 rect_selection,
                 selected_nodes,
+                selected_wires,
                 draw_order,
+                wire_pulses,
             })
         })
     }
@@ -238,6 +459,15 @@ fn prune_selected_nodes<T>(selected_nodes: &mut Vec<NodeId>, snarl: &Snarl<T>) -
     old_size != selected_nodes.len()
 }
 
+fn prune_selected_wires<T>(
+    selected_wires: &mut Vec<(OutPinId, InPinId)>,
+    snarl: &Snarl<T>,
+) -> bool {
+    let old_size = selected_wires.len();
+    selected_wires.retain(|&(out_pin, in_pin)| snarl.connected(out_pin, in_pin));
+    old_size != selected_wires.len()
+}
+
 impl SnarlState {
     pub fn load<T>(
         cx: &Context,
@@ -247,29 +477,58 @@ impl SnarlState {
         snarl: &Snarl<T>,
         style: &SnarlStyle,
     ) -> Self {
-        let Some(mut data) = SnarlStateData::load(cx, id) else {
+        cx.data_mut(|d| d.insert_temp(id, ViewportRect(viewport)));
+
+        let Some(mut data) = SnarlStateData::load(cx, id).filter(SnarlStateData::is_finite) else {
             return Self::initial(id, viewport, snarl, style);
         };
 
-        let new_scale = cx.animate_value_with_time(id.with("zoom-scale"), data.target_scale, 0.1);
+        let prev_offset = data.offset;
+        let prev_scale = data.scale;
+
+        let mut dirty = false;
+
+        let target_scale = data
+            .target_scale
+            .clamp(style.get_min_scale(), style.get_max_scale());
 
         #[allow(clippy::float_cmp)]
-        let mut dirty = if new_scale == data.scale {
-            false
-        } else {
+        if target_scale != data.target_scale {
+            data.target_scale = target_scale;
+            data.zoom_reason = ViewportChangeReason::Clamped;
+            dirty = true;
+        }
+
+        // Target scale is kept continuous, so small zoom steps accumulate.
+        let new_scale = cx.animate_value_with_time(
+            id.with("zoom-scale"),
+            style.snap_scale(data.target_scale),
+            0.1,
+        );
+
+        let mut viewport_change = None;
+
+        #[allow(clippy::float_cmp)]
+        if new_scale != data.scale {
             let a = pivot + data.offset - viewport.center().to_vec2();
 
             data.offset += a * new_scale / data.scale - a;
             data.scale = new_scale;
-            true
-        };
+            viewport_change = Some(data.zoom_reason);
+            dirty = true;
+        }
 
         dirty |= prune_selected_nodes(&mut data.selected_nodes, snarl);
+        dirty |= prune_selected_wires(&mut data.selected_wires, snarl);
 
         SnarlState {
             offset: data.offset,
             scale: data.scale,
             target_scale: data.target_scale,
+            zoom_reason: data.zoom_reason,
+            prev_offset,
+            prev_scale,
+            viewport_change,
             new_wires: data.new_wires,
             is_link_menu_open: data.is_link_menu_open,
             id,
@@ -277,6 +536,13 @@ impl SnarlState {
             draw_order: data.draw_order,
             rect_selection: data.rect_selection,
             selected_nodes: data.selected_nodes,
+            selected_wires: data.selected_wires,
+            hovered_pin: data.hovered_pin,
+            new_hovered_pin: None,
+            menu_open: data.menu_open,
+            new_menu_open: false,
+            wire_pulses: data.wire_pulses,
+            wire_target_rejected: false,
         }
     }
 
@@ -288,7 +554,8 @@ impl SnarlState {
         }
 
         let mut offset = Vec2::ZERO;
-        let mut scale = 1.0f32.clamp(style.get_min_scale(), style.get_max_scale());
+        let mut scale =
+            style.snap_scale(1.0f32.clamp(style.get_min_scale(), style.get_max_scale()));
 
         if bb.is_positive() {
             bb = bb.expand(100.0);
@@ -302,6 +569,7 @@ impl SnarlState {
                 .min(style.get_max_scale())
                 .max(style.get_min_scale());
 
+            scale = style.snap_scale(scale);
             offset = bb.center().to_vec2() * scale;
         }
 
@@ -309,6 +577,10 @@ impl SnarlState {
             offset,
             scale,
             target_scale: scale,
+            zoom_reason: ViewportChangeReason::UserZoom,
+            prev_offset: offset,
+            prev_scale: scale,
+            viewport_change: None,
             new_wires: None,
             is_link_menu_open: false,
             id,
@@ -316,31 +588,51 @@ impl SnarlState {
             draw_order: Vec::new(),
             rect_selection: None,
             selected_nodes: Vec::new(),
+            selected_wires: Vec::new(),
+            hovered_pin: None,
+            new_hovered_pin: None,
+            menu_open: false,
+            new_menu_open: false,
+            wire_pulses: Vec::new(),
+            wire_target_rejected: false,
         }
     }
 
     #[inline(always)]
     pub fn store<T>(mut self, snarl: &Snarl<T>, cx: &Context) {
         self.dirty |= prune_selected_nodes(&mut self.selected_nodes, snarl);
+        self.dirty |= prune_selected_wires(&mut self.selected_wires, snarl);
+        self.dirty |= self.hovered_pin != self.new_hovered_pin;
+        self.dirty |= self.menu_open != self.new_menu_open;
 
         if self.dirty {
             let data = SnarlStateData {
                 offset: self.offset,
                 scale: self.scale,
                 target_scale: self.target_scale,
+                zoom_reason: self.zoom_reason,
                 new_wires: self.new_wires,
                 is_link_menu_open: self.is_link_menu_open,
+                menu_open: self.new_menu_open,
+                hovered_pin: self.new_hovered_pin,
                 draw_order: self.draw_order,
                 rect_selection: self.rect_selection,
                 selected_nodes: self.selected_nodes,
+                selected_wires: self.selected_wires,
+                wire_pulses: self.wire_pulses,
             };
-            data.save(cx, self.id);
+
+            // Broken transform is never stored, previous state is kept instead.
+            if data.is_finite() {
+                data.save(cx, self.id);
+            }
         }
     }
 
     #[inline(always)]
     pub fn pan(&mut self, delta: Vec2) {
         self.offset += delta;
+        self.viewport_change = Some(ViewportChangeReason::UserPan);
         self.dirty = true;
     }
 
@@ -355,9 +647,84 @@ impl SnarlState {
     }
 
     #[inline(always)]
-    pub fn set_scale(&mut self, scale: f32) {
+    pub fn set_scale(&mut self, scale: f32, reason: ViewportChangeReason) {
+        self.target_scale = scale;
+        self.zoom_reason = reason;
+        self.dirty = true;
+    }
+
+    /// Sets viewport scale immediately, keeping the anchor in place on screen.
+    pub fn zoom_to(
+        &mut self,
+        scale: f32,
+        anchor: ZoomAnchor,
+        reason: ViewportChangeReason,
+        cx: &Context,
+    ) {
+        self.offset = zoomed_offset(self.offset, self.scale, scale, anchor);
+        self.scale = scale;
         self.target_scale = scale;
+        self.zoom_reason = reason;
+        self.viewport_change = Some(reason);
         self.dirty = true;
+        skip_zoom_animation(cx, self.id, scale);
+    }
+
+    /// Centers viewport on graph space `rect` and scales it to fit the rect.
+    /// Viewport is never zoomed in above `1.0`.
+    /// Does nothing if the rect is empty.
+    pub fn fit_rect(
+        &mut self,
+        rect: Rect,
+        viewport: Rect,
+        style: &SnarlStyle,
+        reason: ViewportChangeReason,
+        cx: &Context,
+    ) {
+        if rect.any_nan() || rect.is_negative() {
+            return;
+        }
+
+        let rect = rect.expand(FIT_MARGIN);
+        let scale = (viewport.width() / rect.width())
+            .min(viewport.height() / rect.height())
+            .min(1.0)
+            .clamp(style.get_min_scale(), style.get_max_scale());
+        let scale = style.snap_scale(scale);
+
+        self.scale = scale;
+        self.target_scale = scale;
+        self.zoom_reason = reason;
+        self.set_offset(rect.center().to_vec2() * scale, reason);
+        skip_zoom_animation(cx, self.id, scale);
+    }
+
+    /// Returns viewports at the beginning and at the end of the frame
+    /// and reason of the change, if viewport was changed in this frame.
+    pub fn viewport_change(
+        &self,
+        rect: Rect,
+    ) -> Option<(Viewport, Viewport, ViewportChangeReason)> {
+        let reason = self.viewport_change?;
+
+        #[allow(clippy::float_cmp)]
+        if self.prev_offset == self.offset && self.prev_scale == self.scale {
+            return None;
+        }
+
+        let prev = Viewport {
+            rect,
+            scale: self.prev_scale,
+            offset: self.prev_offset,
+        };
+
+        let viewport = Viewport {
+            rect,
+            scale: self.scale,
+            offset: self.offset,
+        };
+
+        Some((prev, viewport, reason))
     }
 
     #[inline(always)]
@@ -479,6 +846,76 @@ impl SnarlState {
         self.new_wires = Some(wires);
     }
 
+    /// Returns pin that was hovered in the previous frame.
+    pub const fn hovered_pin(&self) -> Option<AnyPin> {
+        self.hovered_pin
+    }
+
+    /// Marks pin as hovered in this frame.
+    pub fn set_hovered_pin(&mut self, pin: AnyPin) {
+        self.new_hovered_pin = Some(pin);
+    }
+
+    /// Marks that new wires are dragged over a pin they can't be connected to in this frame.
+    pub fn reject_wire_target(&mut self) {
+        self.wire_target_rejected = true;
+    }
+
+    /// Returns true if new wires are dragged over a pin they can't be connected to in this frame.
+    pub const fn is_wire_target_rejected(&self) -> bool {
+        self.wire_target_rejected
+    }
+
+    /// Returns true if a context menu was open in the previous frame.
+    pub const fn is_menu_open(&self) -> bool {
+        self.menu_open
+    }
+
+    /// Marks that a context menu is open in this frame.
+    pub fn set_menu_open(&mut self) {
+        self.new_menu_open = true;
+    }
+
+    /// Starts pulse of the wire at `time`.
+    pub fn start_wire_pulse(&mut self, from: OutPinId, to: InPinId, time: f64) {
+        match self
+            .wire_pulses
+            .iter_mut()
+            .find(|(f, t, _)| *f == from && *t == to)
+        {
+            Some((_, _, start)) => *start = time,
+            None => self.wire_pulses.push((from, to, time)),
+        }
+        self.dirty = true;
+    }
+
+    /// Returns intensity of the wire pulse at `time` in range `0.0..=1.0`.
+    /// Returns `None` if wire is not pulsing.
+    pub fn wire_pulse(&self, from: OutPinId, to: InPinId, time: f64) -> Option<f32> {
+        let (_, _, start) = self
+            .wire_pulses
+            .iter()
+            .find(|(f, t, _)| *f == from && *t == to)?;
+
+        let t = (time - start) / WIRE_PULSE_DURATION;
+        if t >= 1.0 {
+            return None;
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        Some(1.0 - t.max(0.0) as f32)
+    }
+
+    /// Removes finished wire pulses.
+    /// Returns true if there are pulses in progress.
+    pub fn prune_wire_pulses(&mut self, time: f64) -> bool {
+        let old_len = self.wire_pulses.len();
+        self.wire_pulses
+            .retain(|(_, _, start)| time - start < WIRE_PULSE_DURATION);
+        self.dirty |= old_len != self.wire_pulses.len();
+        !self.wire_pulses.is_empty()
+    }
+
     pub(crate) fn open_link_menu(&mut self) {
         self.is_link_menu_open = true;
         self.dirty = true;
@@ -494,7 +931,7 @@ impl SnarlState {
         self.is_link_menu_open
     }
 
-    pub(crate) fn update_draw_order<T>(&mut self, snarl: &Snarl<T>) -> Vec<NodeId> {
+    pub(crate) fn update_draw_order<T>(&mut self, snarl: &Snarl<T>, cx: &Context) -> Vec<NodeId> {
         let mut node_ids = snarl
             .nodes
             .iter()
@@ -503,6 +940,11 @@ impl SnarlState {
 
         self.draw_order.retain(|id| {
             let has = node_ids.remove(id);
+            if !has {
+                // Node was removed outside of the UI.
+                // Its state would stay in memory forever otherwise.
+                NodeState::forget(cx, NodeState::node_id(self.id, *id));
+            }
             self.dirty |= !has;
             has
         });
@@ -524,15 +966,192 @@ impl SnarlState {
         self.dirty = true;
     }
 
-    pub fn set_offset(&mut self, offset: Vec2) {
+    pub fn set_offset(&mut self, offset: Vec2, reason: ViewportChangeReason) {
         self.offset = offset;
+        self.viewport_change = Some(reason);
         self.dirty = true;
     }
 
+    /// Returns the pin whose node is nearest to `pos` in graph space.
+    ///
+    /// Distance is measured to node centers.
+    /// Nodes that were not shown yet are measured to their positions.
+    pub fn nearest_remote<T, P: Copy>(
+        &self,
+        snarl: &Snarl<T>,
+        remotes: &[P],
+        node_of: impl Fn(P) -> NodeId,
+        pos: Pos2,
+        cx: &Context,
+    ) -> Option<P> {
+        remotes
+            .iter()
+            .filter_map(|&pin| {
+                let node = node_of(pin);
+                let info = snarl.nodes.get(node.0)?;
+                let center = NodeState::graph_rect(
+                    cx,
+                    NodeState::node_id(self.id, node),
+                    info.pos,
+                    info.open,
+                )
+                .map_or(info.pos, |rect| rect.center());
+                Some((pin, center.distance_sq(pos)))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(pin, _)| pin)
+    }
+
+    /// Centers viewport on the followed node, if any.
+    /// Follow target is cleared when the node no longer exists.
+    ///
+    /// Returns `true` if a node is followed.
+    pub fn follow_node<T>(&mut self, snarl: &Snarl<T>, cx: &Context) -> bool {
+        let Some(FollowNode(node)) = cx.data(|d| d.get_temp::<FollowNode>(self.id)) else {
+            return false;
+        };
+
+        let Some(info) = snarl.nodes.get(node.0) else {
+            cx.data_mut(|d| d.remove::<FollowNode>(self.id));
+            return false;
+        };
+
+        let center =
+            NodeState::graph_rect(cx, NodeState::node_id(self.id, node), info.pos, info.open)
+                .map_or(info.pos, |rect| rect.center());
+
+        // Graph position `p` is drawn at `p * scale - offset + viewport.center()`.
+        let offset = center.to_vec2() * self.scale;
+
+        if offset != self.offset {
+            self.set_offset(offset, ViewportChangeReason::FollowNode);
+        }
+        true
+    }
+
+    /// Applies viewport change requested with [`Snarl::set_view`],
+    /// [`Snarl::fit_view`] or [`Snarl::center_on_node`], if any.
+    pub fn apply_view_request<T>(
+        &mut self,
+        snarl: &Snarl<T>,
+        viewport: Rect,
+        style: &SnarlStyle,
+        cx: &Context,
+    ) {
+        let Some(request) = cx.data_mut(|d| {
+            let request = d.get_temp::<ViewRequest>(self.id);
+            d.remove::<ViewRequest>(self.id);
+            request
+        }) else {
+            return;
+        };
+
+        let reason = ViewportChangeReason::Programmatic;
+        match request {
+            ViewRequest::View { center, scale } => {
+                let scale =
+                    style.snap_scale(scale.clamp(style.get_min_scale(), style.get_max_scale()));
+
+                self.scale = scale;
+                self.target_scale = scale;
+                self.zoom_reason = reason;
+                self.set_offset(center.to_vec2() * scale, reason);
+                skip_zoom_animation(cx, self.id, scale);
+            }
+            ViewRequest::Rect(rect) => self.fit_rect(rect, viewport, style, reason, cx),
+            ViewRequest::Node(node) => {
+                let Some(info) = snarl.nodes.get(node.0) else {
+                    return;
+                };
+
+                let center = NodeState::graph_rect(
+                    cx,
+                    NodeState::node_id(self.id, node),
+                    info.pos,
+                    info.open,
+                )
+                .map_or(info.pos, |rect| rect.center());
+
+                self.set_offset(center.to_vec2() * self.scale, reason);
+            }
+        }
+    }
+
     pub fn selected_nodes(&self) -> &[NodeId] {
         &self.selected_nodes
     }
 
+    /// Remembers that the node was moved by the ongoing drag.
+    pub fn add_moved_node(&self, node: NodeId, cx: &Context) {
+        cx.data_mut(|d| {
+            let moved = &mut d.get_temp_mut_or_default::<MovedNodes>(self.id).0;
+            if !moved.contains(&node) {
+                moved.push(node);
+            }
+        });
+    }
+
+    /// Returns nodes moved by the drag that has ended and forgets them.
+    pub fn take_moved_nodes(&self, cx: &Context) -> Vec<NodeId> {
+        cx.data_mut(|d| d.remove_temp::<MovedNodes>(self.id))
+            .map_or_else(Vec::new, |moved| moved.0)
+    }
+
+    /// Returns sizes of wire widgets measured in the previous frame and forgets them.
+    pub fn take_wire_widget_sizes(&self, cx: &Context) -> HashMap<(OutPinId, InPinId), Vec2> {
+        cx.data_mut(|d| d.remove_temp::<WireWidgetSizes>(self.id))
+            .map_or_else(HashMap::new, |sizes| sizes.0)
+    }
+
+    /// Remembers sizes of wire widgets shown in this frame.
+    /// Sizes of widgets that were not shown are forgotten.
+    pub fn set_wire_widget_sizes(&self, sizes: HashMap<(OutPinId, InPinId), Vec2>, cx: &Context) {
+        if !sizes.is_empty() {
+            cx.data_mut(|d| d.insert_temp(self.id, WireWidgetSizes(sizes)));
+        }
+    }
+
+    /// Returns true if the ongoing node drag has moved past the drag threshold.
+    pub fn is_drag_threshold_crossed(&self, cx: &Context) -> bool {
+        cx.data(|d| d.get_temp::<DragThresholdCrossed>(self.id).is_some())
+    }
+
+    /// Remembers that the ongoing node drag has moved past the drag threshold.
+    pub fn cross_drag_threshold(&self, cx: &Context) {
+        cx.data_mut(|d| d.insert_temp(self.id, DragThresholdCrossed));
+    }
+
+    /// Forgets that the drag threshold was crossed, so next drag starts gated again.
+    pub fn reset_drag_threshold(&self, cx: &Context) {
+        cx.data_mut(|d| d.remove::<DragThresholdCrossed>(self.id));
+    }
+
+    /// Returns wire the dragged node would be inserted onto if dropped now.
+    pub fn wire_insertion(&self, cx: &Context) -> Option<WireInsertion> {
+        cx.data(|d| d.get_temp::<Option<WireInsertion>>(self.id))
+            .flatten()
+    }
+
+    /// Remembers wire the dragged node would be inserted onto if dropped now.
+    /// Nothing is kept in memory when there is no such wire.
+    pub fn set_wire_insertion(&self, insertion: Option<WireInsertion>, cx: &Context) {
+        cx.data_mut(|d| match insertion {
+            Some(insertion) => d.insert_temp(self.id, Some(insertion)),
+            None => d.remove::<Option<WireInsertion>>(self.id),
+        });
+    }
+
+    /// Returns wire the dropped node is inserted onto and forgets it.
+    pub fn take_wire_insertion(&self, cx: &Context) -> Option<WireInsertion> {
+        cx.data_mut(|d| d.remove_temp::<Option<WireInsertion>>(self.id))
+            .flatten()
+    }
+
+    /// Removes nodes that no longer exist from selection.
+    pub fn prune_selected_nodes<T>(&mut self, snarl: &Snarl<T>) {
+        self.dirty |= prune_selected_nodes(&mut self.selected_nodes, snarl);
+    }
+
     pub fn select_one_node(&mut self, reset: bool, node: NodeId) {
         if reset {
             if self.selected_nodes[..] == [node] {
@@ -581,6 +1200,56 @@ impl SnarlState {
         self.selected_nodes.clear();
     }
 
+    pub fn selected_wires(&self) -> &[(OutPinId, InPinId)] {
+        &self.selected_wires
+    }
+
+    /// Removes wires that no longer exist from selection.
+    pub fn prune_selected_wires<T>(&mut self, snarl: &Snarl<T>) {
+        self.dirty |= prune_selected_wires(&mut self.selected_wires, snarl);
+    }
+
+    pub fn select_one_wire(&mut self, reset: bool, wire: (OutPinId, InPinId)) {
+        if reset {
+            if self.selected_wires[..] == [wire] {
+                return;
+            }
+
+            self.deselect_all_wires();
+        } else if self.selected_wires.contains(&wire) {
+            return;
+        }
+        self.selected_wires.push(wire);
+        self.dirty = true;
+    }
+
+    pub fn select_many_wires(
+        &mut self,
+        reset: bool,
+        wires: impl Iterator<Item = (OutPinId, InPinId)>,
+    ) {
+        if reset {
+            self.deselect_all_wires();
+        }
+        wires.for_each(|wire| self.select_one_wire(false, wire));
+    }
+
+    pub fn deselect_one_wire(&mut self, wire: (OutPinId, InPinId)) {
+        if let Some(pos) = self.selected_wires.iter().position(|w| *w == wire) {
+            self.selected_wires.remove(pos);
+            self.dirty = true;
+        }
+    }
+
+    pub fn deselect_many_wires(&mut self, wires: impl Iterator<Item = (OutPinId, InPinId)>) {
+        wires.for_each(|wire| self.deselect_one_wire(wire));
+    }
+
+    pub fn deselect_all_wires(&mut self) {
+        self.dirty |= !self.selected_wires.is_empty();
+        self.selected_wires.clear();
+    }
+
     pub fn start_rect_selection(&mut self, pos: Pos2) {
         self.dirty |= self.rect_selection.is_none();
         self.rect_selection = Some(RectSelect {
@@ -633,4 +1302,504 @@ impl<T> Snarl<T> {
                 .0
         })
     }
+
+    /// Returns wires selected in the UI.
+    ///
+    /// Use `id_salt` and [`Ui`] that were used in [`Snarl::show`] method.
+    ///
+    /// If same [`Ui`] is not available, use [`Snarl::get_selected_wires_at`] and provide `id` of the [`Ui`] used in [`Snarl::show`] method.
+    pub fn get_selected_wires(id_salt: impl Hash, ui: &mut Ui) -> Vec<(OutPinId, InPinId)> {
+        Self::get_selected_wires_at(id_salt, ui.id(), ui.ctx())
+    }
+
+    /// Returns wires selected in the UI.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn get_selected_wires_at(
+        id_salt: impl Hash,
+        id: Id,
+        cx: &Context,
+    ) -> Vec<(OutPinId, InPinId)> {
+        let snarl_id = id.with(id_salt);
+
+        cx.data(|d| {
+            d.get_temp::<SelectedWires>(snarl_id)
+                .unwrap_or(SelectedWires(Vec::new()))
+                .0
+        })
+    }
+
+    /// Saves nodes currently selected in the UI as named selection group.
+    /// Group with the same name is replaced.
+    ///
+    /// Selection groups are kept in egui memory along with other UI state and are not persisted.
+    /// Use [`Snarl::get_selection_groups`] and [`Snarl::set_selection_groups`]
+    /// to store them in the application's document.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn save_selection_group(name: &str, id_salt: impl Hash, id: Id, cx: &Context) {
+        let selected = Self::get_selected_nodes_at(&id_salt, id, cx);
+        let snarl_id = id.with(id_salt);
+
+        cx.data_mut(|d| {
+            d.get_temp_mut_or_default::<SelectionGroups>(snarl_id)
+                .0
+                .insert(name.to_owned(), selected);
+        });
+    }
+
+    /// Selects nodes from named selection group in the UI.
+    ///
+    /// Nodes that no longer exist are removed from the group.
+    /// Returns selected nodes or `None` if there's no group with this name.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn load_selection_group(
+        &self,
+        name: &str,
+        id_salt: impl Hash,
+        id: Id,
+        cx: &Context,
+    ) -> Option<Vec<NodeId>> {
+        let snarl_id = id.with(id_salt);
+
+        cx.data_mut(|d| {
+            let groups = d.get_temp_mut_or_default::<SelectionGroups>(snarl_id);
+            let group = groups.0.get_mut(name)?;
+            prune_selected_nodes(group, self);
+            let group = group.clone();
+
+            if group.is_empty() {
+                d.remove::<SelectedNodes>(snarl_id);
+            } else {
+                d.insert_temp(snarl_id, SelectedNodes(group.clone()));
+            }
+            Some(group)
+        })
+    }
+
+    /// Deletes named selection group.
+    /// Returns `false` if there's no group with this name.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn delete_selection_group(name: &str, id_salt: impl Hash, id: Id, cx: &Context) -> bool {
+        let snarl_id = id.with(id_salt);
+
+        cx.data_mut(|d| {
+            d.get_temp_mut_or_default::<SelectionGroups>(snarl_id)
+                .0
+                .remove(name)
+                .is_some()
+        })
+    }
+
+    /// Returns all selection groups by name.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn get_selection_groups(
+        id_salt: impl Hash,
+        id: Id,
+        cx: &Context,
+    ) -> BTreeMap<String, Vec<NodeId>> {
+        let snarl_id = id.with(id_salt);
+
+        cx.data(|d| {
+            d.get_temp::<SelectionGroups>(snarl_id)
+                .unwrap_or_default()
+                .0
+        })
+    }
+
+    /// Replaces all selection groups, e.g. with groups loaded from application's document.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn set_selection_groups(
+        groups: BTreeMap<String, Vec<NodeId>>,
+        id_salt: impl Hash,
+        id: Id,
+        cx: &Context,
+    ) {
+        let snarl_id = id.with(id_salt);
+
+        cx.data_mut(|d| {
+            if groups.is_empty() {
+                d.remove::<SelectionGroups>(snarl_id);
+            } else {
+                d.insert_temp(snarl_id, SelectionGroups(groups));
+            }
+        });
+    }
+
+    /// Removes all UI state of the graph from egui memory.
+    ///
+    /// See [`Snarl::forget_ui_state_at`] for details.
+    ///
+    /// Use `id_salt` and [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn forget_ui_state(id_salt: impl Hash, ui: &mut Ui) {
+        Self::forget_ui_state_at(id_salt, ui.id(), ui.ctx());
+    }
+
+    /// Removes all UI state of the graph from egui memory,
+    /// e.g. when panel showing the graph is closed for good.
+    ///
+    /// This clears viewport offset and scale, draw order, node and wire selection, selection groups,
+    /// followed node, requested viewport change, nodes and wires being dragged,
+    /// wire the dragged node would be inserted onto, rect selection,
+    /// wire pulses, wire widget sizes and remembered node menu pin,
+    /// as well as measured size and input keys of every node that was shown.
+    /// Next [`Snarl::show`] with the same id starts from initial state.
+    ///
+    /// Animations are managed by egui and fade out on their own.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn forget_ui_state_at(id_salt: impl Hash, id: Id, cx: &Context) {
+        let snarl_id = id.with(id_salt);
+
+        let draw_order = cx.data_mut(|d| {
+            let draw_order = d.get_temp::<DrawOrder>(snarl_id);
+
+            d.remove::<SnarlStateDataHeader>(snarl_id);
+            d.remove::<NewWires>(snarl_id);
+            d.remove::<RectSelect>(snarl_id);
+            d.remove::<SelectedNodes>(snarl_id);
+            d.remove::<SelectedWires>(snarl_id);
+            d.remove::<DrawOrder>(snarl_id);
+            d.remove::<WirePulses>(snarl_id);
+            d.remove::<SelectionGroups>(snarl_id);
+            d.remove::<FollowNode>(snarl_id);
+            d.remove::<ViewRequest>(snarl_id);
+            d.remove::<ViewportRect>(snarl_id);
+            d.remove::<MovedNodes>(snarl_id);
+            d.remove::<DragThresholdCrossed>(snarl_id);
+            d.remove::<Option<WireInsertion>>(snarl_id);
+            d.remove::<WireWidgetSizes>(snarl_id);
+            d.remove::<Option<AnyPin>>(snarl_id.with("node-menu-pin"));
+
+            draw_order
+        });
+
+        // Draw order lists every node that was shown.
+        for node in draw_order.map_or_else(Vec::new, |order| order.0) {
+            NodeState::forget(cx, NodeState::node_id(snarl_id, node));
+        }
+    }
+
+    /// Keeps viewport centered on the node while it moves, without changing zoom.
+    /// Pass `None` to stop following.
+    ///
+    /// While a node is followed, it overrides panning by dragging the background
+    /// as well as centering with double click.
+    /// Following stops when the node is removed.
+    ///
+    /// Use `id_salt` and [`Ui`] that were used in [`Snarl::show`] method.
+    ///
+    /// If same [`Ui`] is not available, use [`Snarl::follow_node_at`] and provide `id` of the [`Ui`] used in [`Snarl::show`] method.
+    pub fn follow_node(node: Option<NodeId>, id_salt: impl Hash, ui: &mut Ui) {
+        Self::follow_node_at(node, id_salt, ui.id(), ui.ctx());
+    }
+
+    /// Keeps viewport centered on the node while it moves, without changing zoom.
+    /// Pass `None` to stop following.
+    ///
+    /// See [`Snarl::follow_node`] for details.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn follow_node_at(node: Option<NodeId>, id_salt: impl Hash, id: Id, cx: &Context) {
+        let snarl_id = id.with(id_salt);
+
+        cx.data_mut(|d| match node {
+            Some(node) => d.insert_temp(snarl_id, FollowNode(node)),
+            None => d.remove::<FollowNode>(snarl_id),
+        });
+    }
+
+    /// Returns node followed by the viewport.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn get_followed_node_at(id_salt: impl Hash, id: Id, cx: &Context) -> Option<NodeId> {
+        let snarl_id = id.with(id_salt);
+
+        cx.data(|d| d.get_temp::<FollowNode>(snarl_id))
+            .map(|FollowNode(node)| node)
+    }
+
+    /// Moves viewport so that graph space point `center` is in its center
+    /// and sets viewport scale.
+    ///
+    /// Change is applied when the graph is shown next time,
+    /// so it may be requested before the graph is shown for the first time.
+    /// Scale is clamped to [`SnarlStyle::min_scale`] and [`SnarlStyle::max_scale`].
+    /// Later request replaces earlier one made before the graph is shown.
+    ///
+    /// Use `id_salt` and [`Ui`] that were used in [`Snarl::show`] method.
+    ///
+    /// If same [`Ui`] is not available, use [`Snarl::set_view_at`] and provide `id` of the [`Ui`] used in [`Snarl::show`] method.
+    pub fn set_view(center: Pos2, scale: f32, id_salt: impl Hash, ui: &mut Ui) {
+        Self::set_view_at(center, scale, id_salt, ui.id(), ui.ctx());
+    }
+
+    /// Moves viewport so that graph space point `center` is in its center
+    /// and sets viewport scale.
+    ///
+    /// See [`Snarl::set_view`] for details.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn set_view_at(center: Pos2, scale: f32, id_salt: impl Hash, id: Id, cx: &Context) {
+        if !center.is_finite() || !scale.is_finite() || scale <= 0.0 {
+            return;
+        }
+
+        request_view(ViewRequest::View { center, scale }, id.with(id_salt), cx);
+    }
+
+    /// Centers viewport on graph space `rect` and scales it to fit the rect with a small margin,
+    /// e.g. the one returned by [`Snarl::nodes_bounding_rect`].
+    /// Viewport is never zoomed in above `1.0` this way.
+    ///
+    /// Change is applied when the graph is shown next time,
+    /// so it may be requested before the graph is shown for the first time.
+    /// Later request replaces earlier one made before the graph is shown.
+    ///
+    /// Use `id_salt` and [`Ui`] that were used in [`Snarl::show`] method.
+    ///
+    /// If same [`Ui`] is not available, use [`Snarl::fit_view_at`] and provide `id` of the [`Ui`] used in [`Snarl::show`] method.
+    pub fn fit_view(rect: Rect, id_salt: impl Hash, ui: &mut Ui) {
+        Self::fit_view_at(rect, id_salt, ui.id(), ui.ctx());
+    }
+
+    /// Centers viewport on graph space `rect` and scales it to fit the rect.
+    ///
+    /// See [`Snarl::fit_view`] for details.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn fit_view_at(rect: Rect, id_salt: impl Hash, id: Id, cx: &Context) {
+        request_view(ViewRequest::Rect(rect), id.with(id_salt), cx);
+    }
+
+    /// Centers viewport on the node without changing zoom.
+    ///
+    /// Change is applied when the graph is shown next time,
+    /// so it may be requested before the graph is shown for the first time.
+    /// Node that was not shown yet is centered on its position.
+    /// Later request replaces earlier one made before the graph is shown.
+    /// Unlike [`Snarl::follow_node`] viewport is moved only once.
+    ///
+    /// Use `id_salt` and [`Ui`] that were used in [`Snarl::show`] method.
+    ///
+    /// If same [`Ui`] is not available, use [`Snarl::center_on_node_at`] and provide `id` of the [`Ui`] used in [`Snarl::show`] method.
+    pub fn center_on_node(node: NodeId, id_salt: impl Hash, ui: &mut Ui) {
+        Self::center_on_node_at(node, id_salt, ui.id(), ui.ctx());
+    }
+
+    /// Centers viewport on the node without changing zoom.
+    ///
+    /// See [`Snarl::center_on_node`] for details.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    ///
+    /// # Examples
+    ///
+    /// Button in a side panel that centers the graph shown in the central panel on selected node.
+    ///
+    /// ```
+    /// # use egui_snarl::Snarl;
+    /// fn side_panel<T>(ctx: &egui::Context, graph_ui: egui::Id) {
+    ///     egui::SidePanel::left("tools").show(ctx, |ui| {
+    ///         let selected = Snarl::<T>::get_selected_nodes_at("snarl", graph_ui, ctx);
+    ///         if let [node] = selected[..] {
+    ///             if ui.button("Focus").clicked() {
+    ///                 Snarl::<T>::center_on_node_at(node, "snarl", graph_ui, ctx);
+    ///             }
+    ///         }
+    ///     });
+    /// }
+    /// ```
+    pub fn center_on_node_at(node: NodeId, id_salt: impl Hash, id: Id, cx: &Context) {
+        request_view(ViewRequest::Node(node), id.with(id_salt), cx);
+    }
+
+    /// Sets viewport scale, keeping the anchor in place on screen.
+    ///
+    /// Scale is clamped to [`SnarlStyle::min_scale`] and [`SnarlStyle::max_scale`]
+    /// when the graph is shown next time.
+    /// Does nothing if the graph was not shown yet.
+    ///
+    /// Use `id_salt` and [`Ui`] that were used in [`Snarl::show`] method.
+    ///
+    /// If same [`Ui`] is not available, use [`Snarl::set_zoom_at`] and provide `id` of the [`Ui`] used in [`Snarl::show`] method.
+    pub fn set_zoom(scale: f32, anchor: ZoomAnchor, id_salt: impl Hash, ui: &mut Ui) {
+        Self::set_zoom_at(scale, anchor, id_salt, ui.id(), ui.ctx());
+    }
+
+    /// Sets viewport scale, keeping the anchor in place on screen.
+    ///
+    /// See [`Snarl::set_zoom`] for details.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn set_zoom_at(scale: f32, anchor: ZoomAnchor, id_salt: impl Hash, id: Id, cx: &Context) {
+        if !scale.is_finite() || scale <= 0.0 {
+            return;
+        }
+
+        let snarl_id = id.with(id_salt);
+
+        let changed = cx.data_mut(|d| {
+            let Some(mut header) = d.get_temp::<SnarlStateDataHeader>(snarl_id) else {
+                return false;
+            };
+
+            header.offset = zoomed_offset(header.offset, header.scale, scale, anchor);
+            header.scale = scale;
+            header.target_scale = scale;
+            header.zoom_reason = ViewportChangeReason::Programmatic;
+            d.insert_temp(snarl_id, header);
+            true
+        });
+
+        if changed {
+            skip_zoom_animation(cx, snarl_id, scale);
+        }
+    }
+
+    /// Returns current viewport scale.
+    /// Returns `None` if the graph was not shown yet.
+    ///
+    /// Use `id_salt` and [`Ui`] that were used in [`Snarl::show`] method.
+    ///
+    /// If same [`Ui`] is not available, use [`Snarl::get_zoom_at`] and provide `id` of the [`Ui`] used in [`Snarl::show`] method.
+    pub fn get_zoom(id_salt: impl Hash, ui: &mut Ui) -> Option<f32> {
+        Self::get_zoom_at(id_salt, ui.id(), ui.ctx())
+    }
+
+    /// Returns current viewport scale.
+    /// Returns `None` if the graph was not shown yet.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn get_zoom_at(id_salt: impl Hash, id: Id, cx: &Context) -> Option<f32> {
+        let snarl_id = id.with(id_salt);
+
+        cx.data(|d| d.get_temp::<SnarlStateDataHeader>(snarl_id))
+            .map(|header| header.scale)
+    }
+
+    /// Returns rect in graph space covering all nodes, excluding node frame margins.
+    /// Returns `None` if the graph has no nodes.
+    ///
+    /// Node sizes are measured when nodes are shown,
+    /// so nodes that were not shown yet are covered by their positions only.
+    /// Useful to fit all nodes into view or to size an exported image.
+    ///
+    /// Use `id_salt` and [`Ui`] that were used in [`Snarl::show`] method.
+    ///
+    /// If same [`Ui`] is not available, use [`Snarl::nodes_bounding_rect_at`] and provide `id` of the [`Ui`] used in [`Snarl::show`] method.
+    pub fn nodes_bounding_rect(&self, id_salt: impl Hash, ui: &mut Ui) -> Option<Rect> {
+        self.nodes_bounding_rect_at(id_salt, ui.id(), ui.ctx())
+    }
+
+    /// Returns rect in graph space covering all nodes, excluding node frame margins.
+    /// Returns `None` if the graph has no nodes.
+    ///
+    /// See [`Snarl::nodes_bounding_rect`] for details.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn nodes_bounding_rect_at(&self, id_salt: impl Hash, id: Id, cx: &Context) -> Option<Rect> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let nodes = self.nodes.iter().map(|(idx, _)| NodeId(idx));
+        Some(self.nodes_graph_rect(nodes, id.with(id_salt), cx))
+    }
+
+    /// Returns viewport of the graph as it was at the end of the last frame.
+    /// Returns `None` if the graph was not shown yet.
+    ///
+    /// Use it to convert between screen and graph space outside of [`Snarl::show`],
+    /// e.g. to place nodes where files are dropped onto the graph.
+    ///
+    /// Use `id_salt` and [`Ui`] that were used in [`Snarl::show`] method.
+    ///
+    /// If same [`Ui`] is not available, use [`Snarl::get_viewport_at`] and provide `id` of the [`Ui`] used in [`Snarl::show`] method.
+    pub fn get_viewport(id_salt: impl Hash, ui: &mut Ui) -> Option<Viewport> {
+        Self::get_viewport_at(id_salt, ui.id(), ui.ctx())
+    }
+
+    /// Returns viewport of the graph as it was at the end of the last frame.
+    /// Returns `None` if the graph was not shown yet.
+    ///
+    /// See [`Snarl::get_viewport`] for details.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn get_viewport_at(id_salt: impl Hash, id: Id, cx: &Context) -> Option<Viewport> {
+        let snarl_id = id.with(id_salt);
+
+        cx.data(|d| {
+            let header = d.get_temp::<SnarlStateDataHeader>(snarl_id)?;
+            let ViewportRect(rect) = d.get_temp(snarl_id)?;
+
+            Some(Viewport {
+                rect,
+                scale: header.scale,
+                offset: header.offset,
+            })
+        })
+    }
+
+    /// Returns graph space rect covering given nodes.
+    ///
+    /// Nodes that were not shown yet are covered by their positions only.
+    /// Returns [`Rect::NOTHING`] if there are no such nodes.
+    pub(crate) fn nodes_graph_rect(
+        &self,
+        nodes: impl IntoIterator<Item = NodeId>,
+        snarl_id: Id,
+        cx: &Context,
+    ) -> Rect {
+        let mut rect = Rect::NOTHING;
+        for node in nodes {
+            let Some(info) = self.nodes.get(node.0) else {
+                continue;
+            };
+
+            let node_id = NodeState::node_id(snarl_id, node);
+            match NodeState::graph_rect(cx, node_id, info.pos, info.open) {
+                Some(node_rect) => rect = rect.union(node_rect),
+                None => rect.extend_with(info.pos),
+            }
+        }
+        rect
+    }
+
+    /// Returns rect of the node in graph space, excluding node frame margin.
+    ///
+    /// Size is measured when node is shown, so `None` is returned for nodes that were not shown yet.
+    /// Open state is taken from the node itself and not from collapsing animation,
+    /// so changes made with [`Snarl::open_node`], [`Snarl::open_nodes`] or [`Snarl::open_all`]
+    /// are reflected immediately, without waiting for the next frame.
+    /// Collapsed node measures to its header height.
+    ///
+    /// Use `id_salt` and [`Ui`] that were used in [`Snarl::show`] method.
+    ///
+    /// If same [`Ui`] is not available, use [`Snarl::get_node_rect_at`] and provide `id` of the [`Ui`] used in [`Snarl::show`] method.
+    pub fn get_node_rect(&self, node: NodeId, id_salt: impl Hash, ui: &mut Ui) -> Option<Rect> {
+        self.get_node_rect_at(node, id_salt, ui.id(), ui.ctx())
+    }
+
+    /// Returns rect of the node in graph space, excluding node frame margin.
+    ///
+    /// See [`Snarl::get_node_rect`] for details.
+    ///
+    /// Use `id_salt` as well as [`Id`] and [`Context`] of the [`Ui`] that were used in [`Snarl::show`] method.
+    pub fn get_node_rect_at(
+        &self,
+        node: NodeId,
+        id_salt: impl Hash,
+        id: Id,
+        cx: &Context,
+    ) -> Option<Rect> {
+        let info = self.nodes.get(node.0)?;
+        let snarl_id = id.with(id_salt);
+
+        NodeState::graph_rect(cx, NodeState::node_id(snarl_id, node), info.pos, info.open)
+    }
 }
@@ -0,0 +1,3922 @@
+use std::collections::HashMap;
+
+use egui::{
+    pos2, vec2, Color32, Context, Event, FullOutput, Id, Key, KeyboardShortcut, Modifiers, Painter,
+    PointerButton, Pos2, RawInput, Rect, Shape, Style, TextureId, Ui, Vec2,
+};
+
+use crate::{InPin, InPinId, NodeId, OutPin, OutPinId, Snarl};
+
+use super::{
+    wire::pick_wire_style, AnyPin, AnyPins, BackgroundPattern, Effects, NodeActivity, NodeIcon,
+    PinInfo, PinPlacement, PinSide, ProgressStyle, RaisePolicy, SelectionStyle, SnarlStyle,
+    SnarlViewer, Viewport, ViewportChangeReason, WireColorMode, WireEndMarker, WireInfo, WireStyle,
+    ZoomAnchor,
+};
+
+/// Viewer for nodes with one input and one output.
+#[derive(Default)]
+struct TestViewer {
+    /// Input hover flags reported in each frame.
+    input_hovered: Vec<bool>,
+
+    /// Rect of the input's label.
+    input_label: Option<Rect>,
+
+    /// Positions of drawn input pins.
+    input_pos: HashMap<InPinId, Pos2>,
+
+    /// Positions of drawn output pins.
+    output_pos: HashMap<OutPinId, Pos2>,
+
+    /// Rect of the header's collapse icon of each node.
+    collapse_icons: HashMap<NodeId, Rect>,
+
+    /// Rect of the header's title of each node.
+    titles: HashMap<NodeId, Rect>,
+
+    /// Rect of the header's content of each node.
+    headers: HashMap<NodeId, Rect>,
+
+    /// Pins placed on the side other than default.
+    moved_pins: Vec<AnyPin>,
+
+    /// Nodes in the order their headers were shown.
+    header_order: Vec<NodeId>,
+
+    /// Size of the content shown between pin columns.
+    center_size: Option<Vec2>,
+
+    /// Rect of the content shown between pin columns of each node.
+    centers: HashMap<NodeId, Rect>,
+
+    /// Pins reported to the node menu in each frame it was shown.
+    menu_pins: Vec<Option<AnyPin>>,
+
+    /// Activity reported for all nodes.
+    activity: Option<NodeActivity>,
+
+    /// Pins passed to the dropped wire menu in each frame it was shown.
+    dropped_pins: Vec<Vec<AnyPin>>,
+
+    /// Breakpoint state reported for all nodes.
+    breakpoint: Option<bool>,
+
+    /// Nodes whose breakpoints were toggled.
+    toggled: Vec<NodeId>,
+
+    /// Pins shown as invisible.
+    invisible_pins: Vec<AnyPin>,
+
+    /// Viewport at the beginning of the last frame.
+    viewport: Option<Viewport>,
+
+    /// Opacity reported for all nodes.
+    opacity: Option<f32>,
+
+    /// Reported node moves with old and new positions.
+    moves: Vec<(NodeId, Pos2, Pos2)>,
+
+    /// Nodes reported at the end of each drag.
+    drags: Vec<Vec<NodeId>>,
+
+    /// Input pins that refuse new wires.
+    rejected_inputs: Vec<InPinId>,
+
+    /// Input pins whose wires refuse to be disconnected.
+    locked_inputs: Vec<InPinId>,
+
+    /// Effects returned at the end of the frame.
+    effects: Effects<()>,
+
+    /// Input pins whose wires are drawn red and wide.
+    error_inputs: Vec<InPinId>,
+
+    /// Show widget on wires.
+    wire_widgets: bool,
+
+    /// Rect of the widget of each wire, shown in the last frame.
+    wire_widget_rects: HashMap<(OutPinId, InPinId), Rect>,
+
+    /// Wires whose widget button was clicked.
+    wire_widget_clicks: Vec<(OutPinId, InPinId)>,
+
+    /// Show tooltip on hovered wires.
+    wire_hover: bool,
+
+    /// Wires whose tooltip was shown in each frame.
+    wire_hovers: Vec<(OutPinId, InPinId)>,
+
+    /// Input pins whose wires end with a dot.
+    dot_inputs: Vec<InPinId>,
+
+    /// Fill outputs red and inputs blue.
+    colored_pins: bool,
+
+    /// Draw all wires in a single color, even with gradient color mode.
+    flat_wires: bool,
+
+    /// Flow speed set on all input pins.
+    input_wire_flow: Option<f32>,
+
+    /// Insert dropped nodes onto wires using their first input and output.
+    insert_on_wires: bool,
+}
+
+impl SnarlViewer<()> for TestViewer {
+    fn title(&mut self, (): &()) -> String {
+        "Node".to_owned()
+    }
+
+    fn inputs(&mut self, (): &()) -> usize {
+        1
+    }
+
+    fn outputs(&mut self, (): &()) -> usize {
+        1
+    }
+
+    fn show_header(
+        &mut self,
+        node: NodeId,
+        _: &[InPin],
+        _: &[OutPin],
+        ui: &mut Ui,
+        _: f32,
+        _: &mut Snarl<()>,
+    ) {
+        // Collapse icon is the first element in the header.
+        let icon_size = vec2(ui.spacing().icon_width, ui.spacing().icon_width);
+        self.collapse_icons
+            .insert(node, Rect::from_min_size(ui.min_rect().min, icon_size));
+
+        // Title is not selectable, so clicks on it reach the node.
+        let title = ui.add(egui::Label::new("Node").selectable(false));
+        self.titles.insert(node, title.rect);
+        self.headers.insert(node, ui.min_rect());
+        self.header_order.push(node);
+    }
+
+    fn node_progress(&mut self, (): &()) -> Option<NodeActivity> {
+        self.activity
+    }
+
+    fn node_opacity(&mut self, (): &()) -> f32 {
+        self.opacity.unwrap_or(1.0)
+    }
+
+    fn breakpoint_state(&mut self, (): &()) -> Option<bool> {
+        self.breakpoint
+    }
+
+    fn toggle_breakpoint(&mut self, node: NodeId, _: &mut Snarl<()>) {
+        self.toggled.push(node);
+    }
+
+    fn node_moved(&mut self, node: NodeId, old_pos: Pos2, new_pos: Pos2, snarl: &mut Snarl<()>) {
+        assert_eq!(snarl.get_node_info(node).unwrap().pos, new_pos);
+        self.moves.push((node, old_pos, new_pos));
+    }
+
+    fn node_drag_finished(&mut self, nodes: &[NodeId], _: &mut Snarl<()>) {
+        self.drags.push(nodes.to_vec());
+    }
+
+    fn can_insert_on_wire(
+        &mut self,
+        _: NodeId,
+        _: OutPinId,
+        _: InPinId,
+        _: &Snarl<()>,
+    ) -> Option<(usize, usize)> {
+        self.insert_on_wires.then_some((0, 0))
+    }
+
+    fn has_node_menu(&mut self, (): &()) -> bool {
+        true
+    }
+
+    fn show_node_menu_for_pin(
+        &mut self,
+        _: NodeId,
+        clicked_pin: Option<AnyPin>,
+        _: &[InPin],
+        _: &[OutPin],
+        ui: &mut Ui,
+        _: f32,
+        _: &mut Snarl<()>,
+    ) {
+        self.menu_pins.push(clicked_pin);
+        ui.label("Menu");
+    }
+
+    fn wire_style(
+        &mut self,
+        _: &OutPinId,
+        to: &InPinId,
+        default: WireInfo,
+        _: &Snarl<()>,
+    ) -> WireInfo {
+        if self.error_inputs.contains(to) {
+            WireInfo {
+                color: Color32::RED,
+                end_color: None,
+                width: 4.0,
+                ..default
+            }
+        } else if self.flat_wires {
+            WireInfo {
+                end_color: None,
+                ..default
+            }
+        } else {
+            default
+        }
+    }
+
+    fn has_wire_hover(&mut self, _: &OutPinId, _: &InPinId, _: &Snarl<()>) -> bool {
+        self.wire_hover
+    }
+
+    fn show_wire_hover(
+        &mut self,
+        from: &OutPin,
+        to: &InPin,
+        ui: &mut Ui,
+        _: f32,
+        _: &mut Snarl<()>,
+    ) {
+        self.wire_hovers.push((from.id, to.id));
+        ui.label("Value");
+    }
+
+    fn has_wire_widget(&mut self, _: &OutPinId, _: &InPinId, _: &Snarl<()>) -> bool {
+        self.wire_widgets
+    }
+
+    fn show_wire_widget(
+        &mut self,
+        from: &OutPin,
+        to: &InPin,
+        ui: &mut Ui,
+        _: f32,
+        _: &mut Snarl<()>,
+    ) {
+        let r = ui.button("Mute");
+        if r.clicked() {
+            self.wire_widget_clicks.push((from.id, to.id));
+        }
+        self.wire_widget_rects.insert((from.id, to.id), r.rect);
+    }
+
+    fn take_effects(&mut self) -> Effects<()> {
+        std::mem::take(&mut self.effects)
+    }
+
+    fn can_connect(&mut self, _: &OutPin, to: &InPin, _: &Snarl<()>) -> bool {
+        !self.rejected_inputs.contains(&to.id)
+    }
+
+    fn disconnect(&mut self, from: &OutPin, to: &InPin, snarl: &mut Snarl<()>) {
+        if !self.locked_inputs.contains(&to.id) {
+            snarl.disconnect(from.id, to.id);
+        }
+    }
+
+    fn has_dropped_wire_menu(&mut self, _: AnyPins, _: &mut Snarl<()>) -> bool {
+        true
+    }
+
+    fn show_dropped_wire_menu(
+        &mut self,
+        _: Pos2,
+        ui: &mut Ui,
+        _: f32,
+        src_pins: AnyPins,
+        _: &mut Snarl<()>,
+    ) {
+        assert_eq!(src_pins.len(), src_pins.iter().count());
+        self.dropped_pins.push(src_pins.iter().collect());
+        ui.label("Menu");
+    }
+
+    fn show_center(
+        &mut self,
+        node: NodeId,
+        _: &[InPin],
+        _: &[OutPin],
+        ui: &mut Ui,
+        _: f32,
+        _: &mut Snarl<()>,
+    ) {
+        if let Some(size) = self.center_size {
+            let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+            self.centers.insert(node, rect);
+        }
+    }
+
+    fn pin_side(&mut self, default: PinSide, _: NodeId, pin: AnyPin, _: &Snarl<()>) -> PinSide {
+        if self.moved_pins.contains(&pin) {
+            default.opposite()
+        } else {
+            default
+        }
+    }
+
+    fn show_input(&mut self, pin: &InPin, ui: &mut Ui, _: f32, _: &mut Snarl<()>) -> PinInfo {
+        self.input_hovered.push(pin.hovered);
+
+        ui.vertical(|ui| {
+            // Label is not selectable, so right clicks on it reach the node.
+            self.input_label = Some(ui.add(egui::Label::new("Input").selectable(false)).rect);
+
+            if pin.hovered {
+                // Content grows when hovered.
+                ui.label("Details");
+                ui.label("More details");
+            }
+        });
+
+        let info = if self.invisible_pins.contains(&AnyPin::In(pin.id)) {
+            PinInfo::invisible()
+        } else if self.dot_inputs.contains(&pin.id) {
+            PinInfo::circle().with_wire_end_marker(WireEndMarker::Dot { radius: 3.0 })
+        } else if self.colored_pins {
+            PinInfo::circle().with_fill(Color32::BLUE)
+        } else {
+            PinInfo::circle()
+        };
+
+        match self.input_wire_flow {
+            Some(flow) => info.with_wire_flow(flow),
+            None => info,
+        }
+    }
+
+    fn show_output(&mut self, pin: &OutPin, ui: &mut Ui, _: f32, _: &mut Snarl<()>) -> PinInfo {
+        ui.label("Output");
+
+        if self.invisible_pins.contains(&AnyPin::Out(pin.id)) {
+            PinInfo::invisible()
+        } else if self.colored_pins {
+            PinInfo::circle().with_fill(Color32::RED)
+        } else {
+            PinInfo::circle()
+        }
+    }
+
+    fn draw_background(
+        &mut self,
+        background: Option<&BackgroundPattern>,
+        viewport: &Viewport,
+        snarl_style: &SnarlStyle,
+        style: &Style,
+        painter: &Painter,
+        _: &Snarl<()>,
+    ) {
+        self.viewport = Some(*viewport);
+        if let Some(background) = background {
+            background.draw(viewport, snarl_style, style, painter);
+        }
+    }
+
+    fn draw_input_pin(
+        &mut self,
+        pin: &InPin,
+        pin_info: &PinInfo,
+        pos: Pos2,
+        size: f32,
+        snarl_style: &SnarlStyle,
+        style: &Style,
+        painter: &Painter,
+        scale: f32,
+        _: &Snarl<()>,
+    ) -> Color32 {
+        self.input_pos.insert(pin.id, pos);
+        pin_info.draw(pos, size, snarl_style, style, painter, scale)
+    }
+
+    fn draw_output_pin(
+        &mut self,
+        pin: &OutPin,
+        pin_info: &PinInfo,
+        pos: Pos2,
+        size: f32,
+        snarl_style: &SnarlStyle,
+        style: &Style,
+        painter: &Painter,
+        scale: f32,
+        _: &Snarl<()>,
+    ) -> Color32 {
+        self.output_pos.insert(pin.id, pos);
+        pin_info.draw(pos, size, snarl_style, style, painter, scale)
+    }
+}
+
+fn run_frame<V>(
+    cx: &Context,
+    snarl: &mut Snarl<()>,
+    viewer: &mut V,
+    style: &SnarlStyle,
+    events: Vec<Event>,
+) -> FullOutput
+where
+    V: SnarlViewer<()>,
+{
+    run_frame_with_modifiers(cx, snarl, viewer, style, events, Modifiers::NONE)
+}
+
+fn run_frame_with_modifiers<V>(
+    cx: &Context,
+    snarl: &mut Snarl<()>,
+    viewer: &mut V,
+    style: &SnarlStyle,
+    events: Vec<Event>,
+    modifiers: Modifiers,
+) -> FullOutput
+where
+    V: SnarlViewer<()>,
+{
+    let input = RawInput {
+        screen_rect: Some(Rect::from_min_size(Pos2::ZERO, vec2(800.0, 600.0))),
+        events,
+        modifiers,
+        ..RawInput::default()
+    };
+
+    cx.run(input, |cx| {
+        egui::CentralPanel::default().show(cx, |ui| {
+            snarl.show(viewer, style, "snarl", ui);
+        });
+    })
+}
+
+/// Runs frames until the graph is laid out and returns output of the last one.
+fn settle<V>(cx: &Context, snarl: &mut Snarl<()>, viewer: &mut V, style: &SnarlStyle) -> FullOutput
+where
+    V: SnarlViewer<()>,
+{
+    let mut output = run_frame(cx, snarl, viewer, style, Vec::new());
+    for _ in 0..2 {
+        output = run_frame(cx, snarl, viewer, style, Vec::new());
+    }
+    output
+}
+
+/// Returns all shapes of the output, flattening nested ones.
+fn flatten_shapes(output: &FullOutput) -> Vec<Shape> {
+    fn flatten(shape: &Shape, shapes: &mut Vec<Shape>) {
+        match shape {
+            Shape::Vec(nested) => {
+                for shape in nested {
+                    flatten(shape, shapes);
+                }
+            }
+            _ => shapes.push(shape.clone()),
+        }
+    }
+
+    let mut shapes = Vec::new();
+    for clipped in &output.shapes {
+        flatten(&clipped.shape, &mut shapes);
+    }
+    shapes
+}
+
+/// Returns points of all paths in the output.
+fn collect_paths(output: &FullOutput) -> Vec<Vec<Pos2>> {
+    flatten_shapes(output)
+        .into_iter()
+        .filter_map(|shape| match shape {
+            Shape::Path(path) => Some(path.points),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns points of closed paths and centers and radii of circles in the output.
+fn collect_markers(output: &FullOutput) -> (Vec<Vec<Pos2>>, Vec<(Pos2, f32)>) {
+    let mut polygons = Vec::new();
+    let mut dots = Vec::new();
+    for shape in flatten_shapes(output) {
+        match shape {
+            Shape::Path(path) if path.closed => polygons.push(path.points),
+            Shape::Circle(circle) => dots.push((circle.center, circle.radius)),
+            _ => {}
+        }
+    }
+    (polygons, dots)
+}
+
+/// Finds the path going from `from` to `to`.
+fn find_path(output: &FullOutput, from: Pos2, to: Pos2) -> Option<egui::epaint::PathShape> {
+    flatten_shapes(output)
+        .into_iter()
+        .find_map(|shape| match shape {
+            Shape::Path(path)
+                if path.points.first() == Some(&from) && path.points.last() == Some(&to) =>
+            {
+                Some(path)
+            }
+            _ => None,
+        })
+}
+
+/// Finds color of the path going from `from` to `to`.
+fn find_path_color(output: &FullOutput, from: Pos2, to: Pos2) -> Option<Color32> {
+    match find_path(output, from, to)?.stroke.color {
+        egui::epaint::ColorMode::Solid(color) => Some(color),
+        egui::epaint::ColorMode::UV(_) => None,
+    }
+}
+
+/// Finds width of the path going from `from` to `to`.
+fn find_path_width(output: &FullOutput, from: Pos2, to: Pos2) -> Option<f32> {
+    Some(find_path(output, from, to)?.stroke.width)
+}
+
+/// Returns rects filled with `color` in the output.
+fn collect_rects(output: &FullOutput, color: Color32) -> Vec<Rect> {
+    flatten_shapes(output)
+        .into_iter()
+        .filter_map(|shape| match shape {
+            Shape::Rect(rect) if rect.fill == color => Some(rect.rect),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns bounds of meshes textured with `texture` in the output.
+fn collect_texture_rects(output: &FullOutput, texture: TextureId) -> Vec<Rect> {
+    flatten_shapes(output)
+        .into_iter()
+        .filter_map(|shape| match shape {
+            Shape::Mesh(mesh) if mesh.texture_id == texture => Some(mesh.calc_bounds()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Finds bounds of the `text` in the output.
+fn find_text(output: &FullOutput, text: &str) -> Option<Rect> {
+    flatten_shapes(output)
+        .into_iter()
+        .find_map(|shape| match shape {
+            Shape::Text(shape) if shape.galley.text() == text => Some(shape.visual_bounding_rect()),
+            _ => None,
+        })
+}
+
+#[test]
+fn pin_hover_growth_does_not_flicker() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    snarl.insert_node(pos2(0.0, 0.0), ());
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle::new();
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+    let label = viewer.input_label.expect("Input must be shown");
+
+    for _ in 0..10 {
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![Event::PointerMoved(label.center())],
+        );
+    }
+
+    // Frames after the first hover must keep the pin hovered.
+    let hovered = &viewer.input_hovered[viewer.input_hovered.len() - 9..];
+    assert!(hovered.iter().all(|hovered| *hovered), "{hovered:?}");
+}
+
+#[test]
+fn mirrored_style_swaps_pin_sides() {
+    let pin_positions = |mirrored| {
+        let cx = Context::default();
+        let mut snarl = Snarl::new();
+        let node = snarl.insert_node(pos2(0.0, 0.0), ());
+
+        let mut viewer = TestViewer::default();
+        let style = SnarlStyle {
+            mirrored: Some(mirrored),
+            ..SnarlStyle::new()
+        };
+
+        for _ in 0..2 {
+            run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+        }
+
+        (
+            viewer.input_pos[&InPinId { node, input: 0 }],
+            viewer.output_pos[&OutPinId { node, output: 0 }],
+        )
+    };
+
+    let (input, output) = pin_positions(false);
+    assert!(input.x < output.x, "{input:?} {output:?}");
+
+    let (input, output) = pin_positions(true);
+    assert!(input.x > output.x, "{input:?} {output:?}");
+}
+
+/// Clicks at `pos` with `modifiers` held.
+fn click<V>(
+    cx: &Context,
+    snarl: &mut Snarl<()>,
+    viewer: &mut V,
+    style: &SnarlStyle,
+    pos: Pos2,
+    modifiers: Modifiers,
+) where
+    V: SnarlViewer<()>,
+{
+    let button = |pressed| Event::PointerButton {
+        pos,
+        button: PointerButton::Primary,
+        pressed,
+        modifiers,
+    };
+
+    run_frame_with_modifiers(
+        cx,
+        snarl,
+        viewer,
+        style,
+        vec![Event::PointerMoved(pos)],
+        modifiers,
+    );
+    run_frame_with_modifiers(cx, snarl, viewer, style, vec![button(true)], modifiers);
+    run_frame_with_modifiers(cx, snarl, viewer, style, vec![button(false)], modifiers);
+}
+
+#[test]
+fn alt_click_on_collapse_icon_toggles_all_nodes() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(0.0, 200.0), ());
+    let c = snarl.insert_node(pos2(0.0, 400.0), ());
+
+    let is_open = |snarl: &Snarl<()>, node| snarl.get_node_info(node).unwrap().open;
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle::new();
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+    let icon = viewer.collapse_icons[&a].center();
+
+    // Plain click toggles only clicked node.
+    click(&cx, &mut snarl, &mut viewer, &style, icon, Modifiers::NONE);
+    assert!(!is_open(&snarl, a));
+    assert!(is_open(&snarl, b));
+    assert!(is_open(&snarl, c));
+
+    // Alt-click without selection toggles all nodes to match clicked one.
+    click(&cx, &mut snarl, &mut viewer, &style, icon, Modifiers::ALT);
+    assert!(is_open(&snarl, a));
+    assert!(is_open(&snarl, b));
+    assert!(is_open(&snarl, c));
+
+    click(&cx, &mut snarl, &mut viewer, &style, icon, Modifiers::ALT);
+    assert!(!is_open(&snarl, a));
+    assert!(!is_open(&snarl, b));
+    assert!(!is_open(&snarl, c));
+
+    // Select `b` and alt-click `a`, `c` stays collapsed.
+    let title = viewer.titles[&b].center();
+    click(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        title,
+        Modifiers::SHIFT,
+    );
+
+    click(&cx, &mut snarl, &mut viewer, &style, icon, Modifiers::ALT);
+    assert!(is_open(&snarl, a));
+    assert!(is_open(&snarl, b));
+    assert!(!is_open(&snarl, c));
+}
+
+#[test]
+fn stacked_background_patterns_are_drawn() {
+    let shapes_count = |bg_patterns| {
+        let cx = Context::default();
+        let mut snarl = Snarl::new();
+        let style = SnarlStyle {
+            bg_patterns: Some(bg_patterns),
+            ..SnarlStyle::new()
+        };
+
+        let output = run_frame(
+            &cx,
+            &mut snarl,
+            &mut TestViewer::default(),
+            &style,
+            Vec::new(),
+        );
+        output.shapes.len()
+    };
+
+    let dots = BackgroundPattern::grid(vec2(10.0, 10.0), 0.0);
+    let major = BackgroundPattern::grid(vec2(100.0, 100.0), 0.0);
+
+    let none = shapes_count(vec![]);
+    let dots_only = shapes_count(vec![dots]);
+    let major_only = shapes_count(vec![major]);
+    let both = shapes_count(vec![dots, major]);
+
+    assert!(dots_only > none);
+    assert!(major_only > none);
+    assert_eq!(both - none, (dots_only - none) + (major_only - none));
+}
+
+#[test]
+fn memory_of_removed_nodes_is_released() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let first = snarl.insert_node(pos2(0.0, 0.0), ());
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle::new();
+
+    for _ in 0..2 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    }
+
+    let memory_size = cx.data(|d| d.len());
+
+    #[allow(clippy::cast_precision_loss)]
+    let nodes = (0..100)
+        .map(|i| snarl.insert_node(pos2(i as f32 * 10.0, i as f32 * 10.0), ()))
+        .collect::<Vec<_>>();
+
+    // Zoom out to see all nodes, then pan and zoom back.
+    for zoom in [0.5, 0.5, 0.5, 2.0, 2.0, 2.0] {
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![Event::PointerMoved(pos2(10.0, 10.0)), Event::Zoom(zoom)],
+        );
+    }
+
+    for node in nodes {
+        snarl.remove_node(node);
+    }
+
+    for _ in 0..2 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    }
+
+    assert!(snarl.get_node(first).is_some());
+    assert_eq!(cx.data(|d| d.len()), memory_size);
+}
+
+#[test]
+fn memory_of_removed_wire_widgets_is_released() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 0.0), ());
+
+    let mut viewer = TestViewer {
+        wire_widgets: true,
+        ..TestViewer::default()
+    };
+    let style = SnarlStyle::new();
+
+    for _ in 0..2 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    }
+
+    let memory_size = cx.data(|d| d.len());
+
+    let wires = (0..20)
+        .map(|i| {
+            let wire = (
+                OutPinId { node: a, output: i },
+                InPinId { node: b, input: i },
+            );
+            snarl.connect(wire.0, wire.1);
+            wire
+        })
+        .collect::<Vec<_>>();
+
+    for _ in 0..2 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    }
+    assert!(!viewer.wire_widget_rects.is_empty());
+
+    for (from, to) in wires {
+        snarl.disconnect(from, to);
+    }
+
+    for _ in 0..2 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    }
+
+    assert_eq!(cx.data(|d| d.len()), memory_size);
+}
+
+#[test]
+fn wire_ends_at_pin_center() {
+    for pin_placement in [
+        PinPlacement::Inside,
+        PinPlacement::Edge,
+        PinPlacement::Outside { margin: 5.0 },
+    ] {
+        let cx = Context::default();
+        let mut snarl = Snarl::new();
+        let a = snarl.insert_node(pos2(0.0, 0.0), ());
+        let b = snarl.insert_node(pos2(300.0, 100.0), ());
+
+        let from = OutPinId { node: a, output: 0 };
+        let to = InPinId { node: b, input: 0 };
+        snarl.connect(from, to);
+
+        let mut viewer = TestViewer::default();
+        let style = SnarlStyle {
+            pin_placement: Some(pin_placement),
+            pin_size: Some(10.0),
+            ..SnarlStyle::new()
+        };
+
+        let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+        let paths = collect_paths(&output);
+
+        let from_pos = viewer.output_pos[&from];
+        let to_pos = viewer.input_pos[&to];
+
+        assert!(
+            paths
+                .iter()
+                .any(|path| path.first() == Some(&from_pos) && path.last() == Some(&to_pos)),
+            "No wire from {from_pos:?} to {to_pos:?} with {pin_placement:?}",
+        );
+    }
+}
+
+#[test]
+fn sagging_wire_hangs_below_its_ends() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 0.0), ());
+
+    let from = OutPinId { node: a, output: 0 };
+    let to = InPinId { node: b, input: 0 };
+    snarl.connect(from, to);
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle {
+        wire_style: Some(WireStyle::Sagging { sag: 0.5 }),
+        ..SnarlStyle::new()
+    };
+
+    let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+    let paths = collect_paths(&output);
+
+    let from_pos = viewer.output_pos[&from];
+    let to_pos = viewer.input_pos[&to];
+    let wire = paths
+        .iter()
+        .find(|path| path.first() == Some(&from_pos) && path.last() == Some(&to_pos))
+        .expect("wire is drawn");
+
+    let lowest = wire.iter().map(|p| p.y).fold(f32::MIN, f32::max);
+    let span = (to_pos.x - from_pos.x).abs();
+    assert!(lowest > from_pos.y.max(to_pos.y) + span * 0.2);
+
+    assert_eq!(
+        pick_wire_style(
+            WireStyle::Bezier5,
+            Some(WireStyle::Sagging { sag: 0.1 }),
+            Some(WireStyle::Sagging { sag: 0.3 }),
+        ),
+        WireStyle::Sagging { sag: 0.3 }
+    );
+}
+
+#[test]
+fn dashed_wire_is_split_into_dashes() {
+    let dashes = |speed| {
+        let cx = Context::default();
+        let mut snarl = Snarl::new();
+        let a = snarl.insert_node(pos2(0.0, 0.0), ());
+        let b = snarl.insert_node(pos2(300.0, 0.0), ());
+        snarl.connect(
+            OutPinId { node: a, output: 0 },
+            InPinId { node: b, input: 0 },
+        );
+
+        let mut viewer = TestViewer::default();
+        let style = SnarlStyle {
+            wire_style: Some(WireStyle::Dashed {
+                length: 4.0,
+                gap: 4.0,
+                speed,
+            }),
+            ..SnarlStyle::new()
+        };
+
+        let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+        let from_pos = viewer.output_pos[&OutPinId { node: a, output: 0 }];
+        let to_pos = viewer.input_pos[&InPinId { node: b, input: 0 }];
+        let wire_bb = Rect::from_two_pos(from_pos, to_pos).expand2(vec2(0.0, 50.0));
+
+        let mut paths = collect_paths(&output);
+        paths.retain(|path| path.iter().all(|p| wire_bb.contains(*p)));
+
+        let repaint = output.viewport_output[&egui::ViewportId::ROOT].repaint_delay;
+        (from_pos, paths, repaint)
+    };
+
+    let (from_pos, paths, repaint) = dashes(0.0);
+    assert_ne!(repaint, std::time::Duration::ZERO);
+
+    // Static dashes start right at the output pin.
+    assert!(paths.iter().any(|path| path.first() == Some(&from_pos)));
+    assert!(paths.len() > 10);
+    for path in &paths {
+        let length: f32 = path.windows(2).map(|w| (w[1] - w[0]).length()).sum();
+        assert!(length <= 4.1, "Dash is {length} long");
+    }
+
+    // Marching dashes keep the frames coming.
+    let (_, _, repaint) = dashes(50.0);
+    assert_eq!(repaint, std::time::Duration::ZERO);
+
+    assert_eq!(
+        pick_wire_style(
+            WireStyle::Bezier5,
+            Some(WireStyle::Bezier3),
+            Some(WireStyle::Dashed {
+                length: 5.0,
+                gap: 5.0,
+                speed: 0.0,
+            }),
+        ),
+        WireStyle::Dashed {
+            length: 5.0,
+            gap: 5.0,
+            speed: 0.0,
+        }
+    );
+    assert_eq!(
+        pick_wire_style(
+            WireStyle::Bezier5,
+            Some(WireStyle::Dashed {
+                length: 5.0,
+                gap: 5.0,
+                speed: 0.0,
+            }),
+            Some(WireStyle::Sagging { sag: 0.3 }),
+        ),
+        WireStyle::Sagging { sag: 0.3 }
+    );
+}
+
+#[test]
+fn wire_end_marker_follows_wire_tangent() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 150.0), ());
+    let to = InPinId { node: b, input: 0 };
+    snarl.connect(OutPinId { node: a, output: 0 }, to);
+
+    let mut viewer = TestViewer::default();
+    let mut shapes = |wire_style, wire_end_marker| {
+        let style = SnarlStyle {
+            pin_size: Some(10.0),
+            wire_style: Some(wire_style),
+            wire_end_marker,
+            ..SnarlStyle::new()
+        };
+
+        // Same context keeps nodes in the same order.
+        let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+        let shapes = flatten_shapes(&output);
+        (shapes, viewer.input_pos[&to])
+    };
+
+    // Bezier3 and sagging wires don't end horizontally, so they are not listed.
+    for (wire_style, direction) in [
+        (WireStyle::Bezier5, vec2(1.0, 0.0)),
+        (
+            WireStyle::Dashed {
+                length: 7.0,
+                gap: 5.0,
+                speed: 0.0,
+            },
+            vec2(1.0, 0.0),
+        ),
+        (WireStyle::Dotted { spacing: 7.0 }, vec2(1.0, 0.0)),
+        (
+            WireStyle::AxisAligned {
+                corner_radius: 10.0,
+            },
+            vec2(1.0, 0.0),
+        ),
+        (WireStyle::Bezier5Vertical, vec2(0.0, 1.0)),
+        (
+            WireStyle::AxisAlignedVertical {
+                corner_radius: 10.0,
+            },
+            vec2(0.0, 1.0),
+        ),
+    ] {
+        // Disabled marker doesn't change anything.
+        let (plain, _) = shapes(wire_style, None);
+        let (none, _) = shapes(wire_style, Some(WireEndMarker::None));
+        assert_eq!(plain, none, "{wire_style:?}");
+
+        // Arrow is the only shape added.
+        let (with_arrow, to_pos) = shapes(wire_style, Some(WireEndMarker::Arrow { size: 8.0 }));
+        let mut same = 0;
+        let mut added = Vec::new();
+        for shape in with_arrow {
+            if plain.get(same) == Some(&shape) {
+                same += 1;
+            } else {
+                added.push(shape);
+            }
+        }
+        assert_eq!(same, plain.len(), "{wire_style:?}");
+
+        let [Shape::Path(arrow)] = &added[..] else {
+            panic!("No single arrow with {wire_style:?}: {added:?}");
+        };
+        assert!(arrow.closed);
+
+        // Tip touches the pin and base is perpendicular to the wire's end.
+        // Rounded corners are sampled, so last segment is only close to the tangent.
+        let arrow = &arrow.points;
+        assert!(
+            (arrow[0] - (to_pos - direction * 5.0)).length() < 1e-2,
+            "{arrow:?} {to_pos:?} {wire_style:?}"
+        );
+        assert!(
+            (arrow[1] - arrow[2]).dot(direction).abs() < 1e-2,
+            "{arrow:?} {wire_style:?}"
+        );
+    }
+}
+
+#[test]
+fn flowing_wires_march_and_keep_repainting() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 0.0), ());
+    let from = OutPinId { node: a, output: 0 };
+    let to = InPinId { node: b, input: 0 };
+    snarl.connect(from, to);
+
+    let mut flow = |wire_flow, input_wire_flow| {
+        let mut viewer = TestViewer {
+            input_wire_flow,
+            ..TestViewer::default()
+        };
+        let style = SnarlStyle {
+            wire_flow,
+            wire_width: Some(2.0),
+            ..SnarlStyle::new()
+        };
+
+        let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+        let wire_bb = Rect::from_two_pos(viewer.output_pos[&from], viewer.input_pos[&to])
+            .expand2(vec2(0.0, 50.0));
+
+        let mut paths = collect_paths(&output);
+        paths.retain(|path| path.len() > 1 && path.iter().all(|p| wire_bb.contains(*p)));
+
+        let repaint = output.viewport_output[&egui::ViewportId::ROOT].repaint_delay;
+        (paths.len(), repaint)
+    };
+
+    // No flow, solid wire and no repaint requests.
+    let (paths, repaint) = flow(None, None);
+    assert_eq!(paths, 1);
+    assert_ne!(repaint, std::time::Duration::ZERO);
+
+    let (paths, repaint) = flow(Some(30.0), None);
+    assert!(paths > 10, "{paths} dashes");
+    assert_eq!(repaint, std::time::Duration::ZERO);
+
+    // Pin stops the flow of its wires.
+    let (paths, repaint) = flow(Some(30.0), Some(0.0));
+    assert_eq!(paths, 1);
+    assert_ne!(repaint, std::time::Duration::ZERO);
+
+    // Or makes only its wires flow.
+    let (paths, repaint) = flow(None, Some(30.0));
+    assert!(paths > 10, "{paths} dashes");
+    assert_eq!(repaint, std::time::Duration::ZERO);
+
+    // Returns whether wire shapes change between frames and repaint is requested.
+    let mut moving = |wire_style, wire_flow, input_wire_flow| {
+        let mut viewer = TestViewer {
+            input_wire_flow,
+            ..TestViewer::default()
+        };
+        let style = SnarlStyle {
+            wire_style: Some(wire_style),
+            wire_flow,
+            wire_width: Some(2.0),
+            ..SnarlStyle::new()
+        };
+
+        settle(&cx, &mut snarl, &mut viewer, &style);
+
+        let wire_bb = Rect::from_two_pos(viewer.output_pos[&from], viewer.input_pos[&to])
+            .expand2(vec2(0.0, 50.0));
+        let wire = |output: &FullOutput| {
+            let mut paths = collect_paths(output);
+            paths.retain(|path| path.iter().all(|p| wire_bb.contains(*p)));
+
+            // Pins are circles too, but larger.
+            let (_, mut dots) = collect_markers(output);
+            dots.retain(|&(center, radius)| wire_bb.contains(center) && radius == 2.0);
+
+            (paths, dots)
+        };
+
+        let first = run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+        let second = run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+        let repaint = second.viewport_output[&egui::ViewportId::ROOT].repaint_delay;
+        (
+            wire(&first) != wire(&second),
+            repaint == std::time::Duration::ZERO,
+        )
+    };
+
+    let dashed = |speed| WireStyle::Dashed {
+        length: 10.0,
+        gap: 10.0,
+        speed,
+    };
+    let dotted = WireStyle::Dotted { spacing: 10.0 };
+
+    assert_eq!(moving(dashed(0.0), None, None), (false, false));
+    assert_eq!(moving(dashed(30.0), None, None), (true, true));
+
+    // Flow replaces speed of dashed wires.
+    assert_eq!(moving(dashed(0.0), Some(30.0), None), (true, true));
+    assert_eq!(moving(dashed(30.0), None, Some(0.0)), (false, false));
+    assert_eq!(moving(dashed(30.0), Some(30.0), Some(0.0)), (false, false));
+
+    // And moves dots of dotted wires.
+    assert_eq!(moving(dotted, None, None), (false, false));
+    assert_eq!(moving(dotted, Some(30.0), None), (true, true));
+    assert_eq!(moving(dotted, Some(30.0), Some(0.0)), (false, false));
+}
+
+#[test]
+fn gradient_wire_fades_from_output_to_input_color() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 0.0), ());
+    snarl.connect(
+        OutPinId { node: a, output: 0 },
+        InPinId { node: b, input: 0 },
+    );
+
+    let mut viewer = TestViewer {
+        colored_pins: true,
+        ..TestViewer::default()
+    };
+
+    let mut meshes = |wire_color_mode, flat_wires| {
+        viewer.flat_wires = flat_wires;
+        let style = SnarlStyle {
+            wire_color_mode,
+            ..SnarlStyle::new()
+        };
+
+        let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+        let shapes = flatten_shapes(&output);
+
+        shapes
+            .into_iter()
+            .filter_map(|shape| match shape {
+                Shape::Mesh(mesh) if !mesh.vertices.is_empty() => Some(mesh),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Mixed color wire is a plain path.
+    assert!(meshes(None, false).is_empty());
+    assert!(meshes(Some(WireColorMode::Mix), false).is_empty());
+
+    // Viewer may opt out of the gradient without changing the color.
+    assert!(meshes(Some(WireColorMode::Gradient), true).is_empty());
+
+    let gradient = meshes(Some(WireColorMode::Gradient), false);
+    let [mesh] = &gradient[..] else {
+        panic!("Expected single wire mesh: {gradient:?}");
+    };
+
+    let colors = mesh
+        .vertices
+        .iter()
+        .map(|vertex| vertex.color)
+        .filter(|color| *color != Color32::TRANSPARENT)
+        .collect::<Vec<_>>();
+
+    assert_eq!(colors.first(), Some(&Color32::RED));
+    assert_eq!(colors.last(), Some(&Color32::BLUE));
+    assert!(
+        colors
+            .windows(2)
+            .all(|pair| pair[0].r() >= pair[1].r() && pair[0].b() <= pair[1].b()),
+        "{colors:?}"
+    );
+}
+
+#[test]
+fn wire_without_gradient_is_drawn_unchanged() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 100.0), ());
+    let from = OutPinId { node: a, output: 0 };
+    let to = InPinId { node: b, input: 0 };
+    snarl.connect(from, to);
+
+    // Returns the wire shape and whether any mesh was drawn.
+    let mut wire = |wire_color_mode, colored_pins| {
+        let mut viewer = TestViewer {
+            colored_pins,
+            ..TestViewer::default()
+        };
+        let style = SnarlStyle {
+            wire_color_mode,
+            ..SnarlStyle::new()
+        };
+
+        let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+        let shapes = flatten_shapes(&output);
+
+        let (from_pos, to_pos) = (viewer.output_pos[&from], viewer.input_pos[&to]);
+        let has_mesh = shapes.iter().any(|shape| matches!(shape, Shape::Mesh(_)));
+        let wire = shapes.into_iter().find(|shape| match shape {
+            Shape::Path(path) => {
+                path.points.first() == Some(&from_pos) && path.points.last() == Some(&to_pos)
+            }
+            _ => false,
+        });
+        (wire.expect("Wire must be drawn as a path"), has_mesh)
+    };
+
+    for colored_pins in [false, true] {
+        let (plain, has_mesh) = wire(None, colored_pins);
+        assert!(!has_mesh);
+        assert_eq!((plain, false), wire(Some(WireColorMode::Mix), colored_pins));
+    }
+
+    // Gradient between equal colors is drawn as a flat path.
+    assert_eq!(
+        wire(None, false),
+        wire(Some(WireColorMode::Gradient), false)
+    );
+}
+
+#[test]
+fn degenerate_dashes_are_bounded() {
+    let dashes = |length, gap| {
+        let cx = Context::default();
+        let mut snarl = Snarl::new();
+        let a = snarl.insert_node(pos2(0.0, 0.0), ());
+        let b = snarl.insert_node(pos2(300.0, 0.0), ());
+        let from = OutPinId { node: a, output: 0 };
+        let to = InPinId { node: b, input: 0 };
+        snarl.connect(from, to);
+
+        let mut viewer = TestViewer::default();
+        let style = SnarlStyle {
+            wire_style: Some(WireStyle::Dashed {
+                length,
+                gap,
+                speed: 1.0,
+            }),
+            ..SnarlStyle::new()
+        };
+
+        let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+        let wire_bb =
+            Rect::from_two_pos(viewer.output_pos[&from], viewer.input_pos[&to]).expand(1.0);
+        let mut paths = collect_paths(&output);
+        paths.retain(|path| path.iter().all(|p| wire_bb.contains(*p)));
+        paths.len()
+    };
+
+    // Dashes and gaps are at least half a point long, so a short wire has a bounded number of them.
+    for (length, gap) in [
+        (1e-9, 1e-9),
+        (1e-9, 4.0),
+        (4.0, 1e-9),
+        (f32::MIN_POSITIVE, 0.5),
+    ] {
+        let count = dashes(length, gap);
+        assert!(count > 0, "{length} / {gap} draws nothing");
+        assert!(count < 400, "{length} / {gap} draws {count} dashes");
+    }
+}
+
+#[test]
+fn dotted_wire_is_drawn_as_evenly_spaced_dots() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 0.0), ());
+    let from = OutPinId { node: a, output: 0 };
+    let to = InPinId { node: b, input: 0 };
+    snarl.connect(from, to);
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle {
+        wire_style: Some(WireStyle::Dotted { spacing: 6.0 }),
+        wire_width: Some(2.0),
+        ..SnarlStyle::new()
+    };
+
+    let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+    let (_, mut dots) = collect_markers(&output);
+
+    let from_pos = viewer.output_pos[&from];
+    let to_pos = viewer.input_pos[&to];
+    let wire_bb = Rect::from_two_pos(from_pos, to_pos).expand(1.0);
+    // Pins are circles too, but larger.
+    dots.retain(|&(center, radius)| wire_bb.contains(center) && radius == 2.0);
+
+    // Pins and wire are on the same line, so the wire is straight.
+    assert_eq!(dots.first().map(|(center, _)| *center), Some(from_pos));
+    assert!(dots.len() > 10);
+    for pair in dots.windows(2) {
+        let distance = (pair[1].0 - pair[0].0).length();
+        assert!((distance - 6.0).abs() < 0.1, "Dots are {distance} apart");
+    }
+
+    // Dots are never closer than their diameter.
+    let style = SnarlStyle {
+        wire_style: Some(WireStyle::Dotted { spacing: 1e-9 }),
+        ..style
+    };
+    let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+    let (_, mut dots) = collect_markers(&output);
+    dots.retain(|&(center, radius)| wire_bb.contains(center) && radius == 2.0);
+
+    assert!(dots.len() > 10);
+    for pair in dots.windows(2) {
+        let distance = (pair[1].0 - pair[0].0).length();
+        assert!((distance - 4.0).abs() < 0.1, "Dots are {distance} apart");
+    }
+
+    assert_eq!(
+        pick_wire_style(
+            WireStyle::Bezier5,
+            Some(WireStyle::Dotted { spacing: 3.0 }),
+            Some(WireStyle::Bezier3),
+        ),
+        WireStyle::Dotted { spacing: 3.0 }
+    );
+    assert_eq!(
+        pick_wire_style(
+            WireStyle::Bezier5,
+            Some(WireStyle::Dotted { spacing: 3.0 }),
+            Some(WireStyle::Dashed {
+                length: 5.0,
+                gap: 5.0,
+                speed: 0.0,
+            }),
+        ),
+        WireStyle::Dashed {
+            length: 5.0,
+            gap: 5.0,
+            speed: 0.0,
+        }
+    );
+}
+
+#[test]
+fn wire_end_marker_touches_input_pin() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 0.0), ());
+    let c = snarl.insert_node(pos2(300.0, 200.0), ());
+
+    let from = OutPinId { node: a, output: 0 };
+    let arrow_to = InPinId { node: b, input: 0 };
+    let dot_to = InPinId { node: c, input: 0 };
+    snarl.connect(from, arrow_to);
+    snarl.connect(from, dot_to);
+
+    let mut viewer = TestViewer {
+        dot_inputs: vec![dot_to],
+        ..TestViewer::default()
+    };
+    let style = SnarlStyle {
+        pin_size: Some(10.0),
+        wire_end_marker: Some(WireEndMarker::Arrow { size: 8.0 }),
+        ..SnarlStyle::new()
+    };
+
+    let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+    let (polygons, dots) = collect_markers(&output);
+
+    let near = |a: Pos2, b: Pos2| (a - b).length() < 0.5;
+
+    // Arrow points into the pin, its tip touches the pin's edge.
+    let arrow_pos = viewer.input_pos[&arrow_to];
+    let arrow = polygons
+        .iter()
+        .find(|points| points.len() == 3 && near(points[0], arrow_pos - vec2(5.0, 0.0)))
+        .expect("arrow is drawn");
+    assert!(
+        near(arrow[1], arrow_pos - vec2(13.0, -4.0)) || near(arrow[1], arrow_pos - vec2(13.0, 4.0))
+    );
+
+    // Pin override wins over the style.
+    let dot_pos = viewer.input_pos[&dot_to];
+    assert!(dots
+        .iter()
+        .any(|&(center, radius)| near(center, dot_pos - vec2(8.0, 0.0)) && radius == 3.0));
+    assert!(!polygons
+        .iter()
+        .any(|points| points.len() == 3 && near(points[0], dot_pos - vec2(5.0, 0.0))));
+}
+
+#[test]
+fn vertical_wires_leave_and_enter_pins_vertically() {
+    for wire_style in [
+        WireStyle::Bezier5Vertical,
+        WireStyle::AxisAlignedVertical {
+            corner_radius: 10.0,
+        },
+    ] {
+        let cx = Context::default();
+        let mut snarl = Snarl::new();
+        let a = snarl.insert_node(pos2(0.0, 0.0), ());
+        let b = snarl.insert_node(pos2(100.0, 300.0), ());
+
+        let from = OutPinId { node: a, output: 0 };
+        let to = InPinId { node: b, input: 0 };
+        snarl.connect(from, to);
+
+        let mut viewer = TestViewer::default();
+        let style = SnarlStyle {
+            wire_style: Some(wire_style),
+            ..SnarlStyle::new()
+        };
+
+        let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+        let paths = collect_paths(&output);
+
+        let from_pos = viewer.output_pos[&from];
+        let to_pos = viewer.input_pos[&to];
+        let wire = paths
+            .iter()
+            .find(|path| path.first() == Some(&from_pos) && path.last() == Some(&to_pos))
+            .unwrap_or_else(|| panic!("No wire with {wire_style:?}"));
+
+        // Wire goes down from the output and comes from above into the input.
+        // Straight parts of axis-aligned wire lean slightly into the following turn.
+        let leave = wire[1] - wire[0];
+        let enter = wire[wire.len() - 1] - wire[wire.len() - 2];
+        assert!(
+            leave.y > 0.0 && leave.x.abs() < leave.y * 0.05,
+            "{leave:?} {wire_style:?}"
+        );
+        assert!(
+            enter.y > 0.0 && enter.x.abs() < enter.y * 0.05,
+            "{enter:?} {wire_style:?}"
+        );
+
+        // Wire is hit right below the output, not beside it.
+        let hit = |pos| {
+            super::wire::hit_wire(
+                pos,
+                30.0,
+                false,
+                true,
+                from_pos,
+                to_pos,
+                PinSide::Right,
+                PinSide::Left,
+                2.0,
+                wire_style,
+            )
+        };
+        assert!(hit(from_pos + vec2(0.0, 5.0)), "{wire_style:?}");
+        assert!(!hit(from_pos - vec2(20.0, 0.0)), "{wire_style:?}");
+    }
+
+    assert_eq!(
+        pick_wire_style(
+            WireStyle::Bezier5,
+            Some(WireStyle::AxisAligned {
+                corner_radius: 10.0
+            }),
+            Some(WireStyle::Bezier5Vertical),
+        ),
+        WireStyle::Bezier5Vertical
+    );
+}
+
+#[test]
+fn ghosted_nodes_are_translucent_and_not_interactive() {
+    let wire_color_and_selection = |opacity| {
+        let cx = Context::default();
+        let mut snarl = Snarl::new();
+        let a = snarl.insert_node(pos2(0.0, 0.0), ());
+        let b = snarl.insert_node(pos2(300.0, 0.0), ());
+
+        let from = OutPinId { node: a, output: 0 };
+        let to = InPinId { node: b, input: 0 };
+        snarl.connect(from, to);
+
+        let mut viewer = TestViewer {
+            opacity,
+            ..TestViewer::default()
+        };
+        let style = SnarlStyle {
+            min_interactive_opacity: Some(0.5),
+            ..SnarlStyle::new()
+        };
+
+        let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+        let from_pos = viewer.output_pos[&from];
+        let to_pos = viewer.input_pos[&to];
+        let color = find_path_color(&output, from_pos, to_pos).expect("wire is drawn");
+
+        let mut ui_id = Id::NULL;
+        let _ = cx.run(RawInput::default(), |cx| {
+            egui::CentralPanel::default().show(cx, |ui| ui_id = ui.id());
+        });
+
+        let title = viewer.titles[&a].center();
+        click(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            title,
+            Modifiers::SHIFT,
+        );
+        let selected = Snarl::<()>::get_selected_nodes_at("snarl", ui_id, &cx);
+
+        (color, selected)
+    };
+
+    let (opaque, selected) = wire_color_and_selection(None);
+    assert_eq!(selected.len(), 1);
+
+    // Above the threshold node is translucent but still interactive.
+    let (translucent, selected) = wire_color_and_selection(Some(0.6));
+    assert_eq!(selected.len(), 1);
+    assert!(
+        (f32::from(translucent.a()) - f32::from(opaque.a()) * 0.6).abs() <= 1.0,
+        "{opaque:?} {translucent:?}"
+    );
+
+    let (ghosted, selected) = wire_color_and_selection(Some(0.3));
+    assert!(selected.is_empty());
+    assert!(
+        (f32::from(ghosted.a()) - f32::from(opaque.a()) * 0.3).abs() <= 1.0,
+        "{opaque:?} {ghosted:?}"
+    );
+}
+
+#[test]
+fn invisible_pin_is_not_drawn_nor_wired() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 100.0), ());
+
+    let from = OutPinId { node: a, output: 0 };
+    let to = InPinId { node: b, input: 0 };
+    snarl.connect(from, to);
+
+    let mut viewer = TestViewer {
+        invisible_pins: vec![AnyPin::In(to)],
+        ..TestViewer::default()
+    };
+    let style = SnarlStyle::new();
+
+    let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+    assert!(!viewer.input_pos.contains_key(&to));
+    assert!(viewer
+        .input_pos
+        .contains_key(&InPinId { node: a, input: 0 }));
+    assert!(viewer.output_pos.contains_key(&from));
+
+    // Row content is still shown.
+    let label = viewer.input_label.unwrap();
+    assert!(label.height() > 0.0);
+
+    let paths = collect_paths(&output);
+    let from_pos = viewer.output_pos[&from];
+    assert!(paths.iter().all(|path| path.first() != Some(&from_pos)));
+}
+
+#[test]
+fn wire_ends_at_clipped_pin_center() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 100.0), ());
+
+    let from = OutPinId { node: b, output: 0 };
+    let to = InPinId { node: a, input: 0 };
+    snarl.connect(from, to);
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle::new();
+
+    for _ in 0..3 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    }
+
+    // Pin position relative to the title when node is fully visible.
+    let pin_offset = viewer.input_pos[&to] - viewer.titles[&a].min;
+
+    // Move the node half outside of the viewport's left edge.
+    let shift = viewer.titles[&a].min.x + 30.0;
+    snarl.get_node_info_mut(a).unwrap().pos.x -= shift;
+
+    let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+    let to_pos = viewer.input_pos[&to];
+    assert!(to_pos.x < 0.0, "{to_pos:?}");
+    assert!(
+        (to_pos - (viewer.titles[&a].min + pin_offset)).length() < 1e-3,
+        "{to_pos:?}"
+    );
+
+    let paths = collect_paths(&output);
+
+    let from_pos = viewer.output_pos[&from];
+    assert!(
+        paths
+            .iter()
+            .any(|path| path.first() == Some(&from_pos) && path.last() == Some(&to_pos)),
+        "No wire from {from_pos:?} to {to_pos:?}",
+    );
+}
+
+#[test]
+fn collapsed_node_measures_to_header_height() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let node = snarl.insert_node(pos2(0.0, 0.0), ());
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle::new();
+
+    let node_rect = |snarl: &Snarl<()>| snarl.get_node_rect_at(node, "snarl", Id::NULL, &cx);
+    assert_eq!(node_rect(&snarl), None);
+
+    let mut id = None;
+    for _ in 0..2 {
+        let _ = cx.run(RawInput::default(), |cx| {
+            egui::CentralPanel::default().show(cx, |ui| {
+                id = Some(ui.id());
+                snarl.show(&mut viewer, &style, "snarl", ui);
+            });
+        });
+    }
+
+    let node_rect = |snarl: &Snarl<()>| snarl.get_node_rect_at(node, "snarl", id.unwrap(), &cx);
+    let header = viewer.headers[&node];
+
+    let open_rect = node_rect(&snarl).unwrap();
+    assert!(open_rect.height() > header.height());
+
+    // Collapsed node is measured without running a frame.
+    snarl.open_all(false);
+
+    let collapsed_rect = node_rect(&snarl).unwrap();
+    assert_eq!(collapsed_rect.min, open_rect.min);
+    assert_eq!(collapsed_rect.width(), open_rect.width());
+    assert_eq!(collapsed_rect.height(), header.height());
+}
+
+/// Viewer with graph menu that records where it is shown.
+#[derive(Default)]
+struct MenuViewer {
+    /// Graph positions of the graph menu in each frame it was shown.
+    menu_pos: Vec<Pos2>,
+
+    /// Number of viewport changes.
+    viewport_changes: usize,
+}
+
+impl SnarlViewer<()> for MenuViewer {
+    fn title(&mut self, (): &()) -> String {
+        "Node".to_owned()
+    }
+
+    fn inputs(&mut self, (): &()) -> usize {
+        0
+    }
+
+    fn outputs(&mut self, (): &()) -> usize {
+        0
+    }
+
+    fn show_input(&mut self, _: &InPin, _: &mut Ui, _: f32, _: &mut Snarl<()>) -> PinInfo {
+        unreachable!()
+    }
+
+    fn show_output(&mut self, _: &OutPin, _: &mut Ui, _: f32, _: &mut Snarl<()>) -> PinInfo {
+        unreachable!()
+    }
+
+    fn has_graph_menu(&mut self, _: Pos2, _: &mut Snarl<()>) -> bool {
+        true
+    }
+
+    fn show_graph_menu(&mut self, pos: Pos2, ui: &mut Ui, _: f32, _: &mut Snarl<()>) {
+        self.menu_pos.push(pos);
+        ui.label("Menu");
+    }
+
+    fn viewport_changed(
+        &mut self,
+        _: &Viewport,
+        _: &Viewport,
+        _: ViewportChangeReason,
+        _: &Snarl<()>,
+    ) {
+        self.viewport_changes += 1;
+    }
+}
+
+#[test]
+fn open_menu_blocks_zoom_and_pan() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    snarl.insert_node(pos2(0.0, 0.0), ());
+
+    let mut viewer = MenuViewer::default();
+    let style = SnarlStyle::new();
+
+    // Open graph menu with right click on empty space.
+    let menu_at = pos2(600.0, 400.0);
+    let button = |pos, pressed| Event::PointerButton {
+        pos,
+        button: PointerButton::Secondary,
+        pressed,
+        modifiers: Modifiers::NONE,
+    };
+    run_frame(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        vec![Event::PointerMoved(menu_at)],
+    );
+    run_frame(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        vec![button(menu_at, true)],
+    );
+    run_frame(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        vec![button(menu_at, false)],
+    );
+    assert!(!viewer.menu_pos.is_empty(), "Graph menu must be open");
+
+    viewer.viewport_changes = 0;
+    let shown = viewer.menu_pos.len();
+
+    // Zoom with pointer away from the menu.
+    let away = pos2(100.0, 100.0);
+    run_frame(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        vec![Event::PointerMoved(away)],
+    );
+    for _ in 0..5 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, vec![Event::Zoom(1.5)]);
+    }
+
+    assert_eq!(viewer.viewport_changes, 0);
+
+    // Menu is still open and stays at the same place in the graph.
+    // First frame of the menu is a sizing pass and is skipped.
+    assert_eq!(
+        viewer.menu_pos.len(),
+        shown + 6,
+        "Graph menu must stay open"
+    );
+    let first = viewer.menu_pos[1];
+    for pos in &viewer.menu_pos[2..] {
+        assert!((*pos - first).length() < 1e-3, "{first:?} != {pos:?}");
+    }
+}
+
+#[test]
+fn drag_threshold_keeps_node_in_place() {
+    let drag_node = |threshold, distance: f32| {
+        let cx = Context::default();
+        let mut snarl = Snarl::new();
+        let node = snarl.insert_node(pos2(0.0, 0.0), ());
+
+        let mut viewer = TestViewer::default();
+        let style = SnarlStyle {
+            drag_threshold: threshold,
+            ..SnarlStyle::new()
+        };
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+        let start = viewer.titles[&node].center();
+        let button = |pos, pressed| Event::PointerButton {
+            pos,
+            button: PointerButton::Primary,
+            pressed,
+            modifiers: Modifiers::NONE,
+        };
+
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![Event::PointerMoved(start)],
+        );
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![button(start, true)],
+        );
+        for step in 1..=5 {
+            let pos = start + vec2(distance * step as f32 / 5.0, 0.0);
+            run_frame(
+                &cx,
+                &mut snarl,
+                &mut viewer,
+                &style,
+                vec![Event::PointerMoved(pos)],
+            );
+        }
+        let end = start + vec2(distance, 0.0);
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![button(end, false)],
+        );
+
+        snarl.get_node_info(node).unwrap().pos
+    };
+
+    // Motion within threshold does not move the node.
+    assert_eq!(drag_node(Some(30.0), 20.0), pos2(0.0, 0.0));
+
+    // Same motion moves the node without threshold.
+    assert!(drag_node(None, 20.0).x > 0.0);
+
+    // Node catches up with the pointer when threshold is crossed.
+    let moved = drag_node(Some(30.0), 60.0).x;
+    let unrestricted = drag_node(None, 60.0).x;
+    assert!(moved >= unrestricted - 1e-3, "{moved} < {unrestricted}");
+}
+
+#[test]
+fn node_follows_pointer_back_through_drag_origin() {
+    let drag_node = |threshold| {
+        let cx = Context::default();
+        let mut snarl = Snarl::new();
+        let node = snarl.insert_node(pos2(0.0, 0.0), ());
+
+        let mut viewer = TestViewer::default();
+        let style = SnarlStyle {
+            drag_threshold: threshold,
+            ..SnarlStyle::new()
+        };
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+        let start = viewer.titles[&node].center();
+        let button = |pos, pressed| Event::PointerButton {
+            pos,
+            button: PointerButton::Primary,
+            pressed,
+            modifiers: Modifiers::NONE,
+        };
+
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![Event::PointerMoved(start)],
+        );
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![button(start, true)],
+        );
+
+        // Out to +50, back through the origin and then to +20.
+        for x in [10.0, 30.0, 50.0, 30.0, 5.0, 0.0, -5.0, 5.0, 20.0] {
+            run_frame(
+                &cx,
+                &mut snarl,
+                &mut viewer,
+                &style,
+                vec![Event::PointerMoved(start + vec2(x, 0.0))],
+            );
+        }
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![button(start + vec2(20.0, 0.0), false)],
+        );
+
+        snarl.get_node_info(node).unwrap().pos
+    };
+
+    let with_threshold = drag_node(Some(8.0));
+    assert!(
+        (with_threshold.x - 20.0).abs() < 1e-3,
+        "Node at {with_threshold:?}"
+    );
+
+    // Without threshold node lags only by egui's own drag detection.
+    let without_threshold = drag_node(None);
+    assert!(
+        without_threshold.x <= 20.0 + 1e-3,
+        "Node at {without_threshold:?}"
+    );
+}
+
+#[test]
+fn pin_side_moves_input_next_to_outputs() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(0.0, 200.0), ());
+
+    // Feedback wire from output of `b` to input of `a`, both on the right side.
+    let from = OutPinId { node: b, output: 0 };
+    let to = InPinId { node: a, input: 0 };
+    snarl.connect(from, to);
+
+    let mut viewer = TestViewer {
+        moved_pins: vec![AnyPin::In(to)],
+        ..TestViewer::default()
+    };
+    let style = SnarlStyle::new();
+
+    let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+    let from_pos = viewer.output_pos[&from];
+    let to_pos = viewer.input_pos[&to];
+    let output_a = viewer.output_pos[&OutPinId { node: a, output: 0 }];
+
+    // Moved input is on the same side as outputs, below them.
+    assert!(
+        (to_pos.x - output_a.x).abs() < 1.0,
+        "{to_pos:?} {output_a:?}"
+    );
+    assert!(to_pos.y > output_a.y, "{to_pos:?} {output_a:?}");
+
+    // Wire loops around the right side.
+    let paths = collect_paths(&output);
+
+    let wire = paths
+        .iter()
+        .find(|path| path.first() == Some(&from_pos) && path.last() == Some(&to_pos))
+        .expect("Wire must be drawn");
+
+    let right = wire.iter().map(|p| p.x).fold(f32::MIN, f32::max);
+    assert!(right > from_pos.x.max(to_pos.x) + 1.0, "{wire:?}");
+}
+
+#[test]
+fn unconnected_pins_skip_wired_ones() {
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(200.0, 0.0), ());
+    let c = snarl.insert_node(pos2(400.0, 0.0), ());
+    let d = snarl.insert_node(pos2(600.0, 0.0), ());
+    snarl.remove_node(c);
+
+    snarl.connect(
+        OutPinId { node: a, output: 0 },
+        InPinId { node: b, input: 0 },
+    );
+    snarl.connect(
+        OutPinId { node: a, output: 0 },
+        InPinId { node: d, input: 0 },
+    );
+
+    let mut viewer = TestViewer::default();
+
+    assert_eq!(
+        snarl.unconnected_inputs(&mut viewer),
+        [InPinId { node: a, input: 0 }]
+    );
+    assert_eq!(
+        snarl.unconnected_outputs(&mut viewer),
+        [
+            OutPinId { node: b, output: 0 },
+            OutPinId { node: d, output: 0 }
+        ]
+    );
+}
+
+#[test]
+fn raise_policy_never_keeps_draw_order() {
+    // Frame may be run in several passes, last one is shown.
+    let last_pass = |viewer: &mut TestViewer| {
+        let order = std::mem::take(&mut viewer.header_order);
+        order[order.len() - 3..].to_vec()
+    };
+
+    let click_bottom_node = |policy| {
+        let cx = Context::default();
+        let mut snarl = Snarl::new();
+        for x in [-200.0, 0.0, 200.0] {
+            snarl.insert_node(pos2(x, 0.0), ());
+        }
+
+        let mut viewer = TestViewer::default();
+        let style = SnarlStyle {
+            raise_on_interact: policy,
+            ..SnarlStyle::new()
+        };
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+        let before = last_pass(&mut viewer);
+
+        let pos = viewer.titles[&before[0]].center();
+        click(&cx, &mut snarl, &mut viewer, &style, pos, Modifiers::NONE);
+
+        viewer.header_order.clear();
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+        (before, last_pass(&mut viewer))
+    };
+
+    let (before, after) = click_bottom_node(Some(RaisePolicy::Never));
+    assert_eq!(before, after);
+
+    let (before, after) = click_bottom_node(None);
+    assert_eq!(after, [before[1], before[2], before[0]]);
+}
+
+#[test]
+fn zoom_snaps_to_nearest_step() {
+    let style = SnarlStyle {
+        zoom_steps: Some(vec![0.25, 0.5, 0.75, 1.0, 1.25, 10.0]),
+        ..SnarlStyle::new()
+    };
+
+    assert_eq!(style.snap_scale(1.0), 1.0);
+    assert_eq!(style.snap_scale(0.9), 1.0);
+    assert_eq!(style.snap_scale(0.6), 0.5);
+    assert_eq!(style.snap_scale(0.01), 0.25);
+
+    // Steps above maximum scale are ignored.
+    assert_eq!(style.snap_scale(8.0), 1.25);
+
+    // Without steps zooming is continuous.
+    assert_eq!(SnarlStyle::new().snap_scale(0.9), 0.9);
+}
+
+#[test]
+fn center_content_widens_node_between_pins() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let node = snarl.insert_node(pos2(0.0, 0.0), ());
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle::new();
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    let narrow = viewer.output_pos[&OutPinId { node, output: 0 }].x
+        - viewer.input_pos[&InPinId { node, input: 0 }].x;
+
+    viewer.center_size = Some(vec2(200.0, 8.0));
+    for _ in 0..3 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    }
+
+    let input = viewer.input_pos[&InPinId { node, input: 0 }];
+    let output = viewer.output_pos[&OutPinId { node, output: 0 }];
+    let center = viewer.centers[&node];
+
+    assert!(output.x - input.x >= narrow + 200.0, "{input:?} {output:?}");
+    assert!(
+        input.x < center.left() && center.right() < output.x,
+        "{center:?}"
+    );
+
+    // Content is vertically centered on the pin row.
+    let label = viewer.input_label.unwrap();
+    assert!(
+        (center.center().y - label.center().y).abs() < 1.0,
+        "{center:?} {label:?}"
+    );
+}
+
+#[test]
+fn dragged_node_follows_pointer_in_same_frame() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let node = snarl.insert_node(pos2(0.0, 0.0), ());
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle::new();
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+    let start = viewer.titles[&node].center();
+    run_frame(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        vec![Event::PointerMoved(start)],
+    );
+    run_frame(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        vec![Event::PointerButton {
+            pos: start,
+            button: PointerButton::Primary,
+            pressed: true,
+            modifiers: Modifiers::NONE,
+        }],
+    );
+
+    let mut pointer = start;
+    for step in 1..=5 {
+        let title = viewer.titles[&node];
+        let input = viewer.input_pos[&InPinId { node, input: 0 }];
+
+        pointer += vec2(10.0 * step as f32, 5.0);
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![Event::PointerMoved(pointer)],
+        );
+
+        // Node and its pins are drawn at the new position in the same frame.
+        let delta = viewer.titles[&node].min - title.min;
+        let pin_delta = viewer.input_pos[&InPinId { node, input: 0 }] - input;
+        assert!(
+            (delta - vec2(10.0 * step as f32, 5.0)).length() < 1e-3,
+            "step {step}: {delta:?}"
+        );
+        assert!((pin_delta - delta).length() < 1e-3, "{pin_delta:?}");
+    }
+}
+
+#[test]
+fn node_dropped_onto_wire_is_inserted() {
+    for insert_on_wires in [false, true] {
+        let cx = Context::default();
+        let mut snarl = Snarl::new();
+        let a = snarl.insert_node(pos2(0.0, 0.0), ());
+        let b = snarl.insert_node(pos2(400.0, 0.0), ());
+        let c = snarl.insert_node(pos2(150.0, 250.0), ());
+
+        let a_out = OutPinId { node: a, output: 0 };
+        let b_in = InPinId { node: b, input: 0 };
+        let c_in = InPinId { node: c, input: 0 };
+        let c_out = OutPinId { node: c, output: 0 };
+        snarl.connect(a_out, b_in);
+
+        let mut viewer = TestViewer {
+            insert_on_wires,
+            wire_hover: true,
+            ..TestViewer::default()
+        };
+        let style = SnarlStyle::new();
+
+        let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+        let wire_width = |output: &FullOutput, viewer: &TestViewer| {
+            find_path_width(output, viewer.output_pos[&a_out], viewer.input_pos[&b_in])
+        };
+        let width = wire_width(&output, &viewer).unwrap();
+
+        // Drag the node by its title onto the middle of the wire.
+        let start = viewer.titles[&c].center();
+        let target = viewer.output_pos[&a_out].lerp(viewer.input_pos[&b_in], 0.5);
+        let button = |pos, pressed| Event::PointerButton {
+            pos,
+            button: PointerButton::Primary,
+            pressed,
+            modifiers: Modifiers::NONE,
+        };
+
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![Event::PointerMoved(start)],
+        );
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![button(start, true)],
+        );
+
+        for step in 1..=5 {
+            let pos = start.lerp(target, step as f32 / 5.0);
+            run_frame(
+                &cx,
+                &mut snarl,
+                &mut viewer,
+                &style,
+                vec![Event::PointerMoved(pos)],
+            );
+        }
+
+        // Wire is highlighted a frame after the node reaches it.
+        let output = run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+        let hover_width = wire_width(&output, &viewer).unwrap();
+
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![button(target, false)],
+        );
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+        // Node is still moved as usual.
+        assert_eq!(viewer.drags.last(), Some(&vec![c]));
+
+        // Highlighted wire shows no tooltip under the dragged node.
+        assert!(viewer.wire_hovers.is_empty());
+
+        if insert_on_wires {
+            // Wire is highlighted while the node is over it.
+            assert!(hover_width > width, "{hover_width} > {width}");
+
+            assert!(!snarl.connected(a_out, b_in));
+            assert!(snarl.connected(a_out, c_in));
+            assert!(snarl.connected(c_out, b_in));
+        } else {
+            assert!((hover_width - width).abs() < 1e-3);
+
+            assert!(snarl.connected(a_out, b_in));
+            assert_eq!(snarl.wires().count(), 1);
+        }
+    }
+}
+
+#[test]
+fn selection_groups_are_saved_and_pruned() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(-200.0, 0.0), ());
+    let b = snarl.insert_node(pos2(0.0, 0.0), ());
+    let c = snarl.insert_node(pos2(200.0, 0.0), ());
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle::new();
+
+    let mut ui_id = Id::NULL;
+    let _ = cx.run(RawInput::default(), |cx| {
+        egui::CentralPanel::default().show(cx, |ui| ui_id = ui.id());
+    });
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+    for node in [a, b] {
+        let pos = viewer.titles[&node].center();
+        click(&cx, &mut snarl, &mut viewer, &style, pos, Modifiers::SHIFT);
+    }
+    assert_eq!(
+        Snarl::<()>::get_selected_nodes_at("snarl", ui_id, &cx),
+        [a, b]
+    );
+
+    Snarl::<()>::save_selection_group("ab", "snarl", ui_id, &cx);
+
+    let pos = viewer.titles[&c].center();
+    click(&cx, &mut snarl, &mut viewer, &style, pos, Modifiers::SHIFT);
+    Snarl::<()>::save_selection_group("abc", "snarl", ui_id, &cx);
+
+    assert_eq!(
+        snarl.load_selection_group("ab", "snarl", ui_id, &cx),
+        Some(vec![a, b])
+    );
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert_eq!(
+        Snarl::<()>::get_selected_nodes_at("snarl", ui_id, &cx),
+        [a, b]
+    );
+
+    // Removed nodes are pruned from loaded group.
+    snarl.remove_node(b);
+    assert_eq!(
+        snarl.load_selection_group("abc", "snarl", ui_id, &cx),
+        Some(vec![a, c])
+    );
+    assert_eq!(
+        snarl.load_selection_group("missing", "snarl", ui_id, &cx),
+        None
+    );
+
+    // Groups can be stored elsewhere and restored.
+    let groups = Snarl::<()>::get_selection_groups("snarl", ui_id, &cx);
+    assert_eq!(groups.keys().collect::<Vec<_>>(), ["ab", "abc"]);
+    assert_eq!(groups["abc"], [a, c]);
+
+    assert!(Snarl::<()>::delete_selection_group(
+        "ab", "snarl", ui_id, &cx
+    ));
+    assert!(!Snarl::<()>::delete_selection_group(
+        "ab", "snarl", ui_id, &cx
+    ));
+
+    Snarl::<()>::set_selection_groups(groups.clone(), "snarl", ui_id, &cx);
+    assert_eq!(
+        Snarl::<()>::get_selection_groups("snarl", ui_id, &cx),
+        groups
+    );
+}
+
+#[test]
+fn node_menu_receives_clicked_pin() {
+    let open_menu_at = |target: fn(&TestViewer, NodeId) -> Pos2| {
+        let cx = Context::default();
+        let mut snarl = Snarl::new();
+        let node = snarl.insert_node(pos2(0.0, 0.0), ());
+
+        let mut viewer = TestViewer::default();
+        let style = SnarlStyle::new();
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+        let pos = target(&viewer, node);
+        let button = |pressed| Event::PointerButton {
+            pos,
+            button: PointerButton::Secondary,
+            pressed,
+            modifiers: Modifiers::NONE,
+        };
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![Event::PointerMoved(pos)],
+        );
+        run_frame(&cx, &mut snarl, &mut viewer, &style, vec![button(true)]);
+        run_frame(&cx, &mut snarl, &mut viewer, &style, vec![button(false)]);
+
+        // Pointer moves to the menu.
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![Event::PointerMoved(pos + vec2(20.0, 20.0))],
+        );
+
+        assert!(!viewer.menu_pins.is_empty(), "Node menu must be open");
+        let first = viewer.menu_pins[0];
+        assert!(viewer.menu_pins.iter().all(|pin| *pin == first));
+        (node, first)
+    };
+
+    let (node, pin) = open_menu_at(|viewer, _| viewer.input_label.unwrap().center());
+    assert_eq!(pin, Some(AnyPin::In(InPinId { node, input: 0 })));
+
+    let (_, pin) = open_menu_at(|viewer, node| viewer.titles[&node].center());
+    assert_eq!(pin, None);
+}
+
+#[test]
+fn progress_strip_is_shown_under_header() {
+    let strip_rects = |open| {
+        let cx = Context::default();
+        let mut snarl = Snarl::new();
+        let node = if open {
+            snarl.insert_node(pos2(0.0, 0.0), ())
+        } else {
+            snarl.insert_node_collapsed(pos2(0.0, 0.0), ())
+        };
+
+        let mut viewer = TestViewer {
+            activity: Some(NodeActivity::Progress(0.25)),
+            ..TestViewer::default()
+        };
+        let style = SnarlStyle {
+            progress_style: Some(ProgressStyle {
+                height: 4.0,
+                fill: Color32::RED,
+                background: Color32::BLUE,
+            }),
+            ..SnarlStyle::new()
+        };
+
+        let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+        let done = collect_rects(&output, Color32::RED);
+        let rest = collect_rects(&output, Color32::BLUE);
+        (viewer.headers[&node], done, rest)
+    };
+
+    for open in [true, false] {
+        let (header, done, rest) = strip_rects(open);
+        assert_eq!(done.len(), 1, "open: {open}");
+        assert_eq!(rest.len(), 1, "open: {open}");
+
+        let (done, rest) = (done[0], rest[0]);
+        assert!((rest.height() - 4.0).abs() < 1e-3, "{rest:?}");
+        assert!(rest.top() >= header.bottom(), "{rest:?} {header:?}");
+        assert!(
+            (done.width() - rest.width() * 0.25).abs() < 1e-3,
+            "{done:?}"
+        );
+        assert!((done.left() - rest.left()).abs() < 1e-3, "{done:?}");
+    }
+}
+
+#[test]
+fn dropped_wires_are_passed_to_menu_together() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(-300.0, -100.0), ());
+    let b = snarl.insert_node(pos2(-300.0, 100.0), ());
+    let c = snarl.insert_node(pos2(0.0, 0.0), ());
+
+    let a_out = OutPinId { node: a, output: 0 };
+    let b_out = OutPinId { node: b, output: 0 };
+    let c_in = InPinId { node: c, input: 0 };
+    snarl.connect(a_out, c_in);
+    snarl.connect(b_out, c_in);
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle::new();
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+    // Pick both wires from the input pin with command modifier.
+    let start = viewer.input_pos[&c_in];
+    let button = |pos, pressed| Event::PointerButton {
+        pos,
+        button: PointerButton::Primary,
+        pressed,
+        modifiers: Modifiers::COMMAND,
+    };
+    let frames = [
+        vec![Event::PointerMoved(start)],
+        vec![button(start, true)],
+        vec![Event::PointerMoved(start + vec2(0.0, 50.0))],
+        vec![Event::PointerMoved(start + vec2(0.0, 150.0))],
+        vec![button(start + vec2(0.0, 150.0), false)],
+        Vec::new(),
+    ];
+    for events in frames {
+        run_frame_with_modifiers(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            events,
+            Modifiers::COMMAND,
+        );
+    }
+
+    // Wires are detached from the input and both outputs are passed to the menu.
+    assert!(snarl.in_pin(c_in).remotes.is_empty());
+    let mut pins = viewer
+        .dropped_pins
+        .last()
+        .expect("Menu must be shown")
+        .clone();
+    pins.sort_by_key(|pin| pin.node());
+    assert_eq!(pins, [AnyPin::Out(a_out), AnyPin::Out(b_out)]);
+}
+
+#[test]
+fn wire_is_detached_from_pin_and_dragged() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(-300.0, -200.0), ());
+    let b = snarl.insert_node(pos2(-300.0, 200.0), ());
+    let c = snarl.insert_node(pos2(0.0, -150.0), ());
+    let d = snarl.insert_node(pos2(250.0, 100.0), ());
+
+    let a_out = OutPinId { node: a, output: 0 };
+    let b_out = OutPinId { node: b, output: 0 };
+    let c_in = InPinId { node: c, input: 0 };
+    let d_in = InPinId { node: d, input: 0 };
+    snarl.connect(a_out, c_in);
+    snarl.connect(b_out, c_in);
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle {
+        detach_wire_modifiers: Some(Modifiers::ALT),
+        ..SnarlStyle::new()
+    };
+    for _ in 0..3 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    }
+
+    let button = |pos, pressed| Event::PointerButton {
+        pos,
+        button: PointerButton::Primary,
+        pressed,
+        modifiers: Modifiers::ALT,
+    };
+    // Target is looked up every frame, as pin rows grow when wires are dragged over them.
+    let drag = |snarl: &mut Snarl<()>,
+                viewer: &mut TestViewer,
+                start: Pos2,
+                end: &dyn Fn(&TestViewer) -> Pos2| {
+        run_frame_with_modifiers(
+            &cx,
+            snarl,
+            viewer,
+            &style,
+            vec![Event::PointerMoved(start)],
+            Modifiers::ALT,
+        );
+        run_frame_with_modifiers(
+            &cx,
+            snarl,
+            viewer,
+            &style,
+            vec![button(start, true)],
+            Modifiers::ALT,
+        );
+        let halfway = start.lerp(end(viewer), 0.5);
+        run_frame_with_modifiers(
+            &cx,
+            snarl,
+            viewer,
+            &style,
+            vec![Event::PointerMoved(halfway)],
+            Modifiers::ALT,
+        );
+        for _ in 0..2 {
+            let pos = end(viewer);
+            run_frame_with_modifiers(
+                &cx,
+                snarl,
+                viewer,
+                &style,
+                vec![Event::PointerMoved(pos)],
+                Modifiers::ALT,
+            );
+        }
+        let pos = end(viewer);
+        run_frame_with_modifiers(
+            &cx,
+            snarl,
+            viewer,
+            &style,
+            vec![button(pos, false)],
+            Modifiers::ALT,
+        );
+        run_frame_with_modifiers(&cx, snarl, viewer, &style, Vec::new(), Modifiers::ALT);
+    };
+
+    // Only the wire from the nearest node is detached and dropped on empty space.
+    let start = viewer.input_pos[&c_in];
+    drag(&mut snarl, &mut viewer, start, &|_| {
+        start + vec2(100.0, -50.0)
+    });
+
+    assert!(!snarl.connected(a_out, c_in));
+    assert!(snarl.connected(b_out, c_in));
+    assert_eq!(
+        viewer.dropped_pins.last().expect("Menu must be shown"),
+        &vec![AnyPin::Out(a_out)]
+    );
+
+    // Close the menu.
+    run_frame(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        vec![Event::Key {
+            key: Key::Escape,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: Modifiers::NONE,
+        }],
+    );
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+    // Detached wire is connected where it is dropped.
+    let start = viewer.input_pos[&c_in];
+    drag(&mut snarl, &mut viewer, start, &|viewer| {
+        viewer.input_pos[&d_in]
+    });
+
+    assert!(snarl.in_pin(c_in).remotes.is_empty());
+    assert!(snarl.connected(b_out, d_in));
+
+    // Wire is not dragged away if viewer keeps it connected.
+    viewer.locked_inputs.push(d_in);
+    let start = viewer.input_pos[&d_in];
+    drag(&mut snarl, &mut viewer, start, &|viewer| {
+        viewer.input_pos[&c_in]
+    });
+
+    assert!(snarl.connected(b_out, d_in));
+    assert!(snarl.in_pin(c_in).remotes.is_empty());
+}
+
+#[test]
+fn followed_node_stays_centered() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let node = snarl.insert_node(pos2(-200.0, 100.0), ());
+    snarl.insert_node(pos2(200.0, -100.0), ());
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle::new();
+
+    let mut ui_id = Id::NULL;
+    let _ = cx.run(RawInput::default(), |cx| {
+        egui::CentralPanel::default().show(cx, |ui| ui_id = ui.id());
+    });
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+    Snarl::<()>::follow_node_at(Some(node), "snarl", ui_id, &cx);
+    assert_eq!(
+        Snarl::<()>::get_followed_node_at("snarl", ui_id, &cx),
+        Some(node)
+    );
+
+    // Central panel spans the screen except for its margins.
+    let screen_center = pos2(400.0, 300.0);
+    let in_pin = InPinId { node, input: 0 };
+    let out_pin = OutPinId { node, output: 0 };
+
+    let mut title: Option<Pos2> = None;
+    for step in 0..5 {
+        snarl.get_node_info_mut(node).unwrap().pos += vec2(37.0 * step as f32, -23.0);
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+        // Pins are on opposite edges of the node, so node center is between them.
+        let center_x = (viewer.input_pos[&in_pin].x + viewer.output_pos[&out_pin].x) / 2.0;
+        assert!((center_x - screen_center.x).abs() < 1.0, "{center_x}");
+
+        // Node doesn't move on screen.
+        let rect = viewer.titles[&node];
+        if let Some(title) = title {
+            assert!((rect.min - title).length() < 1e-3, "step {step}: {rect:?}");
+        }
+        title = Some(rect.min);
+    }
+
+    // Dragging background doesn't pan away from the node.
+    let start = pos2(700.0, 500.0);
+    let frames = [
+        vec![Event::PointerMoved(start)],
+        vec![Event::PointerButton {
+            pos: start,
+            button: PointerButton::Primary,
+            pressed: true,
+            modifiers: Modifiers::NONE,
+        }],
+        vec![Event::PointerMoved(start - vec2(50.0, 50.0))],
+        vec![Event::PointerMoved(start - vec2(100.0, 100.0))],
+    ];
+    for events in frames {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, events);
+    }
+    assert!((viewer.titles[&node].min - title.unwrap()).length() < 1e-3);
+
+    // Following stops when node is removed.
+    snarl.remove_node(node);
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert_eq!(Snarl::<()>::get_followed_node_at("snarl", ui_id, &cx), None);
+}
+
+#[test]
+fn breakpoint_gutter_toggles_without_selecting() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(-200.0, 0.0), ());
+    let b = snarl.insert_node(pos2(0.0, 0.0), ());
+
+    let mut viewer = TestViewer {
+        breakpoint: Some(false),
+        ..TestViewer::default()
+    };
+    let style = SnarlStyle {
+        breakpoint_shortcut: Some(KeyboardShortcut::new(Modifiers::NONE, Key::F9)),
+        ..SnarlStyle::new()
+    };
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+    let mut ui_id = Id::NULL;
+    let _ = cx.run(RawInput::default(), |cx| {
+        egui::CentralPanel::default().show(cx, |ui| ui_id = ui.id());
+    });
+
+    // Select and raise `a`.
+    let title = viewer.titles[&a].center();
+    click(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        title,
+        Modifiers::SHIFT,
+    );
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert_eq!(viewer.header_order.last(), Some(&a));
+
+    // Gutter is the first element in the header, where collapse icon is without it.
+    let gutter = viewer.collapse_icons[&b].center();
+    let pos = snarl.get_node_info(b).unwrap().pos;
+    click(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        gutter,
+        Modifiers::NONE,
+    );
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+    assert_eq!(viewer.toggled, [b]);
+    assert_eq!(
+        viewer.header_order.last(),
+        Some(&a),
+        "gutter click raised node"
+    );
+    assert_eq!(Snarl::<()>::get_selected_nodes_at("snarl", ui_id, &cx), [a]);
+    assert!(snarl.get_node_info(b).unwrap().open);
+    assert_eq!(snarl.get_node_info(b).unwrap().pos, pos);
+
+    // Shortcut toggles selected nodes.
+    run_frame(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        vec![Event::Key {
+            key: Key::F9,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: Modifiers::NONE,
+        }],
+    );
+    assert_eq!(viewer.toggled, [b, a]);
+}
+
+#[test]
+fn forget_ui_state_resets_selection_and_viewport() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let node = snarl.insert_node(pos2(0.0, 0.0), ());
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle::new();
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    let initial = viewer.titles[&node];
+
+    let mut ui_id = Id::NULL;
+    let _ = cx.run(RawInput::default(), |cx| {
+        egui::CentralPanel::default().show(cx, |ui| ui_id = ui.id());
+    });
+
+    // Select the node and pan the viewport.
+    click(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        initial.center(),
+        Modifiers::SHIFT,
+    );
+    let start = pos2(700.0, 500.0);
+    let frames = [
+        vec![Event::PointerMoved(start)],
+        vec![Event::PointerButton {
+            pos: start,
+            button: PointerButton::Primary,
+            pressed: true,
+            modifiers: Modifiers::NONE,
+        }],
+        vec![Event::PointerMoved(start - vec2(50.0, 50.0))],
+        vec![Event::PointerMoved(start - vec2(100.0, 100.0))],
+        vec![Event::PointerButton {
+            pos: start - vec2(100.0, 100.0),
+            button: PointerButton::Primary,
+            pressed: false,
+            modifiers: Modifiers::NONE,
+        }],
+    ];
+    for events in frames {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, events);
+    }
+    assert_eq!(
+        Snarl::<()>::get_selected_nodes_at("snarl", ui_id, &cx),
+        [node]
+    );
+    assert!((viewer.titles[&node].min - initial.min).length() > 10.0);
+
+    Snarl::<()>::forget_ui_state_at("snarl", ui_id, &cx);
+    assert!(Snarl::<()>::get_selected_nodes_at("snarl", ui_id, &cx).is_empty());
+    assert_eq!(snarl.get_node_rect_at(node, "snarl", ui_id, &cx), None);
+
+    // Viewport starts over.
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert!((viewer.titles[&node].min - initial.min).length() < 1e-3);
+}
+
+#[test]
+fn show_after_clear_draws_empty_graph() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let nodes = [0.0, 150.0, 300.0].map(|x| snarl.insert_node(pos2(x, 0.0), ()));
+    snarl.connect(
+        OutPinId {
+            node: nodes[0],
+            output: 0,
+        },
+        InPinId {
+            node: nodes[1],
+            input: 0,
+        },
+    );
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle::new();
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+    let mut ui_id = Id::NULL;
+    let _ = cx.run(RawInput::default(), |cx| {
+        egui::CentralPanel::default().show(cx, |ui| ui_id = ui.id());
+    });
+
+    for node in &nodes[..2] {
+        let center = viewer.titles[node].center();
+        click(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            center,
+            Modifiers::SHIFT,
+        );
+    }
+    assert_eq!(
+        Snarl::<()>::get_selected_nodes_at("snarl", ui_id, &cx).len(),
+        2
+    );
+
+    snarl.clear();
+    viewer.titles.clear();
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+    assert!(viewer.titles.is_empty());
+    assert!(Snarl::<()>::get_selected_nodes_at("snarl", ui_id, &cx).is_empty());
+
+    // Reused id doesn't inherit selection of the cleared node.
+    let node = snarl.insert_node(pos2(0.0, 0.0), ());
+    assert_eq!(node, nodes[0]);
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+    assert!(viewer.titles.contains_key(&node));
+    assert!(Snarl::<()>::get_selected_nodes_at("snarl", ui_id, &cx).is_empty());
+}
+
+#[test]
+fn empty_viewport_does_not_break_initial_fit() {
+    let run = |cx: &Context, snarl: &mut Snarl<()>, viewer: &mut TestViewer, size: Vec2| {
+        let input = RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, size)),
+            ..RawInput::default()
+        };
+        let _ = cx.run(input, |cx| {
+            egui::CentralPanel::default().show(cx, |ui| {
+                snarl.show(viewer, &SnarlStyle::new(), "snarl", ui);
+            });
+        });
+    };
+
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(-300.0, -200.0), ());
+    let b = snarl.insert_node(pos2(300.0, 200.0), ());
+
+    // Graph shown in a normal panel right away.
+    let cx = Context::default();
+    let mut expected = TestViewer::default();
+    run(&cx, &mut snarl, &mut expected, vec2(800.0, 600.0));
+
+    // Graph shown in a panel that is collapsed first.
+    let cx = Context::default();
+    let mut viewer = TestViewer::default();
+    for size in [Vec2::ZERO, vec2(1.0, 1.0), Vec2::ZERO] {
+        run(&cx, &mut snarl, &mut viewer, size);
+        assert!(viewer.titles.is_empty(), "nodes are shown in {size:?}");
+    }
+    run(&cx, &mut snarl, &mut viewer, vec2(800.0, 600.0));
+
+    for node in [a, b] {
+        let title = viewer.titles[&node];
+        assert!(title.is_finite(), "{title:?}");
+        assert!((title.min - expected.titles[&node].min).length() < 1e-3);
+    }
+}
+
+fn press_key(
+    cx: &Context,
+    snarl: &mut Snarl<()>,
+    viewer: &mut TestViewer,
+    style: &SnarlStyle,
+    pointer: Option<Pos2>,
+    key: Key,
+) {
+    let mut events = vec![pointer.map_or(Event::PointerGone, Event::PointerMoved)];
+    events.push(Event::Key {
+        key,
+        physical_key: None,
+        pressed: true,
+        repeat: false,
+        modifiers: Modifiers::NONE,
+    });
+    run_frame(cx, snarl, viewer, style, events);
+}
+
+#[test]
+fn zoom_shortcuts_keep_anchor_in_place() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(600.0, 400.0), ());
+
+    let mut viewer = TestViewer::default();
+    let shortcut = |key| Some(KeyboardShortcut::new(Modifiers::NONE, key));
+    let style = SnarlStyle {
+        zoom_in_shortcut: shortcut(Key::I),
+        zoom_out_shortcut: shortcut(Key::O),
+        zoom_reset_shortcut: shortcut(Key::Num0),
+        zoom_fit_shortcut: shortcut(Key::F),
+        zoom_fit_selection_shortcut: shortcut(Key::G),
+        ..SnarlStyle::new()
+    };
+
+    for _ in 0..2 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    }
+
+    let mut ui_id = Id::NULL;
+    let _ = cx.run(RawInput::default(), |cx| {
+        egui::CentralPanel::default().show(cx, |ui| ui_id = ui.id());
+    });
+    let zoom = |cx: &Context| Snarl::<()>::get_zoom_at("snarl", ui_id, cx).unwrap();
+
+    let hover = Some(pos2(20.0, 580.0));
+
+    // Graph point shown at the center of the viewport.
+    let center = |viewer: &TestViewer| {
+        let viewport = viewer.viewport.unwrap();
+        viewport.screen_pos_to_graph(viewport.rect.center())
+    };
+
+    // Press the key and run one more frame to see resulting viewport.
+    let press = |snarl: &mut Snarl<()>, viewer: &mut TestViewer, pointer, key| {
+        press_key(&cx, snarl, viewer, &style, pointer, key);
+        run_frame(&cx, snarl, viewer, &style, Vec::new());
+    };
+
+    let check_key = |snarl: &mut Snarl<()>, viewer: &mut TestViewer, key, expected: f32| {
+        let before = center(viewer);
+        press(snarl, viewer, hover, key);
+
+        let scale = zoom(&cx);
+        assert!((scale - expected).abs() < 1e-4, "{key:?}: {scale}");
+        assert!((center(viewer) - before).length() < 1e-3);
+        assert!((viewer.viewport.unwrap().scale - scale).abs() < 1e-6);
+    };
+
+    let initial = zoom(&cx);
+    check_key(&mut snarl, &mut viewer, Key::I, initial * 1.25);
+    check_key(&mut snarl, &mut viewer, Key::O, initial);
+    check_key(&mut snarl, &mut viewer, Key::Num0, 1.0);
+
+    // Zoom in, so nodes don't fit, then fit all of them.
+    check_key(&mut snarl, &mut viewer, Key::I, 1.25);
+    check_key(&mut snarl, &mut viewer, Key::I, 1.5625);
+    press(&mut snarl, &mut viewer, hover, Key::F);
+    assert!(zoom(&cx) <= 1.0);
+    let viewport = viewer.viewport.unwrap().rect;
+    assert!(viewport.contains_rect(viewer.titles[&a]));
+    assert!(viewport.contains_rect(viewer.titles[&b]));
+
+    // Fit selected node, it is centered without zooming in.
+    let title = viewer.titles[&b];
+    click(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        title.center(),
+        Modifiers::SHIFT,
+    );
+    press(&mut snarl, &mut viewer, hover, Key::G);
+    assert!((zoom(&cx) - 1.0).abs() < 1e-4);
+    // Measured size differs slightly between scales due to text rounding.
+    let node_rect = snarl.get_node_rect_at(b, "snarl", ui_id, &cx).unwrap();
+    assert!((center(&viewer) - node_rect.center()).length() < 2.0);
+
+    // Shortcuts are ignored while pointer is not over the graph.
+    press(&mut snarl, &mut viewer, None, Key::I);
+    assert!((zoom(&cx) - 1.0).abs() < 1e-4);
+
+    // Explicit zoom is applied without animation.
+    let before = center(&viewer);
+    Snarl::<()>::set_zoom_at(2.0, ZoomAnchor::Center, "snarl", ui_id, &cx);
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert!((viewer.viewport.unwrap().scale - 2.0).abs() < 1e-6);
+    assert!((center(&viewer) - before).length() < 1e-3);
+
+    // Graph anchor stays at the same screen position.
+    let anchor = pos2(100.0, 50.0);
+    let screen = viewer.viewport.unwrap().graph_pos_to_screen(anchor);
+    Snarl::<()>::set_zoom_at(0.5, ZoomAnchor::Graph(anchor), "snarl", ui_id, &cx);
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    let viewport = viewer.viewport.unwrap();
+    assert!((viewport.scale - 0.5).abs() < 1e-6);
+    assert!((viewport.graph_pos_to_screen(anchor) - screen).length() < 1e-3);
+}
+
+/// Viewer that uses default header with an icon.
+struct IconViewer {
+    icon: NodeIcon,
+}
+
+impl SnarlViewer<()> for IconViewer {
+    fn title(&mut self, (): &()) -> String {
+        "Node".to_owned()
+    }
+
+    fn node_icon(&mut self, (): &()) -> Option<NodeIcon> {
+        Some(self.icon.clone())
+    }
+
+    fn inputs(&mut self, (): &()) -> usize {
+        1
+    }
+
+    fn outputs(&mut self, (): &()) -> usize {
+        1
+    }
+
+    fn show_input(&mut self, _: &InPin, _: &mut Ui, _: f32, _: &mut Snarl<()>) -> PinInfo {
+        PinInfo::circle()
+    }
+
+    fn show_output(&mut self, _: &OutPin, _: &mut Ui, _: f32, _: &mut Snarl<()>) -> PinInfo {
+        PinInfo::circle()
+    }
+}
+
+#[test]
+fn default_header_shows_icon_before_title() {
+    let texture = TextureId::User(42);
+
+    for open in [true, false] {
+        let cx = Context::default();
+        let mut snarl = Snarl::new();
+        if open {
+            snarl.insert_node(pos2(0.0, 0.0), ());
+        } else {
+            snarl.insert_node_collapsed(pos2(0.0, 0.0), ());
+        }
+
+        let mut viewer = IconViewer {
+            icon: NodeIcon::texture(texture),
+        };
+        let style = SnarlStyle::new();
+
+        let output = settle(&cx, &mut snarl, &mut viewer, &style);
+
+        let icons = collect_texture_rects(&output, texture);
+        let title = find_text(&output, "Node").expect("Title must be shown");
+
+        assert_eq!(icons.len(), 1, "open: {open}");
+        let icon = icons[0];
+        assert!((icon.width() - icon.height()).abs() < 1e-3, "{icon:?}");
+        assert!(icon.right() <= title.left(), "{icon:?} {title:?}");
+        assert!(
+            (icon.center().y - title.center().y).abs() < 1.0,
+            "{icon:?} {title:?}"
+        );
+    }
+}
+
+#[test]
+fn bounding_rect_covers_all_nodes() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+
+    let mut ui_id = Id::NULL;
+    let _ = cx.run(RawInput::default(), |cx| {
+        egui::CentralPanel::default().show(cx, |ui| ui_id = ui.id());
+    });
+    assert_eq!(snarl.nodes_bounding_rect_at("snarl", ui_id, &cx), None);
+
+    let a = snarl.insert_node(pos2(-50.0, 20.0), ());
+    let b = snarl.insert_node(pos2(200.0, 150.0), ());
+
+    // Sizes are not known before nodes are shown.
+    assert_eq!(
+        snarl.nodes_bounding_rect_at("snarl", ui_id, &cx),
+        Some(Rect::from_min_max(pos2(-50.0, 20.0), pos2(200.0, 150.0)))
+    );
+
+    let mut viewer = TestViewer::default();
+    run_frame(&cx, &mut snarl, &mut viewer, &SnarlStyle::new(), Vec::new());
+
+    let a_rect = snarl.get_node_rect_at(a, "snarl", ui_id, &cx).unwrap();
+    let b_rect = snarl.get_node_rect_at(b, "snarl", ui_id, &cx).unwrap();
+    assert_eq!(a_rect.min, pos2(-50.0, 20.0));
+    assert_eq!(b_rect.min, pos2(200.0, 150.0));
+    assert!(b_rect.width() > 0.0 && b_rect.height() > 0.0);
+
+    let rect = snarl.nodes_bounding_rect_at("snarl", ui_id, &cx).unwrap();
+    assert_eq!(rect, a_rect.union(b_rect));
+}
+
+#[test]
+fn view_requests_apply_before_first_frame() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(3000.0, 1000.0), ());
+
+    let mut ui_id = Id::NULL;
+    let _ = cx.run(RawInput::default(), |cx| {
+        egui::CentralPanel::default().show(cx, |ui| ui_id = ui.id());
+    });
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle::new();
+    let viewport_center = pos2(400.0, 300.0);
+
+    // Title is drawn a bit right and below of the node position.
+    let near_center = |title: Rect| {
+        let d = title.min - viewport_center;
+        d.x >= 0.0 && d.y >= 0.0 && d.x < 100.0 && d.y < 100.0
+    };
+
+    Snarl::<()>::center_on_node_at(b, "snarl", ui_id, &cx);
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert!(near_center(viewer.titles[&b]), "{:?}", viewer.titles[&b]);
+    let initial_zoom = Snarl::<()>::get_zoom_at("snarl", ui_id, &cx).unwrap();
+
+    // Request is applied once.
+    Snarl::<()>::set_view_at(pos2(0.0, 0.0), 1.5, "snarl", ui_id, &cx);
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert_eq!(Snarl::<()>::get_zoom_at("snarl", ui_id, &cx), Some(1.5));
+    assert!(near_center(viewer.titles[&a]), "{:?}", viewer.titles[&a]);
+
+    // Scale is clamped.
+    Snarl::<()>::set_view_at(pos2(0.0, 0.0), 100.0, "snarl", ui_id, &cx);
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert_eq!(Snarl::<()>::get_zoom_at("snarl", ui_id, &cx), Some(5.0));
+
+    let rect = snarl.nodes_bounding_rect_at("snarl", ui_id, &cx).unwrap();
+    Snarl::<()>::fit_view_at(rect, "snarl", ui_id, &cx);
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    let screen = Rect::from_min_size(Pos2::ZERO, vec2(800.0, 600.0));
+    assert!(screen.contains_rect(viewer.titles[&a]));
+    assert!(screen.contains_rect(viewer.titles[&b]));
+    assert!(Snarl::<()>::get_zoom_at("snarl", ui_id, &cx).unwrap() <= initial_zoom + 1e-3);
+}
+
+#[test]
+fn viewport_converts_between_screen_and_graph() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let node = snarl.insert_node(pos2(100.0, 50.0), ());
+
+    let mut ui_id = Id::NULL;
+    let _ = cx.run(RawInput::default(), |cx| {
+        egui::CentralPanel::default().show(cx, |ui| ui_id = ui.id());
+    });
+    assert_eq!(Snarl::<()>::get_viewport_at("snarl", ui_id, &cx), None);
+
+    let mut viewer = TestViewer::default();
+    Snarl::<()>::set_view_at(pos2(100.0, 50.0), 2.0, "snarl", ui_id, &cx);
+    run_frame(&cx, &mut snarl, &mut viewer, &SnarlStyle::new(), Vec::new());
+
+    let viewport = Snarl::<()>::get_viewport_at("snarl", ui_id, &cx).unwrap();
+    let screen_rect = Rect::from_min_size(Pos2::ZERO, vec2(800.0, 600.0));
+    assert!(screen_rect.contains_rect(viewport.rect));
+    assert_eq!(viewport.rect.center(), screen_rect.center());
+    assert_eq!(viewport.scale, 2.0);
+    assert_eq!(
+        viewport.graph_pos_to_screen(pos2(100.0, 50.0)),
+        screen_rect.center()
+    );
+
+    let screen = pos2(123.0, 456.0);
+    let graph = viewport.screen_pos_to_graph(screen);
+    assert!((viewport.graph_pos_to_screen(graph) - screen).length() < 1e-3);
+
+    // Node title is drawn right of the node position.
+    let title = viewport.screen_pos_to_graph(viewer.titles[&node].min);
+    assert!(title.x > 100.0 && title.y >= 50.0);
+
+    Snarl::<()>::forget_ui_state_at("snarl", ui_id, &cx);
+    assert_eq!(Snarl::<()>::get_viewport_at("snarl", ui_id, &cx), None);
+}
+
+#[test]
+fn delete_shortcut_removes_selected_nodes() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(0.0, 200.0), ());
+    let c = snarl.insert_node(pos2(300.0, 100.0), ());
+    snarl.connect(
+        OutPinId { node: a, output: 0 },
+        InPinId { node: c, input: 0 },
+    );
+    snarl.connect(
+        OutPinId { node: b, output: 0 },
+        InPinId { node: c, input: 1 },
+    );
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle {
+        delete_selected_shortcut: Some(KeyboardShortcut::new(Modifiers::NONE, Key::Delete)),
+        ..SnarlStyle::new()
+    };
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+    let mut ui_id = Id::NULL;
+    let _ = cx.run(RawInput::default(), |cx| {
+        egui::CentralPanel::default().show(cx, |ui| ui_id = ui.id());
+    });
+
+    for node in [a, b] {
+        let pos = viewer.titles[&node].center();
+        click(&cx, &mut snarl, &mut viewer, &style, pos, Modifiers::SHIFT);
+    }
+    let mut selected = Snarl::<()>::get_selected_nodes_at("snarl", ui_id, &cx);
+    selected.sort();
+    assert_eq!(selected, [a, b]);
+
+    // Shortcut is ignored while pointer is outside of the graph.
+    press_key(&cx, &mut snarl, &mut viewer, &style, None, Key::Delete);
+    assert_eq!(snarl.node_count(), 3);
+
+    press_key(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        Some(pos2(700.0, 500.0)),
+        Key::Delete,
+    );
+    assert_eq!(snarl.node_ids().map(|(id, _)| id).collect::<Vec<_>>(), [c]);
+    assert_eq!(snarl.wire_count(), 0);
+    assert!(Snarl::<()>::get_selected_nodes_at("snarl", ui_id, &cx).is_empty());
+}
+
+#[test]
+fn wires_are_selected_and_deleted() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 0.0), ());
+    let c = snarl.insert_node(pos2(0.0, 200.0), ());
+    let d = snarl.insert_node(pos2(300.0, 200.0), ());
+    let top = (
+        OutPinId { node: a, output: 0 },
+        InPinId { node: b, input: 0 },
+    );
+    let bottom = (
+        OutPinId { node: c, output: 0 },
+        InPinId { node: d, input: 0 },
+    );
+    snarl.connect(top.0, top.1);
+    snarl.connect(bottom.0, bottom.1);
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle {
+        delete_selected_shortcut: Some(KeyboardShortcut::new(Modifiers::NONE, Key::Delete)),
+        select_wires: Some(true),
+        select_style: Some(SelectionStyle {
+            stroke: egui::Stroke::new(2.0, Color32::GOLD),
+            ..SelectionStyle::default()
+        }),
+        ..SnarlStyle::new()
+    };
+    for _ in 0..3 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    }
+
+    let mut ui_id = Id::NULL;
+    let _ = cx.run(RawInput::default(), |cx| {
+        egui::CentralPanel::default().show(cx, |ui| ui_id = ui.id());
+    });
+    let selected = |cx: &Context| Snarl::<()>::get_selected_wires_at("snarl", ui_id, cx);
+
+    let top_from = viewer.output_pos[&top.0];
+    let top_to = viewer.input_pos[&top.1];
+    let on_top = top_from.lerp(top_to, 0.25);
+
+    // Plain click doesn't select the wire.
+    click(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        on_top,
+        Modifiers::NONE,
+    );
+    assert!(selected(&cx).is_empty());
+
+    click(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        on_top,
+        Modifiers::SHIFT,
+    );
+    assert_eq!(selected(&cx), [top]);
+
+    // Selection is drawn underneath the wire.
+    let output = run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    let color = find_path_color(&output, top_from, top_to);
+    assert_eq!(color, Some(Color32::GOLD));
+
+    click(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        on_top,
+        Modifiers::COMMAND,
+    );
+    assert!(selected(&cx).is_empty());
+
+    // Rect around the middle of the bottom wire selects only the wire.
+    let middle = viewer.output_pos[&bottom.0].lerp(viewer.input_pos[&bottom.1], 0.5);
+    let button = |pos, pressed| Event::PointerButton {
+        pos,
+        button: PointerButton::Primary,
+        pressed,
+        modifiers: Modifiers::SHIFT,
+    };
+    let frames = [
+        vec![Event::PointerMoved(middle - vec2(20.0, 20.0))],
+        vec![button(middle - vec2(20.0, 20.0), true)],
+        vec![Event::PointerMoved(middle)],
+        vec![Event::PointerMoved(middle + vec2(20.0, 20.0))],
+        vec![button(middle + vec2(20.0, 20.0), false)],
+    ];
+    for events in frames {
+        run_frame_with_modifiers(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            events,
+            Modifiers::SHIFT,
+        );
+    }
+    assert_eq!(selected(&cx), [bottom]);
+    assert!(Snarl::<()>::get_selected_nodes_at("snarl", ui_id, &cx).is_empty());
+
+    press_key(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        Some(middle),
+        Key::Delete,
+    );
+    assert!(!snarl.connected(bottom.0, bottom.1));
+    assert!(snarl.connected(top.0, top.1));
+    assert_eq!(snarl.node_count(), 4);
+    assert!(selected(&cx).is_empty());
+
+    // Wires removed elsewhere are dropped from selection.
+    click(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        on_top,
+        Modifiers::SHIFT,
+    );
+    assert_eq!(selected(&cx), [top]);
+
+    // Forgetting UI state clears wire selection.
+    Snarl::<()>::forget_ui_state_at("snarl", ui_id, &cx);
+    assert!(selected(&cx).is_empty());
+
+    click(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        on_top,
+        Modifiers::SHIFT,
+    );
+    assert_eq!(selected(&cx), [top]);
+    snarl.disconnect(top.0, top.1);
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert!(selected(&cx).is_empty());
+}
+
+#[test]
+fn dragging_selection_reports_each_moved_node() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(0.0, 200.0), ());
+    let c = snarl.insert_node(pos2(300.0, 100.0), ());
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle::new();
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+    for node in [a, b] {
+        let pos = viewer.titles[&node].center();
+        click(&cx, &mut snarl, &mut viewer, &style, pos, Modifiers::SHIFT);
+    }
+    assert!(viewer.moves.is_empty());
+    assert!(viewer.drags.is_empty());
+
+    let start = viewer.titles[&a].center();
+    let press = |pressed| Event::PointerButton {
+        pos: start,
+        button: PointerButton::Primary,
+        pressed,
+        modifiers: Modifiers::NONE,
+    };
+    run_frame(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        vec![Event::PointerMoved(start)],
+    );
+    run_frame(&cx, &mut snarl, &mut viewer, &style, vec![press(true)]);
+
+    let mut pointer = start;
+    for _ in 0..3 {
+        viewer.moves.clear();
+        pointer += vec2(20.0, 10.0);
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![Event::PointerMoved(pointer)],
+        );
+
+        let mut moved = viewer.moves.iter().map(|m| m.0).collect::<Vec<_>>();
+        moved.sort();
+        assert_eq!(moved, [a, b]);
+        for &(node, old_pos, new_pos) in &viewer.moves {
+            assert_eq!(snarl.get_node_info(node).unwrap().pos, new_pos);
+            assert!(new_pos.x > old_pos.x && new_pos.y > old_pos.y);
+        }
+        assert!(viewer.drags.is_empty());
+    }
+
+    viewer.moves.clear();
+    run_frame(&cx, &mut snarl, &mut viewer, &style, vec![press(false)]);
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert!(viewer.moves.is_empty());
+    assert_eq!(viewer.drags.len(), 1);
+    let mut dragged = viewer.drags[0].clone();
+    dragged.sort();
+    assert_eq!(dragged, [a, b]);
+    assert_eq!(snarl.get_node_info(c).unwrap().pos, pos2(300.0, 100.0));
+}
+
+#[test]
+fn rejected_pin_is_not_a_wire_target() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 0.0), ());
+
+    let from = OutPinId { node: a, output: 0 };
+    let to = InPinId { node: b, input: 0 };
+
+    let mut viewer = TestViewer {
+        rejected_inputs: vec![to],
+        ..TestViewer::default()
+    };
+    let style = SnarlStyle {
+        invalid_wire_color: Some(Color32::RED),
+        ..SnarlStyle::new()
+    };
+    for _ in 0..3 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    }
+
+    let start = viewer.output_pos[&from];
+    let end = viewer.input_pos[&to];
+    let button = |pos, pressed| Event::PointerButton {
+        pos,
+        button: PointerButton::Primary,
+        pressed,
+        modifiers: Modifiers::NONE,
+    };
+    let frames = [
+        vec![Event::PointerMoved(start)],
+        vec![button(start, true)],
+        vec![Event::PointerMoved(start + vec2(50.0, 0.0))],
+        vec![Event::PointerMoved(end)],
+    ];
+    let mut output = None;
+    for events in frames {
+        output = Some(run_frame(&cx, &mut snarl, &mut viewer, &style, events));
+    }
+
+    // Input is not highlighted and the dragged wire is drawn as invalid.
+    assert!(!viewer.input_hovered.last().unwrap());
+    let color = find_path_color(&output.unwrap(), start, end).expect("wire is drawn");
+    assert_eq!(color, Color32::RED);
+
+    // Releasing the wire over the input doesn't connect, but opens the dropped wire menu.
+    run_frame(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        vec![button(end, false)],
+    );
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert!(snarl.in_pin(to).remotes.is_empty());
+    assert_eq!(viewer.dropped_pins.last(), Some(&vec![AnyPin::Out(from)]));
+}
+
+#[test]
+fn effects_are_applied_in_order() {
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), 1);
+    let b = snarl.insert_node(pos2(100.0, 0.0), 2);
+    let c = snarl.insert_node(pos2(200.0, 0.0), 3);
+
+    let a_out = OutPinId { node: a, output: 0 };
+    let b_in = InPinId { node: b, input: 0 };
+    let b_out = OutPinId { node: b, output: 0 };
+    let c_in = InPinId { node: c, input: 0 };
+    snarl.connect(b_out, c_in);
+
+    let mut effects = Effects::new();
+    assert!(effects.is_empty());
+    effects.insert_node(pos2(300.0, 0.0), 4);
+    effects.connect(a_out, b_in);
+    effects.connect(a_out, c_in);
+    effects.disconnect(a_out, c_in);
+    effects.drop_outputs(b_out);
+    effects.open_node(a, false);
+    effects.remove_node(c);
+    // Effects referring to removed nodes are skipped.
+    effects.connect(b_out, c_in);
+    effects.open_node(c, false);
+    assert!(!effects.is_empty());
+
+    snarl.apply_effects(effects);
+
+    assert_eq!(snarl.wires().collect::<Vec<_>>(), [(a_out, b_in)]);
+    assert!(!snarl.get_node_info(a).unwrap().open);
+    assert!(snarl.get_node(c).is_none());
+    let mut values = snarl.nodes().copied().collect::<Vec<_>>();
+    values.sort_unstable();
+    assert_eq!(values, [1, 2, 4]);
+}
+
+#[test]
+fn node_removed_by_effect_is_not_drawn_next_frame() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 0.0), ());
+    snarl.connect(
+        OutPinId { node: a, output: 0 },
+        InPinId { node: b, input: 0 },
+    );
+
+    let mut viewer = TestViewer::default();
+    let style = SnarlStyle::new();
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+    // Node is still shown in the frame the effect is requested and removed after it.
+    viewer.titles.clear();
+    viewer.effects.remove_node(b);
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert!(viewer.titles.contains_key(&b));
+    assert!(snarl.get_node(b).is_none());
+    assert_eq!(snarl.wire_count(), 0);
+
+    viewer.titles.clear();
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert!(viewer.titles.contains_key(&a));
+    assert!(!viewer.titles.contains_key(&b));
+}
+
+#[test]
+fn viewer_overrides_wire_appearance_under_hover() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 0.0), ());
+    let c = snarl.insert_node(pos2(300.0, 200.0), ());
+
+    let from = OutPinId { node: a, output: 0 };
+    let to = InPinId { node: b, input: 0 };
+    let other = InPinId { node: c, input: 0 };
+    snarl.connect(from, to);
+    snarl.connect(from, other);
+
+    let mut viewer = TestViewer {
+        error_inputs: vec![to],
+        ..TestViewer::default()
+    };
+    let style = SnarlStyle::new();
+    for _ in 0..3 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    }
+
+    let start = viewer.output_pos[&from];
+    let end = viewer.input_pos[&to];
+    let stroke = |output: &FullOutput, to: Pos2| {
+        Some((
+            find_path_color(output, start, to)?,
+            find_path_width(output, start, to)?,
+        ))
+    };
+
+    let output = run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert_eq!(stroke(&output, end), Some((Color32::RED, 4.0)));
+    let (color, _) = stroke(&output, viewer.input_pos[&other]).expect("wire is drawn");
+    assert_ne!(color, Color32::RED);
+
+    // Hovered wire is still widened.
+    let middle = start.lerp(end, 0.5);
+    run_frame(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        vec![Event::PointerMoved(middle)],
+    );
+    let output = run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert_eq!(stroke(&output, end), Some((Color32::RED, 6.0)));
+}
+
+#[test]
+fn wire_widget_is_centered_and_blocks_wire() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 0.0), ());
+    let far = snarl.insert_node(pos2(5000.0, 0.0), ());
+    let farther = snarl.insert_node(pos2(5300.0, 0.0), ());
+
+    let wire = (
+        OutPinId { node: a, output: 0 },
+        InPinId { node: b, input: 0 },
+    );
+    let hidden = (
+        OutPinId {
+            node: far,
+            output: 0,
+        },
+        InPinId {
+            node: farther,
+            input: 0,
+        },
+    );
+    snarl.connect(wire.0, wire.1);
+    snarl.connect(hidden.0, hidden.1);
+
+    let mut viewer = TestViewer {
+        wire_widgets: true,
+        ..TestViewer::default()
+    };
+    let style = SnarlStyle::new();
+
+    // Show only two nodes wired to each other, so the other wire is off-screen.
+    let mut ui_id = Id::NULL;
+    let _ = cx.run(RawInput::default(), |cx| {
+        egui::CentralPanel::default().show(cx, |ui| ui_id = ui.id());
+    });
+    Snarl::<()>::fit_view_at(
+        Rect::from_min_max(pos2(-50.0, -50.0), pos2(450.0, 150.0)),
+        "snarl",
+        ui_id,
+        &cx,
+    );
+    for _ in 0..3 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    }
+
+    assert!(!viewer.wire_widget_rects.contains_key(&hidden));
+    let rect = viewer.wire_widget_rects[&wire];
+    let middle = viewer.output_pos[&wire.0].lerp(viewer.input_pos[&wire.1], 0.5);
+    assert!((rect.center() - middle).length() < 1.0);
+
+    // Secondary click on the widget doesn't remove the wire, primary click reaches the widget.
+    for button in [PointerButton::Secondary, PointerButton::Primary] {
+        let event = |pressed| Event::PointerButton {
+            pos: middle,
+            button,
+            pressed,
+            modifiers: Modifiers::NONE,
+        };
+        let frames = [
+            vec![Event::PointerMoved(middle)],
+            vec![event(true)],
+            vec![event(false)],
+        ];
+        for events in frames {
+            run_frame(&cx, &mut snarl, &mut viewer, &style, events);
+        }
+    }
+    assert!(snarl.connected(wire.0, wire.1));
+    assert_eq!(viewer.wire_widget_clicks, [wire]);
+}
+
+#[test]
+fn wire_widget_hides_wire_tooltip_only_under_itself() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 0.0), ());
+    let wire = (
+        OutPinId { node: a, output: 0 },
+        InPinId { node: b, input: 0 },
+    );
+    snarl.connect(wire.0, wire.1);
+
+    let mut viewer = TestViewer {
+        wire_widgets: true,
+        wire_hover: true,
+        ..TestViewer::default()
+    };
+    let style = SnarlStyle::new();
+    for _ in 0..3 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    }
+
+    let start = viewer.output_pos[&wire.0];
+    let end = viewer.input_pos[&wire.1];
+    let middle = start.lerp(end, 0.5);
+    assert!(viewer.wire_widget_rects[&wire].contains(middle));
+
+    for pos in [middle, start.lerp(end, 0.25)] {
+        viewer.wire_hovers.clear();
+        run_frame(
+            &cx,
+            &mut snarl,
+            &mut viewer,
+            &style,
+            vec![Event::PointerMoved(pos)],
+        );
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+
+        if pos == middle {
+            assert!(viewer.wire_hovers.is_empty());
+        } else {
+            assert_eq!(viewer.wire_hovers.last(), Some(&wire));
+        }
+    }
+}
+
+#[test]
+fn wire_tooltip_is_shown_only_when_not_dragging() {
+    let cx = Context::default();
+    let mut snarl = Snarl::new();
+    let a = snarl.insert_node(pos2(0.0, 0.0), ());
+    let b = snarl.insert_node(pos2(300.0, 0.0), ());
+    let wire = (
+        OutPinId { node: a, output: 0 },
+        InPinId { node: b, input: 0 },
+    );
+    snarl.connect(wire.0, wire.1);
+
+    let mut viewer = TestViewer {
+        wire_hover: true,
+        ..TestViewer::default()
+    };
+    let style = SnarlStyle::new();
+    for _ in 0..3 {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    }
+    assert!(viewer.wire_hovers.is_empty());
+
+    let start = viewer.output_pos[&wire.0];
+    let middle = start.lerp(viewer.input_pos[&wire.1], 0.5);
+    run_frame(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        vec![Event::PointerMoved(middle)],
+    );
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert_eq!(viewer.wire_hovers.last(), Some(&wire));
+
+    // Dragging new wire over the wire shows no tooltip.
+    run_frame(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        vec![Event::PointerMoved(start)],
+    );
+    viewer.wire_hovers.clear();
+    let frames = [
+        vec![Event::PointerButton {
+            pos: start,
+            button: PointerButton::Primary,
+            pressed: true,
+            modifiers: Modifiers::NONE,
+        }],
+        vec![Event::PointerMoved(start + vec2(50.0, 0.0))],
+        vec![Event::PointerMoved(middle)],
+        Vec::new(),
+    ];
+    for events in frames {
+        run_frame(&cx, &mut snarl, &mut viewer, &style, events);
+    }
+    assert!(viewer.wire_hovers.is_empty());
+
+    // Wire was actually dragged, dropping it opens the menu.
+    run_frame(
+        &cx,
+        &mut snarl,
+        &mut viewer,
+        &style,
+        vec![Event::PointerButton {
+            pos: middle,
+            button: PointerButton::Primary,
+            pressed: false,
+            modifiers: Modifiers::NONE,
+        }],
+    );
+    run_frame(&cx, &mut snarl, &mut viewer, &style, Vec::new());
+    assert_eq!(viewer.dropped_pins.last(), Some(&vec![AnyPin::Out(wire.0)]));
+}
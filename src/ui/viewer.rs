@@ -2,7 +2,11 @@ use egui::{Color32, Painter, Pos2, Rect, Style, Ui};
 
 use crate::{InPin, InPinId, NodeId, OutPin, OutPinId, Snarl};
 
-use super::{pin::AnyPins, BackgroundPattern, NodeLayout, PinInfo, SnarlStyle, Viewport};
+use super::{
+    pin::{AnyPin, AnyPins},
+    show_node_header, BackgroundPattern, Effects, NodeActivity, NodeIcon, NodeLayout, PinInfo,
+    PinSide, SnarlStyle, Viewport, ViewportChangeReason, WireInfo,
+};
 
 /// `SnarlViewer` is a trait for viewing a Snarl.
 ///
@@ -38,6 +42,91 @@ pub trait SnarlViewer<T> {
         default
     }
 
+    /// Returns activity of the node to show as a slim strip under the node's header.
+    ///
+    /// Strip is visible for both open and collapsed nodes and is styled with [`SnarlStyle::progress_style`].
+    /// It is drawn over the node's content and doesn't change node's size.
+    /// While any node returns [`NodeActivity::Spinner`], repaint is requested each frame.
+    ///
+    /// By default it returns `None` and no strip is shown.
+    #[inline]
+    fn node_progress(&mut self, node: &T) -> Option<NodeActivity> {
+        let _ = node;
+        None
+    }
+
+    /// Returns icon shown before the title in the default node header.
+    ///
+    /// Icon is kept when the node is collapsed.
+    /// By default it returns `None` and only the title is shown.
+    #[inline]
+    fn node_icon(&mut self, node: &T) -> Option<NodeIcon> {
+        let _ = node;
+        None
+    }
+
+    /// Returns opacity of the node from 0.0 to 1.0.
+    ///
+    /// Node's frame, header, pins and content are drawn with this opacity.
+    /// Wires are as opaque as the more transparent of their nodes.
+    /// Nodes below [`SnarlStyle::min_interactive_opacity`] can't be interacted with.
+    ///
+    /// It is called every frame, so it can be used to ghost nodes filtered out of the view.
+    /// By default it returns 1.0.
+    #[inline]
+    fn node_opacity(&mut self, node: &T) -> f32 {
+        let _ = node;
+        1.0
+    }
+
+    /// Returns breakpoint state of the node.
+    ///
+    /// If `Some`, a gutter with a circle is shown before the collapse icon in the node's header.
+    /// Circle is filled when `Some(true)` is returned and only outlined otherwise.
+    /// It is styled with [`SnarlStyle::breakpoint_style`].
+    ///
+    /// By default it returns `None` and no gutter is shown.
+    #[inline]
+    fn breakpoint_state(&mut self, node: &T) -> Option<bool> {
+        let _ = node;
+        None
+    }
+
+    /// Called when breakpoint gutter of the node is clicked,
+    /// or [`SnarlStyle::breakpoint_shortcut`] is pressed while the node is selected.
+    ///
+    /// Clicking the gutter doesn't select, raise or drag the node.
+    #[inline]
+    fn toggle_breakpoint(&mut self, node: NodeId, snarl: &mut Snarl<T>) {
+        let _ = (node, snarl);
+    }
+
+    /// Called when the node is moved by dragging, after its position is changed.
+    ///
+    /// When selected nodes are dragged together, it is called for each of them.
+    /// It is called once per node in each frame the node moves.
+    #[inline]
+    fn node_moved(&mut self, node: NodeId, old_pos: Pos2, new_pos: Pos2, snarl: &mut Snarl<T>) {
+        let _ = (node, old_pos, new_pos, snarl);
+    }
+
+    /// Called when dragging nodes ends, with all nodes moved by the drag.
+    ///
+    /// Use it to record the whole drag as a single undo step.
+    #[inline]
+    fn node_drag_finished(&mut self, nodes: &[NodeId], snarl: &mut Snarl<T>) {
+        let _ = (nodes, snarl);
+    }
+
+    /// Called for each selected node when [`SnarlStyle::delete_selected_shortcut`] is pressed.
+    ///
+    /// Override to keep some nodes or to record removal for undo.
+    /// By default it removes the node with its wires.
+    #[inline]
+    fn remove_node(&mut self, node: NodeId, snarl: &mut Snarl<T>) {
+        snarl.remove_node(node);
+    }
+
     /// Returns layout override for the node.
     ///
     /// This method can be used to override the default layout of the node.
@@ -55,7 +144,70 @@ pub trait SnarlViewer<T> {
         default
     }
 
+    /// Returns side of the node where the pin is placed.
+    ///
+    /// `default` is the side where pins of this kind are placed in the node's layout:
+    /// left for inputs and right for outputs, or the other way around if layout is mirrored.
+    /// Placing a pin on the other side is useful for feedback or bidirectional connections.
+    /// Wires connecting pins on the same side loop around them.
+    ///
+    /// With [`NodeLayout::Basic`] pins moved to the other side are shown below the pins of that side.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use egui::Ui;
+    /// # use egui_snarl::{InPin, NodeId, OutPin, Snarl, ui::{AnyPin, PinInfo, PinSide, SnarlViewer}};
+    /// struct FeedbackViewer;
+    ///
+    /// impl SnarlViewer<&'static str> for FeedbackViewer {
+    ///     fn title(&mut self, node: &&'static str) -> String {
+    ///         node.to_string()
+    ///     }
+    ///
+    ///     fn inputs(&mut self, _: &&'static str) -> usize {
+    ///         1
+    ///     }
+    ///
+    ///     fn outputs(&mut self, node: &&'static str) -> usize {
+    ///         if *node == "Delay" { 2 } else { 1 }
+    ///     }
+    ///
+    ///     fn show_input(&mut self, _: &InPin, ui: &mut Ui, _: f32, _: &mut Snarl<&'static str>) -> PinInfo {
+    ///         ui.label("In");
+    ///         PinInfo::circle()
+    ///     }
+    ///
+    ///     fn show_output(&mut self, pin: &OutPin, ui: &mut Ui, _: f32, _: &mut Snarl<&'static str>) -> PinInfo {
+    ///         ui.label(if pin.id.output == 1 { "Feedback" } else { "Out" });
+    ///         PinInfo::circle()
+    ///     }
+    ///
+    ///     fn pin_side(&mut self, default: PinSide, node: NodeId, pin: AnyPin, snarl: &Snarl<&'static str>) -> PinSide {
+    ///         match pin {
+    ///             // Feedback output of the delay node goes back to the nodes on the left.
+    ///             AnyPin::Out(pin) if snarl[node] == "Delay" && pin.output == 1 => default.opposite(),
+    ///             _ => default,
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    #[inline]
+    fn pin_side(
+        &mut self,
+        default: PinSide,
+        node: NodeId,
+        pin: AnyPin,
+        snarl: &Snarl<T>,
+    ) -> PinSide {
+        let _ = (node, pin, snarl);
+        default
+    }
+
     /// Renders the node's header.
+    ///
+    /// By default it shows [`SnarlViewer::node_icon`] followed by [`SnarlViewer::title`]
+    /// using [`show_node_header`].
     #[inline]
     fn show_header(
         &mut self,
@@ -66,8 +218,9 @@ pub trait SnarlViewer<T> {
         scale: f32,
         snarl: &mut Snarl<T>,
     ) {
-        let _ = (inputs, outputs, scale);
-        ui.label(self.title(&snarl[node]));
+        let _ = (inputs, outputs);
+        let icon = self.node_icon(&snarl[node]);
+        show_node_header(ui, icon.as_ref(), self.title(&snarl[node]), scale);
     }
 
     /// Returns number of input pins of the node.
@@ -75,7 +228,28 @@ pub trait SnarlViewer<T> {
     /// [`SnarlViewer::show_input`] and [`SnarlViewer::draw_input_pin`] will be called for each input in range `0..inputs()`.
     fn inputs(&mut self, node: &T) -> usize;
 
+    /// Returns stable key of the node's input pin.
+    ///
+    /// Input pins are identified by index, so wires stay at the same index when inputs are reordered.
+    /// If keys are returned for all inputs of the node, they are remembered between frames.
+    /// When key moves to another index, wires are moved with it
+    /// using [`Snarl::remap_inputs_by_key`].
+    /// Wires connected to inputs whose keys disappeared are removed.
+    ///
+    /// Keys must be unique among inputs of the same node.
+    ///
+    /// By default returns `None` and inputs are identified by index only.
+    #[inline]
+    fn input_key(&mut self, node: &T, input: usize) -> Option<u64> {
+        let _ = (node, input);
+        None
+    }
+
     /// Renders the node's input.
+    ///
+    /// [`InPin::hovered`] tells whether pointer was over the pin's row in the previous frame.
+    /// It can be used to show extra content on hover.
+    /// The whole row is considered, so growing content keeps the pin hovered.
     fn show_input(&mut self, pin: &InPin, ui: &mut Ui, scale: f32, snarl: &mut Snarl<T>)
         -> PinInfo;
 
@@ -85,6 +259,10 @@ pub trait SnarlViewer<T> {
     fn outputs(&mut self, node: &T) -> usize;
 
     /// Renders the node's output.
+    ///
+    /// [`OutPin::hovered`] tells whether pointer was over the pin's row in the previous frame.
+    /// It can be used to show extra content on hover.
+    /// The whole row is considered, so growing content keeps the pin hovered.
     fn show_output(
         &mut self,
         pin: &OutPin,
@@ -114,6 +292,49 @@ pub trait SnarlViewer<T> {
         let _ = (node, inputs, outputs, ui, scale, snarl);
     }
 
+    /// Renders content in the gap between input and output pins
+    /// of a node without body in [`NodeLayout::Basic`].
+    ///
+    /// Unlike [`SnarlViewer::show_body`] this content never makes the node taller.
+    /// The gap is as tall as the taller pin column and content is clipped to it.
+    /// Width of the content is added to the node's width, so the gap grows to fit it
+    /// in the next frame. Content is centered in the gap.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use egui::{Color32, Sense, Ui};
+    /// # use egui_snarl::{InPin, NodeId, OutPin, Snarl, ui::{PinInfo, SnarlViewer}};
+    /// struct Viewer;
+    ///
+    /// impl SnarlViewer<bool> for Viewer {
+    ///     fn title(&mut self, _: &bool) -> String { "Status".to_owned() }
+    ///     fn inputs(&mut self, _: &bool) -> usize { 1 }
+    ///     fn outputs(&mut self, _: &bool) -> usize { 1 }
+    ///     fn show_input(&mut self, _: &InPin, _: &mut Ui, _: f32, _: &mut Snarl<bool>) -> PinInfo { PinInfo::circle() }
+    ///     fn show_output(&mut self, _: &OutPin, _: &mut Ui, _: f32, _: &mut Snarl<bool>) -> PinInfo { PinInfo::circle() }
+    ///
+    ///     fn show_center(&mut self, node: NodeId, _: &[InPin], _: &[OutPin], ui: &mut Ui, scale: f32, snarl: &mut Snarl<bool>) {
+    ///         // Status light between the pins.
+    ///         let color = if snarl[node] { Color32::GREEN } else { Color32::RED };
+    ///         let (rect, _) = ui.allocate_exact_size(egui::vec2(8.0, 8.0) * scale, Sense::hover());
+    ///         ui.painter().circle_filled(rect.center(), 4.0 * scale, color);
+    ///     }
+    /// }
+    /// ```
+    #[inline]
+    fn show_center(
+        &mut self,
+        node: NodeId,
+        inputs: &[InPin],
+        outputs: &[OutPin],
+        ui: &mut Ui,
+        scale: f32,
+        snarl: &mut Snarl<T>,
+    ) {
+        let _ = (node, inputs, outputs, ui, scale, snarl);
+    }
+
     /// Checks if node has something to show in footer - below pins and body.
     #[inline]
     fn has_footer(&mut self, node: &T) -> bool {
@@ -173,8 +394,35 @@ pub trait SnarlViewer<T> {
         let _ = (node, inputs, outputs, ui, scale, snarl);
     }
 
+    /// Checks if wire has something to show in on-hover tooltip.
+    ///
+    /// Called only for the hovered wire and not while new wire is dragged.
+    #[inline]
+    fn has_wire_hover(&mut self, from: &OutPinId, to: &InPinId, snarl: &Snarl<T>) -> bool {
+        let _ = (from, to, snarl);
+        false
+    }
+
+    /// Renders the wire's on-hover tooltip next to the pointer.
+    ///
+    /// Can be used to show the value flowing through the wire.
+    #[inline]
+    fn show_wire_hover(
+        &mut self,
+        from: &OutPin,
+        to: &InPin,
+        ui: &mut Ui,
+        scale: f32,
+        snarl: &mut Snarl<T>,
+    ) {
+        let _ = (from, to, ui, scale, snarl);
+    }
+
     /// Checks if wire has something to show in widget.
     /// This may not be called if wire is invisible.
+    ///
+    /// Widget is shown with [`SnarlViewer::show_wire_widget`] centered on the wire's midpoint.
+    /// It is not shown for wires connected to invisible pins and for wires outside of the viewport.
     #[inline]
     fn has_wire_widget(&mut self, from: &OutPinId, to: &InPinId, snarl: &Snarl<T>) -> bool {
         let _ = (from, to, snarl);
@@ -183,6 +431,19 @@ pub trait SnarlViewer<T> {
 
     /// Renders the wire's widget.
     /// This may not be called if wire is invisible.
+    ///
+    /// Called after all wires are drawn, so widget is shown above them.
+    /// `ui` is centered on the wire's midpoint and its style is zoomed with the graph.
+    ///
+    /// Pointer over the widget doesn't hover the wire or the background,
+    /// so it can be used for value badges, toggles and other interactive elements.
+    /// This means that under the widget the wire is not highlighted,
+    /// its [`SnarlViewer::show_wire_hover`] tooltip is not shown
+    /// and secondary click doesn't disconnect it.
+    /// Everywhere else along the wire hover and disconnect work as usual.
+    ///
+    /// `ui` is disabled if either node is less opaque than [`SnarlStyle::min_interactive_opacity`].
+    /// Widget may remove its own wire, other widgets are skipped if their wires are removed.
     #[inline]
     fn show_wire_widget(
         &mut self,
@@ -195,6 +456,37 @@ pub trait SnarlViewer<T> {
         let _ = (from, to, ui, scale, snarl);
     }
 
+    /// Checks if the wire should pulse.
+    ///
+    /// Called for each visible wire every frame.
+    /// When it returns `true` the wire starts a brief brightness pulse.
+    /// Viewer decides when it should happen, e.g. when value flowing through the wire changes.
+    #[inline]
+    fn wire_pulse(&mut self, from: &OutPinId, to: &InPinId, snarl: &Snarl<T>) -> bool {
+        let _ = (from, to, snarl);
+        false
+    }
+
+    /// Returns appearance of the wire.
+    ///
+    /// Called for each visible wire every frame before it is drawn.
+    /// `default` is derived from colors and styles of the wire's pins and the graph style.
+    /// Width of hovered and pulsing wires is increased after this call.
+    ///
+    /// This can be used to highlight wires carrying errors or active execution path.
+    /// By default it returns `default`.
+    #[inline]
+    fn wire_style(
+        &mut self,
+        from: &OutPinId,
+        to: &InPinId,
+        default: WireInfo,
+        snarl: &Snarl<T>,
+    ) -> WireInfo {
+        let _ = (from, to, snarl);
+        default
+    }
+
     /// Checks if the snarl has something to show in context menu if right-clicked or long-touched on empty space at `pos`.
     #[inline]
     fn has_graph_menu(&mut self, pos: Pos2, snarl: &mut Snarl<T>) -> bool {
@@ -211,6 +503,8 @@ pub trait SnarlViewer<T> {
     }
 
     /// Checks if the snarl has something to show in context menu if wire drag is stopped at `pos`.
+    ///
+    /// `src_pins` may contain several pins when multiple wires are dragged at once.
     #[inline]
     fn has_dropped_wire_menu(&mut self, src_pins: AnyPins, snarl: &mut Snarl<T>) -> bool {
         let _ = (src_pins, snarl);
@@ -220,6 +514,11 @@ pub trait SnarlViewer<T> {
     /// Show context menu for the snarl. This menu is opened when releasing a pin to empty
     /// space. It can be used to implement menu for adding new node, and directly
     /// connecting it to the released wire.
+    ///
+    /// Wires picked from a pin with command modifier are dragged together,
+    /// so `src_pins` may contain several pins, all of the same direction.
+    /// Use [`AnyPins::len`] and [`AnyPins::iter`] to build a menu
+    /// that connects new node to all of them at once.
     #[inline]
     fn show_dropped_wire_menu(
         &mut self,
@@ -232,6 +531,16 @@ pub trait SnarlViewer<T> {
         let _ = (pos, ui, scale, src_pins, snarl);
     }
 
+    /// Checks if the node may be drawn on top of others after being clicked or dragged.
+    ///
+    /// Only consulted when [`SnarlStyle::raise_on_interact`] permits raising.
+    /// Returning `false` keeps nodes like comment frames behind content nodes.
+    #[inline]
+    fn allow_raise(&mut self, node: &T) -> bool {
+        let _ = node;
+        true
+    }
+
     /// Checks if the node has something to show in context menu if right-clicked or long-touched on the node.
     #[inline]
     fn has_node_menu(&mut self, node: &T) -> bool {
@@ -255,6 +564,45 @@ pub trait SnarlViewer<T> {
         let _ = (node, inputs, outputs, ui, scale, snarl);
     }
 
+    /// Show context menu for the node, knowing which pin was right-clicked.
+    ///
+    /// `clicked_pin` is the pin of this node whose row was under the pointer
+    /// when menu was opened with right-click or long-touch, or `None` if menu was opened elsewhere on the node.
+    /// Right-click on the pin shape itself drops pin's wires instead of opening the menu,
+    /// and widgets in pin's content that sense clicks, like selectable labels, receive right-clicks themselves.
+    ///
+    /// By default it calls [`SnarlViewer::show_node_menu`].
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    fn show_node_menu_for_pin(
+        &mut self,
+        node: NodeId,
+        clicked_pin: Option<AnyPin>,
+        inputs: &[InPin],
+        outputs: &[OutPin],
+        ui: &mut Ui,
+        scale: f32,
+        snarl: &mut Snarl<T>,
+    ) {
+        let _ = clicked_pin;
+        self.show_node_menu(node, inputs, outputs, ui, scale, snarl);
+    }
+
+    /// Checks whether wire dragged from one pin may be dropped on the other one.
+    ///
+    /// While a new wire is dragged over a pin that can't be connected,
+    /// the pin is not highlighted and not reported as hovered,
+    /// and the wire is drawn with [`SnarlStyle::invalid_wire_color`] if it is set.
+    /// Releasing the wire over such pin is handled as dropping it on empty space.
+    ///
+    /// When several wires are dragged, pin accepts them only if all of them can be connected.
+    /// By default it returns `true`.
+    #[inline]
+    fn can_connect(&mut self, from: &OutPin, to: &InPin, snarl: &Snarl<T>) -> bool {
+        let _ = (from, to, snarl);
+        true
+    }
+
     /// Asks the viewer to connect two pins.
     ///
     /// This is usually happens when user drags a wire from one node's output pin to another node's input pin or vice versa.
@@ -270,6 +618,27 @@ pub trait SnarlViewer<T> {
         snarl.disconnect(from.id, to.id);
     }
 
+    /// Checks if the dragged node can be inserted onto the wire from `from` to `to`.
+    ///
+    /// Returns indices of the node's input and output to connect to `from` and `to`,
+    /// or `None` to leave the wire alone. By default nodes are never inserted.
+    ///
+    /// While a single node is dragged over exactly one wire that is not connected to it,
+    /// the wire is highlighted as hovered. When the node is dropped there,
+    /// the wire is replaced with two wires through the node using [`SnarlViewer::disconnect`]
+    /// and [`SnarlViewer::connect`], before [`SnarlViewer::node_drag_finished`] is called.
+    #[inline]
+    fn can_insert_on_wire(
+        &mut self,
+        node: NodeId,
+        from: OutPinId,
+        to: InPinId,
+        snarl: &Snarl<T>,
+    ) -> Option<(usize, usize)> {
+        let _ = (node, from, to, snarl);
+        None
+    }
+
     /// Asks the viewer to disconnect all wires from the output pin.
     ///
     /// This is usually happens when right-clicking on an output pin.
@@ -340,6 +709,9 @@ pub trait SnarlViewer<T> {
     ///
     /// By default it draws the background pattern using [`BackgroundPattern::draw`].
     ///
+    /// If [`SnarlStyle::bg_patterns`] is set, this method is called once for each pattern,
+    /// from bottom to top.
+    ///
     /// If you want to draw the background yourself, you can override this method.
     fn draw_background(
         &mut self,
@@ -356,4 +728,38 @@ pub trait SnarlViewer<T> {
             background.draw(viewport, snarl_style, style, painter);
         }
     }
+
+    /// Notifies the viewer that the viewport was changed.
+    ///
+    /// Called at the end of the frame only if viewport's offset or scale actually changed.
+    /// `prev` is the viewport at the beginning of the frame.
+    /// If viewport was changed multiple times in one frame, `reason` is of the last change.
+    ///
+    /// This can be used to synchronize other views with the graph
+    /// or to persist the camera only when it changes.
+    #[inline]
+    fn viewport_changed(
+        &mut self,
+        prev: &Viewport,
+        viewport: &Viewport,
+        reason: ViewportChangeReason,
+        snarl: &Snarl<T>,
+    ) {
+        let _ = (prev, viewport, reason, snarl);
+    }
+
+    /// Returns effects collected by the viewer during the frame.
+    ///
+    /// Called once at the end of [`Snarl::show`], after all other viewer methods.
+    /// Returned effects are applied with [`Snarl::apply_effects`] in order they were added,
+    /// so graph changes requested while nodes are drawn take place after the whole graph is shown.
+    /// Nodes removed this way are not drawn in the next frame.
+    ///
+    /// Viewer can keep [`Effects`] and push to it instead of mutating the graph directly,
+    /// for example to remove a node from its own menu.
+    /// By default it returns no effects.
+    #[inline]
+    fn take_effects(&mut self) -> Effects<T> {
+        Effects::default()
+    }
 }
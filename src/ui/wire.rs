@@ -1,6 +1,45 @@
 use std::f32;
 
-use egui::{epaint::PathShape, pos2, Color32, Pos2, Rect, Shape, Stroke, Ui};
+use egui::{epaint::PathShape, pos2, Color32, Mesh, Pos2, Rect, Shape, Stroke, Ui, Vec2};
+
+use super::PinSide;
+
+/// Marker drawn at the input end of the wire to show direction of the flow.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "egui-probe", derive(egui_probe::EguiProbe))]
+pub enum WireEndMarker {
+    /// No marker.
+    /// This is default.
+    #[default]
+    None,
+
+    /// Arrowhead pointing into the input pin.
+    Arrow {
+        /// Length and width of the arrowhead.
+        size: f32,
+    },
+
+    /// Dot touching the input pin.
+    Dot {
+        /// Radius of the dot.
+        radius: f32,
+    },
+}
+
+/// Controls how wire is colored from colors of its pins.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "egui-probe", derive(egui_probe::EguiProbe))]
+pub enum WireColorMode {
+    /// Wire has single color, mix of colors of its pins.
+    /// This is default.
+    #[default]
+    Mix,
+
+    /// Wire color fades from output pin color to input pin color along the wire.
+    Gradient,
+}
 
 /// Layer where wires are rendered.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -36,6 +75,73 @@ pub enum WireStyle {
         /// Radius of corners in wire.
         corner_radius: f32,
     },
+
+    /// Draw wire as 3rd degree Bezier curve that sags like a hanging cable.
+    /// Middle control points are pushed down by `sag` times horizontal distance between wire ends.
+    Sagging {
+        /// Sag relative to horizontal distance between wire ends.
+        sag: f32,
+    },
+
+    /// Draw wire as 5th degree Bezier curve made of dashes.
+    /// Dashes march from output to input with `speed`, showing data direction.
+    /// Wire flow, if set, is used instead of `speed`, see [`SnarlStyle::wire_flow`](super::SnarlStyle::wire_flow).
+    Dashed {
+        /// Length of each dash.
+        length: f32,
+
+        /// Length of the gap between dashes.
+        gap: f32,
+
+        /// Speed of dashes along the wire in points per second.
+        /// Zero means static dashes.
+        speed: f32,
+    },
+
+    /// Draw wire as 5th degree Bezier curve that leaves and enters pins vertically.
+    /// Wires go down from pins on the right side and come from above into pins on the left side,
+    /// so graphs can be laid out top to bottom.
+    Bezier5Vertical,
+
+    /// Draw wire as straight lines with 90 degree turns that leaves and enters pins vertically.
+    /// Corners has radius of `corner_radius`.
+    ///
+    /// See [`WireStyle::Bezier5Vertical`] for directions.
+    AxisAlignedVertical {
+        /// Radius of corners in wire.
+        corner_radius: f32,
+    },
+
+    /// Draw wire as 5th degree Bezier curve made of dots.
+    /// Dots are as wide as twice the wire width.
+    /// Dots move from output to input while the wire flows, see [`SnarlStyle::wire_flow`](super::SnarlStyle::wire_flow).
+    Dotted {
+        /// Distance between centers of adjacent dots.
+        spacing: f32,
+    },
+}
+
+/// Appearance of a single wire.
+///
+/// Passed to [`SnarlViewer::wire_style`](super::SnarlViewer::wire_style)
+/// with values derived from the pins and the graph style.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WireInfo {
+    /// Color of the wire.
+    /// If [`WireInfo::end_color`] is set, this is the color at the output pin.
+    pub color: Color32,
+
+    /// Color of the wire at the input pin.
+    /// Wire fades from [`WireInfo::color`] to this color along the wire.
+    ///
+    /// Set to colors of the pins with [`WireColorMode::Gradient`], `None` otherwise.
+    pub end_color: Option<Color32>,
+
+    /// Width of the wire in screen points.
+    pub width: f32,
+
+    /// Style of the wire, already scaled to the screen.
+    pub style: WireStyle,
 }
 
 pub fn pick_wire_style(
@@ -46,6 +152,17 @@ pub fn pick_wire_style(
     match (left, right) {
         (None, None) => default,
         (Some(one), None) | (None, Some(one)) => one,
+        // Vertical wires follow layout of the graph, so they win over horizontal ones.
+        (
+            Some(WireStyle::AxisAlignedVertical { corner_radius: a }),
+            Some(WireStyle::AxisAlignedVertical { corner_radius: b }),
+        ) => WireStyle::AxisAlignedVertical {
+            corner_radius: a.max(b),
+        },
+        (Some(vertical @ WireStyle::AxisAlignedVertical { .. }), Some(_))
+        | (Some(_), Some(vertical @ WireStyle::AxisAlignedVertical { .. })) => vertical,
+        (Some(WireStyle::Bezier5Vertical), Some(_))
+        | (Some(_), Some(WireStyle::Bezier5Vertical)) => WireStyle::Bezier5Vertical,
         (Some(WireStyle::Bezier5), Some(WireStyle::Bezier5)) => WireStyle::Bezier5,
         (Some(WireStyle::Bezier3 | WireStyle::Bezier5), Some(WireStyle::Bezier3))
         | (Some(WireStyle::Bezier3), Some(WireStyle::Bezier5)) => WireStyle::Bezier3,
@@ -59,6 +176,44 @@ pub fn pick_wire_style(
         | (Some(_), Some(WireStyle::AxisAligned { corner_radius })) => {
             WireStyle::AxisAligned { corner_radius }
         }
+        (Some(WireStyle::Sagging { sag: a }), Some(WireStyle::Sagging { sag: b })) => {
+            WireStyle::Sagging { sag: a.max(b) }
+        }
+        (Some(WireStyle::Sagging { sag }), Some(_))
+        | (Some(_), Some(WireStyle::Sagging { sag })) => WireStyle::Sagging { sag },
+        (
+            Some(WireStyle::Dashed {
+                length: a_length,
+                gap: a_gap,
+                speed: a_speed,
+            }),
+            Some(WireStyle::Dashed {
+                length: b_length,
+                gap: b_gap,
+                speed: b_speed,
+            }),
+        ) => WireStyle::Dashed {
+            length: a_length.max(b_length),
+            gap: a_gap.max(b_gap),
+            speed: a_speed.max(b_speed),
+        },
+        (Some(dashed @ WireStyle::Dashed { .. }), Some(_))
+        | (Some(_), Some(dashed @ WireStyle::Dashed { .. })) => dashed,
+        (Some(WireStyle::Dotted { spacing: a }), Some(WireStyle::Dotted { spacing: b })) => {
+            WireStyle::Dotted { spacing: a.max(b) }
+        }
+        (Some(dotted @ WireStyle::Dotted { .. }), Some(_))
+        | (Some(_), Some(dotted @ WireStyle::Dotted { .. })) => dotted,
+    }
+}
+
+/// Picks frame size for the wire from overrides of its ends.
+/// The larger override wins, `default` is used if neither end has one.
+pub fn pick_wire_frame_size(default: f32, left: Option<f32>, right: Option<f32>) -> f32 {
+    match (left, right) {
+        (None, None) => default,
+        (Some(one), None) | (None, Some(one)) => one,
+        (Some(a), Some(b)) => a.max(b),
     }
 }
 
@@ -79,8 +234,67 @@ fn adjust_frame_size(
     frame_size
 }
 
+/// Mirrors point horizontally around zero.
+fn mirror_x(pos: Pos2) -> Pos2 {
+    pos2(-pos.x, pos.y)
+}
+
+/// Mirrors point across the diagonal.
+/// Horizontal wire geometry becomes vertical.
+fn transpose(pos: Pos2) -> Pos2 {
+    pos2(pos.y, pos.x)
+}
+
+/// Returns 5th degree bezier curve control points for the vertical wire.
+fn wire_bezier_5_vertical(
+    frame_size: f32,
+    from: Pos2,
+    to: Pos2,
+    from_side: PinSide,
+    to_side: PinSide,
+) -> [Pos2; 6] {
+    wire_bezier_5(
+        frame_size,
+        transpose(from),
+        transpose(to),
+        from_side,
+        to_side,
+    )
+    .map(transpose)
+}
+
 /// Returns 5th degree bezier curve control points for the wire
-fn wire_bezier_5(frame_size: f32, from: Pos2, to: Pos2) -> [Pos2; 6] {
+///
+/// Wire leaves `from` towards `from_side` and enters `to` from `to_side`.
+/// If both ends are on the same side, wire loops around them.
+fn wire_bezier_5(
+    frame_size: f32,
+    from: Pos2,
+    to: Pos2,
+    from_side: PinSide,
+    to_side: PinSide,
+) -> [Pos2; 6] {
+    match (from_side, to_side) {
+        (PinSide::Right, PinSide::Left) => {}
+        (PinSide::Left, _) => {
+            return wire_bezier_5(
+                frame_size,
+                mirror_x(from),
+                mirror_x(to),
+                PinSide::Right,
+                to_side.opposite(),
+            )
+            .map(mirror_x);
+        }
+        (PinSide::Right, PinSide::Right) => {
+            // Both ends face right, wire loops around the rightmost one.
+            let x = from.x.max(to.x) + frame_size;
+            let from_2 = pos2(x, from.y);
+            let to_2 = pos2(x, to.y);
+            return [from, from_2, from_2, to_2, to_2, to];
+        }
+    }
+
     let from_norm_x = frame_size;
     let from_2 = pos2(from.x + from_norm_x, from.y);
     let to_norm_x = -from_norm_x;
@@ -204,6 +418,23 @@ fn wire_bezier_5(frame_size: f32, from: Pos2, to: Pos2) -> [Pos2; 6] {
     }
 }
 
+/// Returns 3rd degree bezier curve control points for the sagging wire.
+///
+/// Curve follows the [`WireStyle::Bezier3`] wire with middle control points
+/// pushed down proportionally to horizontal distance between the ends.
+fn wire_sagging(
+    frame_size: f32,
+    sag: f32,
+    from: Pos2,
+    to: Pos2,
+    from_side: PinSide,
+    to_side: PinSide,
+) -> [Pos2; 4] {
+    let [a, _, b, c, _, d] = wire_bezier_5(frame_size, from, to, from_side, to_side);
+    let drop = sag * (to.x - from.x).abs();
+    [a, pos2(b.x, b.y + drop), pos2(c.x, c.y + drop), d]
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn draw_wire(
     ui: &Ui,
@@ -213,37 +444,424 @@ pub fn draw_wire(
     downscale: bool,
     from: Pos2,
     to: Pos2,
+    from_side: PinSide,
+    to_side: PinSide,
     mut stroke: Stroke,
+    mut end_color: Option<Color32>,
     style: WireStyle,
+    flow: Option<f32>,
+    end_marker: WireEndMarker,
+    end_offset: f32,
 ) {
     if stroke.width < 1.0 {
         stroke.color = stroke.color.gamma_multiply(stroke.width);
+        end_color = end_color.map(|color| color.gamma_multiply(stroke.width));
         stroke.width = 1.0;
     }
 
+    let feathering = if end_color.is_some_and(|color| color != stroke.color) {
+        let pixels = ui.ctx().tessellation_options(|options| {
+            if options.feathering {
+                options.feathering_size_in_pixels
+            } else {
+                0.0
+            }
+        });
+        pixels / ui.ctx().pixels_per_point()
+    } else {
+        end_color = None;
+        0.0
+    };
+
+    // Flow replaces speed of dashed wires and moves dots of dotted ones.
+    // Other wires are drawn as dashes while they flow.
+    let speed = match style {
+        WireStyle::Dashed { speed, .. } => flow.unwrap_or(speed),
+        _ => flow.unwrap_or(0.0),
+    };
+    let time = ui.input(|i| i.time);
+
+    let dashes = match style {
+        WireStyle::Dashed { length, gap, .. } => Some((length, gap)),
+        WireStyle::Dotted { .. } => None,
+        _ if speed != 0.0 => Some((
+            stroke.width * FLOW_DASH_LENGTH,
+            stroke.width * FLOW_GAP_LENGTH,
+        )),
+        _ => None,
+    };
+
+    let stroke = WireStroke {
+        stroke,
+        end_color,
+        feathering,
+        dashes: dashes.map(|(length, gap)| WireDashes {
+            length,
+            gap,
+            offset: pattern_offset(time, speed, length + gap),
+        }),
+    };
+
+    // Direction of the wire at its end, known only for visible wires.
+    let mut end_direction = None;
+
     let frame_size = adjust_frame_size(frame_size, upscale, downscale, from, to);
     match style {
         WireStyle::Bezier3 => {
-            let [a, _, b, c, _, d] = wire_bezier_5(frame_size, from, to);
+            let [a, _, b, c, _, d] = wire_bezier_5(frame_size, from, to, from_side, to_side);
             let points = [a, b, c, d];
 
             let bb = Rect::from_points(&points);
             if ui.is_rect_visible(bb) {
                 draw_bezier_3(shapes, &points, stroke);
+                end_direction = end_tangent(&points);
             }
         }
 
-        WireStyle::Bezier5 => {
-            let points = wire_bezier_5(frame_size, from, to);
+        WireStyle::Bezier5 | WireStyle::Dashed { .. } => {
+            let points = wire_bezier_5(frame_size, from, to, from_side, to_side);
 
             let bb = Rect::from_points(&points);
             if ui.is_rect_visible(bb) {
                 draw_bezier_5(shapes, &points, stroke);
+                end_direction = end_tangent(&points);
+            }
+        }
+
+        WireStyle::Dotted { spacing } => {
+            let points = wire_bezier_5(frame_size, from, to, from_side, to_side);
+
+            let bb = Rect::from_points(&points);
+            if ui.is_rect_visible(bb) {
+                let path = sample_bezier_path(&points, stroke.stroke.width);
+                draw_dotted_path(shapes, &path, spacing, time, speed, stroke);
+                end_direction = end_tangent(&points);
+            }
+        }
+
+        WireStyle::Sagging { sag } => {
+            let points = wire_sagging(frame_size, sag, from, to, from_side, to_side);
+
+            let bb = Rect::from_points(&points);
+            if ui.is_rect_visible(bb) {
+                draw_bezier_3(shapes, &points, stroke);
+                end_direction = end_tangent(&points);
+            }
+        }
+
+        WireStyle::Bezier5Vertical => {
+            let points = wire_bezier_5_vertical(frame_size, from, to, from_side, to_side);
+
+            let bb = Rect::from_points(&points);
+            if ui.is_rect_visible(bb) {
+                draw_bezier_5(shapes, &points, stroke);
+                end_direction = end_tangent(&points);
             }
         }
 
         WireStyle::AxisAligned { corner_radius } => {
-            draw_axis_aligned(ui, shapes, corner_radius, frame_size, from, to, stroke);
+            end_direction = draw_axis_aligned(
+                ui,
+                shapes,
+                corner_radius,
+                frame_size,
+                from,
+                to,
+                from_side,
+                to_side,
+                stroke,
+                false,
+            );
+        }
+
+        WireStyle::AxisAlignedVertical { corner_radius } => {
+            end_direction = draw_axis_aligned(
+                ui,
+                shapes,
+                corner_radius,
+                frame_size,
+                from,
+                to,
+                from_side,
+                to_side,
+                stroke,
+                true,
+            );
+        }
+    }
+
+    // Keep patterns of visible wires moving.
+    if speed != 0.0 && end_direction.is_some() {
+        ui.ctx().request_repaint();
+    }
+
+    if let Some(direction) = end_direction {
+        draw_end_marker(
+            shapes,
+            end_marker,
+            to,
+            direction,
+            end_offset,
+            stroke.color_at(1.0),
+        );
+    }
+}
+
+/// Stroke of the wire that may fade to another color towards its input end.
+#[derive(Clone, Copy)]
+struct WireStroke {
+    stroke: Stroke,
+
+    /// Color at the input end if it differs from the stroke color.
+    end_color: Option<Color32>,
+
+    /// Size of anti-aliasing edge of gradient meshes in points.
+    feathering: f32,
+
+    /// Dashes the wire is drawn with, if any.
+    dashes: Option<WireDashes>,
+}
+
+/// Dash pattern of the wire.
+#[derive(Clone, Copy)]
+struct WireDashes {
+    length: f32,
+    gap: f32,
+
+    /// Shift of the pattern along the wire, moving dashes from output to input.
+    offset: f32,
+}
+
+/// Shortest dash, gap or dot spacing in points.
+/// Denser patterns are indistinguishable from a solid line.
+const MIN_PATTERN_STEP: f32 = 0.5;
+
+/// Maximum number of dashes drawn for one wire.
+/// Wires that would need more are drawn solid.
+const MAX_WIRE_DASHES: f32 = 4096.0;
+
+/// Length of flow dashes relative to the wire width.
+const FLOW_DASH_LENGTH: f32 = 4.0;
+
+/// Length of gaps between flow dashes relative to the wire width.
+const FLOW_GAP_LENGTH: f32 = 3.0;
+
+impl WireStroke {
+    /// Returns color at fraction `t` of the wire length.
+    fn color_at(&self, t: f32) -> Color32 {
+        match self.end_color {
+            None => self.stroke.color,
+            Some(end_color) => self.stroke.color.lerp_to_gamma(end_color, t),
+        }
+    }
+
+    /// Draws path of the whole wire, as dashes if the wire has them.
+    fn draw_path(&self, shapes: &mut Vec<Shape>, path: Vec<Pos2>) {
+        match self.dashes {
+            None => self.push_path(shapes, path, 0.0, 1.0),
+            Some(dashes) => draw_dashed_path(
+                shapes,
+                &path,
+                dashes.length,
+                dashes.gap,
+                dashes.offset,
+                *self,
+            ),
+        }
+    }
+
+    /// Pushes path that spans from `start` to `end` fractions of the wire length.
+    fn push_path(&self, shapes: &mut Vec<Shape>, points: Vec<Pos2>, start: f32, end: f32) {
+        if self.end_color.is_none() {
+            shapes.push(Shape::Path(PathShape {
+                points,
+                closed: false,
+                fill: Color32::TRANSPARENT,
+                stroke: self.stroke.into(),
+            }));
+            return;
+        }
+
+        shapes.push(Shape::mesh(gradient_path_mesh(
+            &points,
+            self.stroke.width,
+            self.feathering,
+            self.color_at(start),
+            self.color_at(end),
+        )));
+    }
+}
+
+/// Builds anti-aliased mesh for the path with color fading from `from` to `to` along its length.
+fn gradient_path_mesh(
+    points: &[Pos2],
+    width: f32,
+    feathering: f32,
+    from: Color32,
+    to: Color32,
+) -> Mesh {
+    let mut mesh = Mesh::default();
+
+    // Repeated points have no direction.
+    let mut path: Vec<Pos2> = Vec::with_capacity(points.len());
+    for &point in points {
+        if path
+            .last()
+            .map_or(true, |last| (point - *last).length_sq() > 1e-6)
+        {
+            path.push(point);
+        }
+    }
+
+    if path.len() < 2 {
+        return mesh;
+    }
+
+    let length: f32 = path.windows(2).map(|s| (s[1] - s[0]).length()).sum();
+
+    // Each point gets transparent outer vertices and colored inner vertices on both sides.
+    let inner = ((width - feathering) * 0.5).max(0.0);
+    let outer = (width + feathering) * 0.5;
+
+    mesh.reserve_vertices(path.len() * 4);
+    mesh.reserve_triangles((path.len() - 1) * 6);
+
+    let mut done = 0.0;
+    for i in 0..path.len() {
+        let before = i
+            .checked_sub(1)
+            .map(|prev| (path[i] - path[prev]).normalized().rot90());
+        let after = path
+            .get(i + 1)
+            .map(|next| (*next - path[i]).normalized().rot90());
+
+        let normal = match (before, after) {
+            // Miter join, limited for sharp turns.
+            (Some(before), Some(after)) => {
+                let normal = (before + after) * 0.5;
+                normal / normal.length_sq().max(0.5)
+            }
+            (Some(normal), None) | (None, Some(normal)) => normal,
+            (None, None) => unreachable!(),
+        };
+
+        if i > 0 {
+            done += (path[i] - path[i - 1]).length();
+        }
+
+        let t = if length > 0.0 { done / length } else { 0.0 };
+        let color = from.lerp_to_gamma(to, t);
+
+        let point = path[i];
+        mesh.colored_vertex(point + normal * outer, Color32::TRANSPARENT);
+        mesh.colored_vertex(point + normal * inner, color);
+        mesh.colored_vertex(point - normal * inner, color);
+        mesh.colored_vertex(point - normal * outer, Color32::TRANSPARENT);
+
+        if i > 0 {
+            #[allow(clippy::cast_possible_truncation)]
+            let b = (i * 4) as u32;
+            let a = b - 4;
+            for k in 0..3 {
+                mesh.add_triangle(a + k, a + k + 1, b + k);
+                mesh.add_triangle(a + k + 1, b + k, b + k + 1);
+            }
+        }
+    }
+
+    mesh
+}
+
+/// Returns direction of the curve at its last control point.
+///
+/// Bezier curve is tangent to the line from the last distinct control point to the last one.
+/// For polylines this is the direction of the last segment.
+fn end_tangent(points: &[Pos2]) -> Option<Vec2> {
+    let (&end, rest) = points.split_last()?;
+    rest.iter()
+        .rev()
+        .find(|point| (end - **point).length() > 1e-3)
+        .map(|point| (end - *point).normalized())
+}
+
+/// Draws marker that points along `direction` and touches `to` moved back by `offset`.
+fn draw_end_marker(
+    shapes: &mut Vec<Shape>,
+    marker: WireEndMarker,
+    to: Pos2,
+    direction: Vec2,
+    offset: f32,
+    color: Color32,
+) {
+    let tip = to - direction * offset;
+
+    match marker {
+        WireEndMarker::None => {}
+        WireEndMarker::Arrow { size } => {
+            let base = tip - direction * size;
+            let side = direction.rot90() * (size * 0.5);
+            shapes.push(Shape::convex_polygon(
+                vec![tip, base + side, base - side],
+                color,
+                Stroke::NONE,
+            ));
+        }
+        WireEndMarker::Dot { radius } => {
+            shapes.push(Shape::circle_filled(
+                tip - direction * radius,
+                radius,
+                color,
+            ));
+        }
+    }
+}
+
+/// Returns point in the middle of the wire and bounding box of the wire.
+#[allow(clippy::too_many_arguments)]
+pub fn wire_midpoint(
+    frame_size: f32,
+    upscale: bool,
+    downscale: bool,
+    from: Pos2,
+    to: Pos2,
+    from_side: PinSide,
+    to_side: PinSide,
+    style: WireStyle,
+) -> (Pos2, Rect) {
+    let frame_size = adjust_frame_size(frame_size, upscale, downscale, from, to);
+    match style {
+        WireStyle::Bezier3 => {
+            let [a, _, b, c, _, d] = wire_bezier_5(frame_size, from, to, from_side, to_side);
+            let points = [a, b, c, d];
+            (sample_bezier(&points, 0.5), Rect::from_points(&points))
+        }
+        WireStyle::Bezier5 | WireStyle::Dashed { .. } | WireStyle::Dotted { .. } => {
+            let points = wire_bezier_5(frame_size, from, to, from_side, to_side);
+            (sample_bezier(&points, 0.5), Rect::from_points(&points))
+        }
+        WireStyle::Sagging { sag } => {
+            let points = wire_sagging(frame_size, sag, from, to, from_side, to_side);
+            (sample_bezier(&points, 0.5), Rect::from_points(&points))
+        }
+        WireStyle::Bezier5Vertical => {
+            let points = wire_bezier_5_vertical(frame_size, from, to, from_side, to_side);
+            (sample_bezier(&points, 0.5), Rect::from_points(&points))
+        }
+        WireStyle::AxisAligned { corner_radius } => {
+            let wire = wire_axis_aligned(corner_radius, frame_size, from, to, from_side, to_side);
+            (wire.points[2], Rect::from_points(&wire.points))
+        }
+        WireStyle::AxisAlignedVertical { corner_radius } => {
+            let wire = wire_axis_aligned(
+                corner_radius,
+                frame_size,
+                transpose(from),
+                transpose(to),
+                from_side,
+                to_side,
+            );
+            let points = wire.points.map(transpose);
+            (points[2], Rect::from_points(&points))
         }
     }
 }
@@ -256,24 +874,140 @@ pub fn hit_wire(
     downscale: bool,
     from: Pos2,
     to: Pos2,
+    from_side: PinSide,
+    to_side: PinSide,
     threshold: f32,
     style: WireStyle,
 ) -> bool {
     let frame_size = adjust_frame_size(frame_size, upscale, downscale, from, to);
     match style {
         WireStyle::Bezier3 => {
-            let [a, _, b, c, _, d] = wire_bezier_5(frame_size, from, to);
+            let [a, _, b, c, _, d] = wire_bezier_5(frame_size, from, to, from_side, to_side);
             let points = [a, b, c, d];
             hit_bezier_3(pos, &points, threshold)
         }
-        WireStyle::Bezier5 => {
-            let points = wire_bezier_5(frame_size, from, to);
+        WireStyle::Bezier5 | WireStyle::Dashed { .. } | WireStyle::Dotted { .. } => {
+            let points = wire_bezier_5(frame_size, from, to, from_side, to_side);
             hit_bezier_5(pos, &points, threshold)
         }
+        WireStyle::Sagging { sag } => {
+            let points = wire_sagging(frame_size, sag, from, to, from_side, to_side);
+            hit_bezier_3(pos, &points, threshold)
+        }
+        WireStyle::Bezier5Vertical => {
+            let points = wire_bezier_5_vertical(frame_size, from, to, from_side, to_side);
+            hit_bezier_5(pos, &points, threshold)
+        }
+        WireStyle::AxisAligned { corner_radius } => hit_axis_aligned(
+            pos,
+            corner_radius,
+            frame_size,
+            from,
+            to,
+            from_side,
+            to_side,
+            threshold,
+        ),
+        // Distances don't change when everything is transposed.
+        WireStyle::AxisAlignedVertical { corner_radius } => hit_axis_aligned(
+            transpose(pos),
+            corner_radius,
+            frame_size,
+            transpose(from),
+            transpose(to),
+            from_side,
+            to_side,
+            threshold,
+        ),
+    }
+}
+
+/// Returns true if the wire passes through the rect.
+#[allow(clippy::too_many_arguments)]
+pub fn wire_intersects_rect(
+    rect: Rect,
+    frame_size: f32,
+    upscale: bool,
+    downscale: bool,
+    from: Pos2,
+    to: Pos2,
+    from_side: PinSide,
+    to_side: PinSide,
+    width: f32,
+    style: WireStyle,
+) -> bool {
+    let frame_size = adjust_frame_size(frame_size, upscale, downscale, from, to);
+    let path = match style {
+        WireStyle::Bezier3 => {
+            let [a, _, b, c, _, d] = wire_bezier_5(frame_size, from, to, from_side, to_side);
+            sample_bezier_path(&[a, b, c, d], width)
+        }
+        WireStyle::Bezier5 | WireStyle::Dashed { .. } | WireStyle::Dotted { .. } => {
+            let points = wire_bezier_5(frame_size, from, to, from_side, to_side);
+            sample_bezier_path(&points, width)
+        }
+        WireStyle::Sagging { sag } => {
+            let points = wire_sagging(frame_size, sag, from, to, from_side, to_side);
+            sample_bezier_path(&points, width)
+        }
+        WireStyle::Bezier5Vertical => {
+            let points = wire_bezier_5_vertical(frame_size, from, to, from_side, to_side);
+            sample_bezier_path(&points, width)
+        }
         WireStyle::AxisAligned { corner_radius } => {
-            hit_axis_aligned(pos, corner_radius, frame_size, from, to, threshold)
+            let wire = wire_axis_aligned(corner_radius, frame_size, from, to, from_side, to_side);
+            axis_aligned_path(&wire, width)
+        }
+        WireStyle::AxisAlignedVertical { corner_radius } => {
+            let wire = wire_axis_aligned(
+                corner_radius,
+                frame_size,
+                transpose(from),
+                transpose(to),
+                from_side,
+                to_side,
+            );
+            let mut path = axis_aligned_path(&wire, width);
+            for point in &mut path {
+                *point = transpose(*point);
+            }
+            path
+        }
+    };
+
+    let rect = rect.expand(width * 0.5);
+    path.windows(2)
+        .any(|segment| segment_intersects_rect(segment[0], segment[1], rect))
+}
+
+/// Returns true if the segment from `a` to `b` has points inside the rect.
+fn segment_intersects_rect(a: Pos2, b: Pos2, rect: Rect) -> bool {
+    // Clip the segment by each pair of rect sides.
+    let mut t_min = 0.0f32;
+    let mut t_max = 1.0f32;
+
+    for (start, delta, min, max) in [
+        (a.x, b.x - a.x, rect.min.x, rect.max.x),
+        (a.y, b.y - a.y, rect.min.y, rect.max.y),
+    ] {
+        if delta == 0.0 {
+            if start < min || start > max {
+                return false;
+            }
+            continue;
+        }
+
+        let t0 = (min - start) / delta;
+        let t1 = (max - start) / delta;
+        t_min = t_min.max(t0.min(t1));
+        t_max = t_max.min(t0.max(t1));
+
+        if t_min > t_max {
+            return false;
         }
     }
+
+    true
 }
 
 #[inline]
@@ -295,8 +1029,9 @@ fn bezier_samples_number(points: &[Pos2], threshold: f32) -> usize {
     ((reference_size / threshold).ceil().max(0.0) as usize).min(MAX_CURVE_SAMPLES)
 }
 
-fn draw_bezier_5(shapes: &mut Vec<Shape>, points: &[Pos2; 6], stroke: Stroke) {
-    let samples = bezier_samples_number(points, stroke.width);
+/// Samples points along the bezier curve, dense enough for the stroke width.
+fn sample_bezier_path(points: &[Pos2], width: f32) -> Vec<Pos2> {
+    let samples = bezier_samples_number(points, width);
 
     let mut path = Vec::new();
 
@@ -306,35 +1041,182 @@ fn draw_bezier_5(shapes: &mut Vec<Shape>, points: &[Pos2; 6], stroke: Stroke) {
         path.push(sample_bezier(points, t));
     }
 
-    let shape = Shape::Path(PathShape {
-        points: path,
-        closed: false,
-        fill: Color32::TRANSPARENT,
-        stroke: stroke.into(),
-    });
+    path
+}
+
+/// Returns offset of the pattern with `period` moving with `speed` at `time`.
+///
+/// Offset is wrapped to the period in `f64`, so dashes stay steady after long uptime.
+fn pattern_offset(time: f64, speed: f32, period: f32) -> f32 {
+    let offset = (time * f64::from(speed)).rem_euclid(f64::from(period));
 
-    shapes.push(shape);
+    #[allow(clippy::cast_possible_truncation)]
+    let offset = offset as f32;
+    if offset.is_finite() {
+        offset
+    } else {
+        0.0
+    }
 }
 
-fn draw_bezier_3(shapes: &mut Vec<Shape>, points: &[Pos2; 4], stroke: Stroke) {
-    let samples = bezier_samples_number(points, stroke.width);
+/// Draws the path as dashes of `length` separated by `gap`.
+/// Pattern is shifted along the path by `offset`.
+///
+/// Dashes and gaps are at least [`MIN_PATTERN_STEP`] long.
+/// Path is drawn solid if pattern is degenerate or would need more than [`MAX_WIRE_DASHES`] dashes.
+fn draw_dashed_path(
+    shapes: &mut Vec<Shape>,
+    path: &[Pos2],
+    length: f32,
+    gap: f32,
+    offset: f32,
+    stroke: WireStroke,
+) {
+    if !(length > 0.0 && gap > 0.0 && (length + gap).is_finite()) {
+        stroke.push_path(shapes, path.to_vec(), 0.0, 1.0);
+        return;
+    }
 
-    let mut path = Vec::new();
+    let length = length.max(MIN_PATTERN_STEP);
+    let gap = gap.max(MIN_PATTERN_STEP);
+    let period = length + gap;
 
-    for i in 0..samples {
-        #[allow(clippy::cast_precision_loss)]
-        let t = i as f32 / (samples - 1) as f32;
-        path.push(sample_bezier(points, t));
+    let path_length: f32 = path.windows(2).map(|s| (s[1] - s[0]).length()).sum();
+    if path_length > period * MAX_WIRE_DASHES {
+        stroke.push_path(shapes, path.to_vec(), 0.0, 1.0);
+        return;
+    }
+    let fraction = |distance: f32| {
+        if path_length > 0.0 {
+            distance / path_length
+        } else {
+            0.0
+        }
+    };
+
+    // Distance along the path where current dash starts.
+    let mut dash_start = 0.0;
+
+    let mut push_dash = |dash: &mut Vec<Pos2>, dash_start: f32, dash_end: f32| {
+        if dash.len() > 1 {
+            stroke.push_path(
+                shapes,
+                std::mem::take(dash),
+                fraction(dash_start),
+                fraction(dash_end),
+            );
+        }
+        dash.clear();
+    };
+
+    // Position within the pattern, dash is drawn while it is less than `length`.
+    let mut phase = (-offset).rem_euclid(period);
+    let mut dash = Vec::new();
+
+    // Distance along the path to the start of current segment.
+    let mut traversed = 0.0;
+
+    for segment in path.windows(2) {
+        let [a, b] = [segment[0], segment[1]];
+        let segment_length = (b - a).length();
+
+        let mut done = 0.0;
+        while done < segment_length {
+            if phase >= period {
+                phase -= period;
+            }
+
+            let in_dash = phase < length;
+            let boundary = if in_dash {
+                length - phase
+            } else {
+                period - phase
+            };
+            let step = boundary.min(segment_length - done);
+
+            if in_dash {
+                if dash.is_empty() {
+                    dash.push(a.lerp(b, done / segment_length));
+                    dash_start = traversed + done;
+                }
+                dash.push(a.lerp(b, (done + step) / segment_length));
+            }
+
+            done += step;
+            phase += step;
+
+            if in_dash && phase >= length {
+                push_dash(&mut dash, dash_start, traversed + done);
+            }
+        }
+
+        traversed += segment_length;
+    }
+
+    push_dash(&mut dash, dash_start, traversed);
+}
+
+/// Draws dots along the path `spacing` apart, starting at its first point
+/// and moving along the path with `speed` at `time`.
+///
+/// Dots are never closer than their diameter.
+/// Path is drawn solid if spacing is degenerate.
+fn draw_dotted_path(
+    shapes: &mut Vec<Shape>,
+    path: &[Pos2],
+    spacing: f32,
+    time: f64,
+    speed: f32,
+    stroke: WireStroke,
+) {
+    if !(spacing > 0.0 && spacing.is_finite()) {
+        stroke.push_path(shapes, path.to_vec(), 0.0, 1.0);
+        return;
+    }
+
+    let spacing = spacing.max(stroke.stroke.width * 2.0);
+
+    let path_length: f32 = path.windows(2).map(|s| (s[1] - s[0]).length()).sum();
+
+    // Distance along the path to the next dot.
+    let mut next = pattern_offset(time, speed, spacing);
+    let mut done = 0.0;
+
+    for segment in path.windows(2) {
+        let [a, b] = [segment[0], segment[1]];
+        let segment_length = (b - a).length();
+
+        while next <= done + segment_length {
+            let t = if segment_length > 0.0 {
+                (next - done) / segment_length
+            } else {
+                0.0
+            };
+            let color = if path_length > 0.0 {
+                stroke.color_at(next / path_length)
+            } else {
+                stroke.color_at(0.0)
+            };
+            shapes.push(Shape::circle_filled(
+                a.lerp(b, t),
+                stroke.stroke.width,
+                color,
+            ));
+            next += spacing;
+        }
+
+        done += segment_length;
     }
+}
 
-    let shape = Shape::Path(PathShape {
-        points: path,
-        closed: false,
-        fill: Color32::TRANSPARENT,
-        stroke: stroke.into(),
-    });
+fn draw_bezier_5(shapes: &mut Vec<Shape>, points: &[Pos2; 6], stroke: WireStroke) {
+    let path = sample_bezier_path(points, stroke.stroke.width);
+    stroke.draw_path(shapes, path);
+}
 
-    shapes.push(shape);
+fn draw_bezier_3(shapes: &mut Vec<Shape>, points: &[Pos2; 4], stroke: WireStroke) {
+    let path = sample_bezier_path(points, stroke.stroke.width);
+    stroke.draw_path(shapes, path);
 }
 
 #[allow(clippy::let_and_return)]
@@ -516,8 +1398,73 @@ struct AxisAlignedWire {
     turns: [(Pos2, f32); 4],
 }
 
+impl AxisAlignedWire {
+    fn mirror_x(self) -> Self {
+        AxisAlignedWire {
+            points: self.points.map(mirror_x),
+            turns: self.turns.map(|(turn, radius)| (mirror_x(turn), radius)),
+        }
+    }
+}
+
+/// Returns points and turns of the axis aligned wire.
+///
+/// Wire leaves `from` towards `from_side` and enters `to` from `to_side`.
+/// If both ends are on the same side, wire loops around them.
 #[allow(clippy::too_many_lines)]
-fn wire_axis_aligned(corner_radius: f32, frame_size: f32, from: Pos2, to: Pos2) -> AxisAlignedWire {
+fn wire_axis_aligned(
+    corner_radius: f32,
+    frame_size: f32,
+    from: Pos2,
+    to: Pos2,
+    from_side: PinSide,
+    to_side: PinSide,
+) -> AxisAlignedWire {
+    match (from_side, to_side) {
+        (PinSide::Right, PinSide::Left) => {}
+        (PinSide::Left, _) => {
+            return wire_axis_aligned(
+                corner_radius,
+                frame_size,
+                mirror_x(from),
+                mirror_x(to),
+                PinSide::Right,
+                to_side.opposite(),
+            )
+            .mirror_x();
+        }
+        (PinSide::Right, PinSide::Right) => {
+            // Both ends face right, wire loops around the rightmost one.
+            let x = from.x.max(to.x) + frame_size;
+            let mid = pos2(x, (from.y + to.y) / 2.0);
+            let sign: f32 = if from.y < to.y { 1.0 } else { -1.0 };
+
+            let from_turn_radius = f32::abs(x - from.x)
+                .min(f32::abs(mid.y - from.y))
+                .min(corner_radius);
+
+            let to_turn_radius = f32::abs(x - to.x)
+                .min(f32::abs(to.y - mid.y))
+                .min(corner_radius);
+
+            return AxisAlignedWire {
+                points: [from, mid, mid, mid, to],
+                turns: [
+                    (
+                        pos2(x - from_turn_radius, sign.mul_add(from_turn_radius, from.y)),
+                        from_turn_radius,
+                    ),
+                    (mid, 0.0),
+                    (mid, 0.0),
+                    (
+                        pos2(x - to_turn_radius, sign.mul_add(-to_turn_radius, to.y)),
+                        to_turn_radius,
+                    ),
+                ],
+            };
+        }
+    }
+
     if from.x + frame_size <= to.x - frame_size {
         let mid = pos2((from.x + to.x) / 2.0, (from.y + to.y) / 2.0);
 
@@ -645,15 +1592,18 @@ fn wire_axis_aligned(corner_radius: f32, frame_size: f32, from: Pos2, to: Pos2)
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn hit_axis_aligned(
     pos: Pos2,
     corner_radius: f32,
     frame_size: f32,
     from: Pos2,
     to: Pos2,
+    from_side: PinSide,
+    to_side: PinSide,
     threshold: f32,
 ) -> bool {
-    let wire = wire_axis_aligned(corner_radius, frame_size, from, to);
+    let wire = wire_axis_aligned(corner_radius, frame_size, from, to, from_side, to_side);
 
     let aabb = Rect::from_points(&wire.points);
     let aabb_e = aabb.expand(threshold);
@@ -708,6 +1658,8 @@ fn turn_samples_number(radius: f32, threshold: f32) -> usize {
     ((reference_size / threshold).ceil().max(0.0) as usize).min(MAX_CURVE_SAMPLES / 4)
 }
 
+/// Draws axis-aligned wire and returns its direction at `to` if it is visible.
+#[allow(clippy::too_many_arguments)]
 fn draw_axis_aligned(
     ui: &Ui,
     shapes: &mut Vec<Shape>,
@@ -715,16 +1667,49 @@ fn draw_axis_aligned(
     frame_size: f32,
     from: Pos2,
     to: Pos2,
-    stroke: Stroke,
-) {
-    let aabb = Rect::from_two_pos(from, to);
+    from_side: PinSide,
+    to_side: PinSide,
+    stroke: WireStroke,
+    vertical: bool,
+) -> Option<Vec2> {
+    // Vertical wire is built in transposed space and its path is transposed back.
+    let (from, to) = if vertical {
+        (transpose(from), transpose(to))
+    } else {
+        (from, to)
+    };
 
-    if !ui.is_rect_visible(aabb) {
-        return;
+    let wire = wire_axis_aligned(corner_radius, frame_size, from, to, from_side, to_side);
+
+    // Looping wire goes outside of the rect between its ends.
+    let aabb = Rect::from_points(&wire.points);
+    let screen_aabb = if vertical {
+        Rect::from_two_pos(transpose(aabb.min), transpose(aabb.max))
+    } else {
+        aabb
+    };
+
+    if !ui.is_rect_visible(screen_aabb) {
+        return None;
+    }
+
+    let mut path = axis_aligned_path(&wire, stroke.stroke.width);
+
+    if vertical {
+        for point in &mut path {
+            *point = transpose(*point);
+        }
     }
 
-    let wire = wire_axis_aligned(corner_radius, frame_size, from, to);
+    let end_direction = end_tangent(&path);
 
+    stroke.draw_path(shapes, path);
+
+    end_direction
+}
+
+/// Samples points along the axis-aligned wire, rounding its corners.
+fn axis_aligned_path(wire: &AxisAlignedWire, width: f32) -> Vec<Pos2> {
     let mut path = Vec::new();
 
     path.push(wire.points[0]);
@@ -736,7 +1721,7 @@ fn draw_axis_aligned(
             continue;
         }
 
-        let samples = turn_samples_number(radius, stroke.width);
+        let samples = turn_samples_number(radius, width);
 
         for j in 1..samples {
             #[allow(clippy::cast_precision_loss)]
@@ -760,12 +1745,5 @@ fn draw_axis_aligned(
         path.push(wire.points[i + 1]);
     }
 
-    let shape = Shape::Path(PathShape {
-        points: path,
-        closed: false,
-        fill: Color32::TRANSPARENT,
-        stroke: stroke.into(),
-    });
-
-    shapes.push(shape);
+    path
 }
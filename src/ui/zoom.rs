@@ -4,7 +4,7 @@ use egui::{
     FontId, Frame, Margin, Rounding, Stroke, Style, Vec2, Visuals,
 };
 
-use super::{SelectionStyle, WireStyle};
+use super::{BreakpointStyle, ProgressStyle, SelectionStyle, WireEndMarker, WireStyle};
 
 pub trait Zoom {
     #[inline(always)]
@@ -206,14 +206,54 @@ impl Zoom for WireStyle {
     #[inline(always)]
     fn zoom(&mut self, zoom: f32) {
         match self {
-            WireStyle::Bezier3 | WireStyle::Bezier5 => {}
-            WireStyle::AxisAligned { corner_radius } => {
+            // Sag is relative to wire length which is already zoomed.
+            WireStyle::Bezier3
+            | WireStyle::Bezier5
+            | WireStyle::Bezier5Vertical
+            | WireStyle::Sagging { .. } => {}
+            WireStyle::AxisAligned { corner_radius }
+            | WireStyle::AxisAlignedVertical { corner_radius } => {
                 corner_radius.zoom(zoom);
             }
+            // Speed is zoomed too, so dashes keep pace relative to their length.
+            WireStyle::Dashed { length, gap, speed } => {
+                length.zoom(zoom);
+                gap.zoom(zoom);
+                speed.zoom(zoom);
+            }
+            WireStyle::Dotted { spacing } => {
+                spacing.zoom(zoom);
+            }
+        }
+    }
+}
+
+impl Zoom for WireEndMarker {
+    #[inline(always)]
+    fn zoom(&mut self, zoom: f32) {
+        match self {
+            WireEndMarker::None => {}
+            WireEndMarker::Arrow { size } => size.zoom(zoom),
+            WireEndMarker::Dot { radius } => radius.zoom(zoom),
         }
     }
 }
 
+impl Zoom for ProgressStyle {
+    #[inline(always)]
+    fn zoom(&mut self, zoom: f32) {
+        self.height *= zoom;
+    }
+}
+
+impl Zoom for BreakpointStyle {
+    #[inline(always)]
+    fn zoom(&mut self, zoom: f32) {
+        self.radius *= zoom;
+        self.stroke.zoom(zoom);
+    }
+}
+
 impl Zoom for SelectionStyle {
     #[inline(always)]
     fn zoom(&mut self, zoom: f32) {